@@ -1,17 +1,135 @@
 use crate::builder::*;
 use crate::error::*;
-pub use crate::packet::{ConnectComm, CoptFrame, DtData, Parameter, PduType, TpduSize};
-use bytes::{Buf, BufMut, BytesMut};
+pub use crate::packet::{
+    ConnectComm, CoptFrame, DisconnectConfirm, DisconnectReason, DisconnectRequest, DtData,
+    ErrorTpdu, Parameter, PduType, RejectCause, TpduSize,
+};
+use bytes::{BufMut, BytesMut};
 use std::fmt::Debug;
 use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktEncoder, TpktFrame};
 
 pub mod builder;
 pub mod error;
 mod packet;
 
+/// the length of the tpkt header
+const TPKT_HEADER_LEN: usize = 4;
+/// the length of the header of a dt data tpdu
+const DT_HEADER_LEN: usize = 3;
+/// the largest data unit reassembled from dt data
+/// tpdus
+const MAX_DATA_UNIT_LEN: usize = u16::MAX as usize;
+
 #[derive(Default)]
 pub struct CoptEncoder<E>(pub E);
-pub struct CoptDecoder<D>(pub D);
+
+/// Decodes a copt frame. The dt data tpdus of a
+/// data unit before the last one, each one ending
+/// with `src` as framed by tpkt, are kept until the
+/// last one, whose frame carries the payload of the
+/// whole data unit.
+pub struct CoptDecoder<D> {
+    inner: D,
+    /// the data of the tpdus received before the
+    /// last one of the data unit
+    segments: BytesMut,
+}
+
+impl<D> CoptDecoder<D> {
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            segments: BytesMut::new(),
+        }
+    }
+}
+
+impl<D: Default> Default for CoptDecoder<D> {
+    fn default() -> Self {
+        Self::new(D::default())
+    }
+}
+
+/// Encodes copt frames in tpkt packets, the payload
+/// of a dt data tpdu longer than the tpdu size split
+/// into tpdus numbered from 0, each one in its own
+/// packet, the last one with the end of the data
+/// unit.
+pub struct SegmentEncoder<E> {
+    encoder: TpktEncoder<CoptEncoder<E>>,
+    tpdu_size: TpduSize,
+}
+
+impl<E> SegmentEncoder<E> {
+    pub fn new(encoder: E, tpdu_size: TpduSize) -> Self {
+        Self {
+            encoder: TpktEncoder(CoptEncoder(encoder)),
+            tpdu_size,
+        }
+    }
+
+    pub fn tpdu_size(&self) -> TpduSize {
+        self.tpdu_size
+    }
+
+    /// set the tpdu size negotiated by the connection
+    pub fn set_tpdu_size(&mut self, tpdu_size: TpduSize) {
+        self.tpdu_size = tpdu_size;
+    }
+}
+
+impl<E: Default> Default for SegmentEncoder<E> {
+    /// with the largest tpdu size
+    fn default() -> Self {
+        Self::new(E::default(), TpduSize::L8192)
+    }
+}
+
+impl<F: Debug + Eq + PartialEq, E: Encoder<F>> Encoder<TpktFrame<CoptFrame<F>>>
+    for SegmentEncoder<E>
+where
+    <E as Encoder<F>>::Error: ToCoptError + Send + Sync + 'static,
+{
+    type Error = tpkt::Error;
+
+    fn encode(
+        &mut self,
+        item: TpktFrame<CoptFrame<F>>,
+        dst: &mut BytesMut,
+    ) -> std::result::Result<(), Self::Error> {
+        let mut framed = BytesMut::new();
+        self.encoder.encode(item, &mut framed)?;
+        let tpdu_size = self.tpdu_size.pdu_ref() as usize;
+        if framed.get(TPKT_HEADER_LEN + 1) != Some(&0xf0)
+            || framed.len() - TPKT_HEADER_LEN <= tpdu_size
+        {
+            dst.extend_from_slice(&framed);
+            return Ok(());
+        }
+        let version = framed[0];
+        let data = framed.split_off(TPKT_HEADER_LEN + DT_HEADER_LEN);
+        let count = data.len().div_ceil(tpdu_size - DT_HEADER_LEN);
+        if count > 0x80 {
+            return Err(tpkt::Error::Error(format!(
+                "a data unit of {} bytes exceeds 128 tpdus of {} bytes",
+                data.len(),
+                tpdu_size
+            )));
+        }
+        for (number, segment) in data.chunks(tpdu_size - DT_HEADER_LEN).enumerate() {
+            let last_data_unit = number + 1 == count;
+            dst.put_u8(version);
+            dst.put_u8(0);
+            dst.put_u16((TPKT_HEADER_LEN + DT_HEADER_LEN + segment.len()) as u16);
+            dst.put_u8(DT_HEADER_LEN as u8 - 1);
+            dst.put_u8(0xf0);
+            dst.put_u8(number as u8 | if last_data_unit { 0b1000_0000 } else { 0 });
+            dst.extend_from_slice(segment);
+        }
+        Ok(())
+    }
+}
 
 impl<F: Debug + Eq + PartialEq, E: Encoder<F>> Encoder<CoptFrame<F>> for CoptEncoder<E>
 where
@@ -39,10 +157,25 @@ where
             PduType::DtData(conn) => {
                 dst.put_u8(0xf0);
                 let merge =
-                    conn.tpdu_number >> 1 | if conn.last_data_unit { 0b1000_0000 } else { 0 };
+                    conn.tpdu_number & 0b0111_1111 | if conn.last_data_unit { 0b1000_0000 } else { 0 };
                 dst.put_u8(merge);
                 Ok(self.0.encode(conn.payload, dst)?)
             }
+            PduType::DisconnectRequest(conn) => {
+                dst.put_u8(0x80);
+                conn.encode(dst);
+                Ok(())
+            }
+            PduType::DisconnectConfirm(conn) => {
+                dst.put_u8(0xc0);
+                conn.encode(dst);
+                Ok(())
+            }
+            PduType::Error(conn) => {
+                dst.put_u8(0x70);
+                conn.encode(dst);
+                Ok(())
+            }
         }
     }
 }
@@ -62,7 +195,10 @@ where
             return Ok(None)
         };
         let length = *length as usize + 1;
-        if src.len() < length || length < 2 {
+        if length < 2 {
+            return Err(Error::Error("length indicator without pdu type".to_string()));
+        }
+        if src.len() < length {
             return Ok(None);
         };
         match *pdu_type {
@@ -79,18 +215,64 @@ where
                     pdu_type: PduType::ConnectConfirm(ConnectComm::decode(&mut src)?),
                 }))
             }
+            0x80 => {
+                let mut src = src.split_to(length).split_off(2);
+                Ok(Some(CoptFrame {
+                    pdu_type: PduType::DisconnectRequest(DisconnectRequest::decode(&mut src)?),
+                }))
+            }
+            0xc0 => {
+                let mut src = src.split_to(length).split_off(2);
+                Ok(Some(CoptFrame {
+                    pdu_type: PduType::DisconnectConfirm(DisconnectConfirm::decode(&mut src)?),
+                }))
+            }
+            0x70 => {
+                let mut src = src.split_to(length).split_off(2);
+                Ok(Some(CoptFrame {
+                    pdu_type: PduType::Error(ErrorTpdu::decode(&mut src)?),
+                }))
+            }
             0xf0 => {
-                let mut sub_src = src.clone().split_off(length);
-                let pre_length = sub_src.len();
-                let Some(f) = self.0.decode(&mut sub_src)? else {
-                    return Err(Error::Error("decode fail".to_string()));
-
-                };
-                let sub_length = pre_length - sub_src.len();
-                let mut src = src.split_to(length + sub_length).split_off(2);
-                let merge = src.get_u8();
+                if length < DT_HEADER_LEN {
+                    return Err(Error::Error("dt data without tpdu number".to_string()));
+                }
+                let merge = src[2];
                 let tpdu_number = merge & 0b0111_1111;
                 let last_data_unit = merge & 0b1000_0000 > 0;
+                if !last_data_unit || !self.segments.is_empty() {
+                    let data = src.split().split_off(length);
+                    if self.segments.len() + data.len() > MAX_DATA_UNIT_LEN {
+                        self.segments.clear();
+                        return Err(Error::Error(format!(
+                            "data unit longer than {} bytes",
+                            MAX_DATA_UNIT_LEN
+                        )));
+                    }
+                    self.segments.extend_from_slice(&data);
+                    if !last_data_unit {
+                        return Ok(None);
+                    }
+                    let mut data_unit = self.segments.split();
+                    let Some(f) = self.inner.decode(&mut data_unit)? else {
+                        return Err(Error::Error("incomplete data unit".to_string()));
+                    };
+                    return Ok(Some(CoptFrame {
+                        pdu_type: PduType::DtData(DtData {
+                            tpdu_number,
+                            last_data_unit,
+                            payload: f,
+                        }),
+                    }));
+                }
+                // the payload shares the buffer, whose header is dropped once
+                // it is decoded
+                let mut payload = src.split_off(length);
+                let Some(f) = self.inner.decode(&mut payload)? else {
+                    src.unsplit(payload);
+                    return Err(Error::Error("decode fail".to_string()));
+                };
+                *src = payload;
                 Ok(Some(CoptFrame {
                     pdu_type: PduType::DtData(DtData {
                         tpdu_number,
@@ -3,8 +3,11 @@ use crate::{
     DtDataBuilder
 };
 use bytes::{Buf, BufMut, BytesMut};
-use num_enum::{IntoPrimitive, TryFromPrimitive};
-use std::fmt::Debug;
+use num_enum::{
+    FromPrimitive, IntoPrimitive,
+    TryFromPrimitive
+};
+use std::fmt::{self, Debug};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct CoptFrame<F: Debug + Eq + PartialEq> {
@@ -23,6 +26,51 @@ impl<F: Debug + Eq + PartialEq> CoptFrame<F> {
         ConnectBuilder::<F>::default()
     }
 
+    pub fn disconnect_request(
+        destination_ref: [u8; 2],
+        source_ref: [u8; 2],
+        reason: DisconnectReason
+    ) -> Self {
+        Self {
+            pdu_type: PduType::DisconnectRequest(
+                DisconnectRequest {
+                    destination_ref,
+                    source_ref,
+                    reason,
+                    additional_info: Vec::new()
+                }
+            )
+        }
+    }
+
+    pub fn disconnect_confirm(
+        destination_ref: [u8; 2],
+        source_ref: [u8; 2]
+    ) -> Self {
+        Self {
+            pdu_type: PduType::DisconnectConfirm(
+                DisconnectConfirm {
+                    destination_ref,
+                    source_ref
+                }
+            )
+        }
+    }
+
+    pub fn error(
+        destination_ref: [u8; 2],
+        cause: RejectCause,
+        invalid_tpdu: Vec<u8>
+    ) -> Self {
+        Self {
+            pdu_type: PduType::Error(ErrorTpdu {
+                destination_ref,
+                cause,
+                invalid_tpdu
+            })
+        }
+    }
+
     pub fn length(&self) -> u8 {
         self.pdu_type.length()
     }
@@ -35,7 +83,13 @@ pub enum PduType<F: Debug + Eq + PartialEq> {
     /// 0x0d
     ConnectConfirm(ConnectComm),
     /// 0x0f
-    DtData(DtData<F>)
+    DtData(DtData<F>),
+    /// 0x08
+    DisconnectRequest(DisconnectRequest),
+    /// 0x0c
+    DisconnectConfirm(DisconnectConfirm),
+    /// 0x07
+    Error(ErrorTpdu)
 }
 
 impl<F: Debug + Eq + PartialEq> PduType<F> {
@@ -47,7 +101,12 @@ impl<F: Debug + Eq + PartialEq> PduType<F> {
             PduType::ConnectConfirm(conn) => {
                 conn.length()
             },
-            PduType::DtData(_) => 2
+            PduType::DtData(_) => 2,
+            PduType::DisconnectRequest(conn) => {
+                conn.length()
+            },
+            PduType::DisconnectConfirm(_) => 5,
+            PduType::Error(conn) => conn.length()
         }
     }
 }
@@ -72,6 +131,327 @@ impl<F: Debug + Eq + PartialEq> DtData<F> {
     }
 }
 
+/// https://datatracker.ietf.org/doc/html/rfc905 13.5
+#[derive(Debug, Eq, PartialEq)]
+pub struct DisconnectRequest {
+    pub destination_ref: [u8; 2],
+    pub source_ref:      [u8; 2],
+    pub reason:          DisconnectReason,
+    /// the additional information parameter,
+    /// 0xe0
+    pub additional_info: Vec<u8>
+}
+
+impl DisconnectRequest {
+    pub fn length(&self) -> u8 {
+        6 + if self.additional_info.is_empty() {
+            0
+        } else {
+            2 + self.additional_info.len() as u8
+        }
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut
+    ) -> Result<Self> {
+        if src.len() < 5 {
+            return Err(Error::Error(
+                "data not enough".to_string()
+            ));
+        }
+        let destination_ref =
+            [src.get_u8(), src.get_u8()];
+        let source_ref =
+            [src.get_u8(), src.get_u8()];
+        let reason = src.get_u8().into();
+        let mut additional_info = Vec::new();
+        // the parameters other than the
+        // additional information are ignored
+        while let (Some(code), Some(length)) =
+            (src.first(), src.get(1))
+        {
+            let (code, length) =
+                (*code, *length as usize + 2);
+            if src.len() < length {
+                return Err(Error::Error(
+                    "data not enough".to_string()
+                ));
+            }
+            let data = src.split_to(length);
+            if code == 0xe0 {
+                additional_info =
+                    data[2..].to_vec();
+            }
+        }
+        Ok(Self {
+            destination_ref,
+            source_ref,
+            reason,
+            additional_info
+        })
+    }
+
+    pub(crate) fn encode(
+        &self,
+        dst: &mut BytesMut
+    ) {
+        dst.put_slice(&self.destination_ref);
+        dst.put_slice(&self.source_ref);
+        dst.put_u8(self.reason.into());
+        if !self.additional_info.is_empty() {
+            dst.put_u8(0xe0);
+            dst.put_u8(
+                self.additional_info.len() as u8
+            );
+            dst.put_slice(&self.additional_info);
+        }
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc905 13.5.3 e)
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    FromPrimitive,
+    IntoPrimitive,
+)]
+#[repr(u8)]
+pub enum DisconnectReason {
+    NotSpecified       = 0x00,
+    CongestionAtTsap   = 0x01,
+    /// the tsap of the connect request is not
+    /// reachable
+    NotAttachedToTsap  = 0x02,
+    AddressUnknown     = 0x03,
+    Normal             = 0x80,
+    RemoteCongestion   = 0x81,
+    NegotiationFailed  = 0x82,
+    DuplicateSourceRef = 0x83,
+    MismatchedRefs     = 0x84,
+    ProtocolError      = 0x85,
+    ReferenceOverflow  = 0x87,
+    ConnectionRefused  = 0x88,
+    InvalidLength      = 0x8a,
+    #[num_enum(catch_all)]
+    NotSupport(u8)
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>
+    ) -> fmt::Result {
+        let text = match self {
+            Self::NotSpecified => {
+                "reason not specified"
+            },
+            Self::CongestionAtTsap => {
+                "congestion at tsap"
+            },
+            Self::NotAttachedToTsap => {
+                "tsap not reachable"
+            },
+            Self::AddressUnknown => {
+                "address unknown"
+            },
+            Self::Normal => "normal disconnect",
+            Self::RemoteCongestion => {
+                "remote congestion at connect \
+                 request time"
+            },
+            Self::NegotiationFailed => {
+                "connection negotiation failed"
+            },
+            Self::DuplicateSourceRef => {
+                "duplicate source reference"
+            },
+            Self::MismatchedRefs => {
+                "mismatched references"
+            },
+            Self::ProtocolError => {
+                "protocol error"
+            },
+            Self::ReferenceOverflow => {
+                "reference overflow"
+            },
+            Self::ConnectionRefused => {
+                "connection request refused"
+            },
+            Self::InvalidLength => {
+                "header or parameter length \
+                 invalid"
+            },
+            Self::NotSupport(x) => {
+                return write!(
+                    f,
+                    "unknown reason {:#04x}",
+                    x
+                );
+            }
+        };
+        f.write_str(text)
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc905 13.6
+#[derive(Debug, Eq, PartialEq)]
+pub struct DisconnectConfirm {
+    pub destination_ref: [u8; 2],
+    pub source_ref:      [u8; 2]
+}
+
+impl DisconnectConfirm {
+    pub(crate) fn decode(
+        src: &mut BytesMut
+    ) -> Result<Self> {
+        if src.len() < 4 {
+            return Err(Error::Error(
+                "data not enough".to_string()
+            ));
+        }
+        Ok(Self {
+            destination_ref: [
+                src.get_u8(),
+                src.get_u8()
+            ],
+            source_ref:      [
+                src.get_u8(),
+                src.get_u8()
+            ]
+        })
+    }
+
+    pub(crate) fn encode(
+        &self,
+        dst: &mut BytesMut
+    ) {
+        dst.put_slice(&self.destination_ref);
+        dst.put_slice(&self.source_ref);
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc905 13.12
+#[derive(Debug, Eq, PartialEq)]
+pub struct ErrorTpdu {
+    pub destination_ref: [u8; 2],
+    pub cause:           RejectCause,
+    /// the header of the rejected tpdu, the
+    /// parameter 0xc1
+    pub invalid_tpdu:    Vec<u8>
+}
+
+impl ErrorTpdu {
+    pub fn length(&self) -> u8 {
+        4 + if self.invalid_tpdu.is_empty() {
+            0
+        } else {
+            2 + self.invalid_tpdu.len() as u8
+        }
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut
+    ) -> Result<Self> {
+        if src.len() < 3 {
+            return Err(Error::Error(
+                "data not enough".to_string()
+            ));
+        }
+        let destination_ref =
+            [src.get_u8(), src.get_u8()];
+        let cause = src.get_u8().into();
+        let mut invalid_tpdu = Vec::new();
+        while let (Some(code), Some(length)) =
+            (src.first(), src.get(1))
+        {
+            let (code, length) =
+                (*code, *length as usize + 2);
+            if src.len() < length {
+                return Err(Error::Error(
+                    "data not enough".to_string()
+                ));
+            }
+            let data = src.split_to(length);
+            if code == 0xc1 {
+                invalid_tpdu = data[2..].to_vec();
+            }
+        }
+        Ok(Self {
+            destination_ref,
+            cause,
+            invalid_tpdu
+        })
+    }
+
+    pub(crate) fn encode(
+        &self,
+        dst: &mut BytesMut
+    ) {
+        dst.put_slice(&self.destination_ref);
+        dst.put_u8(self.cause.into());
+        if !self.invalid_tpdu.is_empty() {
+            dst.put_u8(0xc1);
+            dst.put_u8(
+                self.invalid_tpdu.len() as u8
+            );
+            dst.put_slice(&self.invalid_tpdu);
+        }
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc905 13.12.3 c)
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    FromPrimitive,
+    IntoPrimitive,
+)]
+#[repr(u8)]
+pub enum RejectCause {
+    NotSpecified         = 0x00,
+    InvalidParameterCode = 0x01,
+    InvalidTpduType      = 0x02,
+    InvalidParameterValue = 0x03,
+    #[num_enum(catch_all)]
+    NotSupport(u8)
+}
+
+impl fmt::Display for RejectCause {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>
+    ) -> fmt::Result {
+        let text = match self {
+            Self::NotSpecified => {
+                "reason not specified"
+            },
+            Self::InvalidParameterCode => {
+                "invalid parameter code"
+            },
+            Self::InvalidTpduType => {
+                "invalid tpdu type"
+            },
+            Self::InvalidParameterValue => {
+                "invalid parameter value"
+            },
+            Self::NotSupport(x) => {
+                return write!(
+                    f,
+                    "unknown cause {:#04x}",
+                    x
+                );
+            }
+        };
+        f.write_str(text)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct ConnectComm {
     pub destination_ref:          [u8; 2],
@@ -135,12 +515,12 @@ impl ConnectComm {
         dst.put_slice(self.source_ref.as_ref());
 
         let merge = self.class << 4
-            & if self.extended_formats {
+            | if self.extended_formats {
                 2
             } else {
                 0
             }
-            & if self.no_explicit_flow_control {
+            | if self.no_explicit_flow_control {
                 1
             } else {
                 0
@@ -153,7 +533,7 @@ impl ConnectComm {
 }
 
 /// https://datatracker.ietf.org/doc/html/rfc905 13.3.4
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Parameter {
     /// 0xc0
     ///            0000 1101  8192 octets (not
@@ -169,7 +549,21 @@ pub enum Parameter {
     /// 0xc1    todo?
     SrcTsap(Vec<u8>),
     /// 0xc2    todo?
-    DstTsap(Vec<u8>)
+    DstTsap(Vec<u8>),
+    /// 0xc3, the checksum of the tpdu, used
+    /// in class 4 only
+    Checksum(u16),
+    /// 0xc4, the version number of the protocol
+    Version(u8),
+    /// 0xc6, the additional option selection
+    AdditionalOptions(u8),
+    /// 0xc7, the alternative protocol classes
+    /// proposed, a class in the high nibble of
+    /// each byte
+    AlternativeClasses(Vec<u8>),
+    /// a parameter not modelled, such as a vendor
+    /// one, kept to be encoded as received
+    Unknown { code: u8, data: Vec<u8> }
 }
 
 #[derive(
@@ -228,6 +622,37 @@ impl Parameter {
             Parameter::DstTsap(data) => {
                 2 + data.len() as u8
             },
+            Parameter::Checksum(_) => 4,
+            Parameter::Version(_)
+            | Parameter::AdditionalOptions(_) => {
+                3
+            },
+            Parameter::AlternativeClasses(
+                data
+            )
+            | Parameter::Unknown {
+                data, ..
+            } => 2 + data.len() as u8
+        }
+    }
+
+    /// the code of the parameter
+    pub fn code(&self) -> u8 {
+        match self {
+            Parameter::TpduSize(_) => 0xc0,
+            Parameter::SrcTsap(_) => 0xc1,
+            Parameter::DstTsap(_) => 0xc2,
+            Parameter::Checksum(_) => 0xc3,
+            Parameter::Version(_) => 0xc4,
+            Parameter::AdditionalOptions(_) => {
+                0xc6
+            },
+            Parameter::AlternativeClasses(_) => {
+                0xc7
+            },
+            Parameter::Unknown {
+                code, ..
+            } => *code
         }
     }
 
@@ -240,7 +665,7 @@ impl Parameter {
         let (Some(ty), Some(length)) = (dst.get(0), dst.get(1)) else {
             return Err(Error::Error("data not enough".to_string()));
         };
-        let length = (length + 2) as usize;
+        let length = *length as usize + 2;
         let ty = *ty;
         if dst.len() < length {
             return Err(Error::Error(
@@ -249,6 +674,20 @@ impl Parameter {
         }
         let mut data =
             dst.split_to(length).split_off(2);
+        let fixed_len = match ty {
+            0xc0 | 0xc4 | 0xc6 => Some(1),
+            0xc3 => Some(2),
+            _ => None
+        };
+        if fixed_len
+            .is_some_and(|x| x != data.len())
+        {
+            return Err(Error::Error(format!(
+                "parameter {:#04x} of {} bytes",
+                ty,
+                data.len()
+            )));
+        }
         match ty {
             0xc0 => {
                 let size = data.get_u8();
@@ -262,15 +701,26 @@ impl Parameter {
             0xc2 => Ok(Some(Self::DstTsap(
                 data.to_vec()
             ))),
-            _ => {
-                return Err(Error::Error(
-                    format!(
-                        "not support parameter: \
-                         {}",
-                        ty
-                    )
-                ));
-            }
+            0xc3 => Ok(Some(Self::Checksum(
+                data.get_u16()
+            ))),
+            0xc4 => Ok(Some(Self::Version(
+                data.get_u8()
+            ))),
+            0xc6 => {
+                Ok(Some(Self::AdditionalOptions(
+                    data.get_u8()
+                )))
+            },
+            0xc7 => Ok(Some(
+                Self::AlternativeClasses(
+                    data.to_vec()
+                )
+            )),
+            code => Ok(Some(Self::Unknown {
+                code,
+                data: data.to_vec()
+            }))
         }
     }
 
@@ -294,6 +744,31 @@ impl Parameter {
                 dst.extend_from_slice(
                     data.as_ref()
                 )
+            },
+            Parameter::Checksum(checksum) => {
+                dst.put_u8(0xc3);
+                dst.put_u8(2);
+                dst.put_u16(*checksum)
+            },
+            Parameter::Version(value)
+            | Parameter::AdditionalOptions(
+                value
+            ) => {
+                dst.put_u8(self.code());
+                dst.put_u8(1);
+                dst.put_u8(*value)
+            },
+            Parameter::AlternativeClasses(
+                data
+            )
+            | Parameter::Unknown {
+                data, ..
+            } => {
+                dst.put_u8(self.code());
+                dst.put_u8(data.len() as u8);
+                dst.extend_from_slice(
+                    data.as_ref()
+                )
             }
         }
     }
@@ -26,13 +26,13 @@ async fn main() -> Result<()> {
             slot: 1,
         },
     );
-    let mut client =
+    let client =
         S7Client::connect(options).await?;
 
-    test_db_write(&mut client).await?;
-    test_process_input(&mut client).await?;
-    test_process_output(&mut client).await?;
-    test_process_merker(&mut client).await?;
+    test_db_write(&client).await?;
+    test_process_input(&client).await?;
+    test_process_output(&client).await?;
+    test_process_merker(&client).await?;
 
     info!("all test pass");
 
@@ -40,7 +40,7 @@ async fn main() -> Result<()> {
 }
 
 async fn test_db_write(
-    client: &mut S7Client,
+    client: &S7Client,
 ) -> Result<()> {
     /*
      * write addr=0 bit6 = 1
@@ -99,7 +99,7 @@ async fn test_db_write(
 }
 
 async fn test_process_input(
-    client: &mut S7Client,
+    client: &S7Client,
 ) -> Result<()> {
     /*
      * write addr=100 bit4 = 1
@@ -152,7 +152,7 @@ async fn test_process_input(
 }
 
 async fn test_process_merker(
-    client: &mut S7Client,
+    client: &S7Client,
 ) -> Result<()> {
     /*
      * write addr=0 bit4 = 1
@@ -206,7 +206,7 @@ async fn test_process_merker(
 }
 
 async fn test_process_output(
-    client: &mut S7Client,
+    client: &S7Client,
 ) -> Result<()> {
     /*
     let rs = client
@@ -28,7 +28,8 @@ impl S7ReadBuilder {
         self
     }
 
-    pub fn build(self) -> Result<BytesMut> {
+    /// the s7 frame, without tpkt and copt
+    pub fn build_frame(self) -> s7_comm::Frame {
         let mut read_builder =
             s7_comm::Frame::job_read_var(
                 self.pdu_ref,
@@ -38,9 +39,13 @@ impl S7ReadBuilder {
             read_builder =
                 read_builder.add_item(item);
         }
+        read_builder.build()
+    }
+
+    pub fn build(self) -> Result<BytesMut> {
         let frame = TpktFrame::new(
             CoptFrame::builder_of_dt_data(
-                read_builder.build(),
+                self.build_frame(),
             )
             .build(0, true),
         );
@@ -21,7 +21,7 @@ impl S7WriteBuilder {
         self
     }
 
-    fn add_item(
+    pub fn add_item(
         mut self,
         item: (ItemRequest, DataItemVal),
     ) -> Self {
@@ -68,7 +68,8 @@ impl S7WriteBuilder {
         self.add_item((req, data_val))
     }
 
-    pub fn build(self) -> Result<BytesMut> {
+    /// the s7 frame, without tpkt and copt
+    pub fn build_frame(self) -> s7_comm::Frame {
         let mut write_builder =
             s7_comm::Frame::job_write_var(
                 self.pdu_ref,
@@ -78,9 +79,13 @@ impl S7WriteBuilder {
             write_builder =
                 write_builder.add_item(item);
         }
+        write_builder.build()
+    }
+
+    pub fn build(self) -> Result<BytesMut> {
         let frame = TpktFrame::new(
             CoptFrame::builder_of_dt_data(
-                write_builder.build(),
+                self.build_frame(),
             )
             .build(0, true),
         );
@@ -1,146 +1,266 @@
 use std::{
-    net::{IpAddr, SocketAddr},
-    time::Duration,
+    net::IpAddr, sync::Arc, time::Duration,
 };
 
 use crate::{
-    build_copt_connect_request, build_s7_read,
-    build_s7_setup, build_s7_write, error::*,
+    build_s7_read, build_s7_write, error::*,
 };
-use bytes::BytesMut;
-use copt::{
-    CoptDecoder, CoptFrame, Parameter, PduType,
-    TpduSize,
-};
-use log::debug;
+use copt::TpduSize;
 use s7_comm::{
     AckData, DataItemVal, DataItemWriteResponse,
-    Frame, S7CommDecoder,
-};
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
-    time::timeout,
+    Frame, ReturnCode,
 };
-use tokio_util::codec::Decoder;
-use tpkt::{TpktDecoder, TpktFrame};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 
+mod address;
+mod alarm;
+mod block;
+mod clock;
+mod connection;
+mod control;
+mod cyclic;
+mod diagnostic;
+mod items;
 mod param;
+mod plan;
+mod poller;
+mod reconnect;
 mod request_param;
+mod security;
+mod subscription;
+mod szl;
+mod tag;
+mod user_data;
+mod value;
 
+pub use address::*;
+pub use alarm::*;
+pub use block::*;
+pub use cyclic::*;
+pub use diagnostic::*;
 pub use param::*;
+pub use poller::*;
+pub use reconnect::*;
 pub use request_param::*;
+pub use szl::*;
+pub use tag::*;
+pub use value::*;
+
+use connection::{
+    serve, Connection, Disconnect, Request,
+    Subscriber,
+};
+use plan::{
+    data_len, merge_read, merge_write, plan_read,
+    plan_write,
+};
 
+/// A handle of a plc connection. Clones share the
+/// connection, and their jobs are pipelined up to
+/// the negotiated `max_amq_calling`.
+#[derive(Clone)]
 pub struct S7Client {
-    options: Options,
-    connect: TcpStream,
+    options: watch::Receiver<Options>,
+    requests: mpsc::Sender<Request>,
+    disconnects: mpsc::Sender<Disconnect>,
+    state: watch::Receiver<ConnectionState>,
+    /// the id of the alarm subscription of the
+    /// connection
+    alarms: Arc<Mutex<Option<u64>>>,
 }
 
 impl S7Client {
     pub async fn connect(
         options: Options,
     ) -> Result<Self> {
-        let connect =
-            tokio::net::TcpStream::connect(
-                SocketAddr::new(
-                    options.address,
-                    options.port,
-                ),
-            )
-            .await?;
-        let mut client =
-            Self { options, connect };
-        client.copt_connect().await?;
-        client.s7_setup().await?;
-        Ok(client)
+        let connection =
+            Connection::connect(&options).await?;
+        let negotiated =
+            connection.options().clone();
+        let (requests, receiver) = mpsc::channel(
+            negotiated.max_amq_calling.max(1)
+                as usize,
+        );
+        let (disconnects, disconnect_receiver) =
+            mpsc::channel(1);
+        let (state_sender, state) =
+            watch::channel(
+                ConnectionState::Connected,
+            );
+        let (options_sender, options_receiver) =
+            watch::channel(negotiated);
+        tokio::spawn(serve(
+            options,
+            connection,
+            receiver,
+            disconnect_receiver,
+            state_sender,
+            options_sender,
+        ));
+        Ok(Self {
+            options: options_receiver,
+            requests,
+            disconnects,
+            state,
+            alarms: Arc::default(),
+        })
     }
 
-    async fn copt_connect(
-        &mut self,
-    ) -> Result<()> {
-        let frame =
-            build_framed_copt_connect_request(
-                &self.options,
-            )?;
-        self.write_frame(frame).await?;
-        let frame =
-            self.read_frame().await?.payload();
-        if let PduType::ConnectConfirm(comm) =
-            &frame.pdu_type
+    /// release the connection with a disconnect
+    /// request, for all the client handles. The
+    /// jobs in flight fail, and the connection
+    /// is not reconnected.
+    pub async fn disconnect(&self) -> Result<()> {
+        let (response, receiver) =
+            oneshot::channel();
+        // the connection task exited, the
+        // connection is already released
+        if self
+            .disconnects
+            .send(response)
+            .await
+            .is_err()
         {
-            debug!("{:?}", comm);
-            for item in &comm.parameters {
-                if let Parameter::TpduSize(size) =
-                    item
-                {
-                    self.options.tpdu_size =
-                        size.clone();
-                }
-            }
-        } else {
-            return Err(Error::ConnectErr(
-                format!(
-                    "should recv connect \
-                     confirm, but not {:?}",
-                    frame
-                ),
-            ));
+            return Ok(());
         }
-        Ok(())
+        receiver.await.unwrap_or(Ok(()))
     }
 
-    async fn s7_setup(&mut self) -> Result<()> {
-        let frame =
-            build_framed_s7_setup(&self.options)?;
-        self.write_frame(frame).await?;
-        let frame =
-            self.read_frame().await?.payload();
-        if let PduType::DtData(comm) =
-            frame.pdu_type
-        {
-            if let Frame::AckData {
-                ack_data,
-                ..
-            } = comm.payload()
-            {
-                if let AckData::SetupCommunication(data) = ack_data {
-                        debug!("{:?}", data);
-                        self.options.pdu_len = data.pdu_length();
-                    }
-            }
-        } else {
-            return Err(Error::ConnectErr(
-                format!(
-                    "should recv connect \
-                     confirm, but not {:?}",
-                    frame
-                ),
-            ));
-        }
-        Ok(())
+    /// the options, with the values negotiated
+    /// with the plc at the last connection or
+    /// reconnection
+    pub fn options(&self) -> Options {
+        self.options.borrow().clone()
+    }
+
+    /// the pdu length negotiated at the last
+    /// connection or reconnection, which jobs
+    /// are planned from
+    fn pdu_len(&self) -> u16 {
+        self.options.borrow().pdu_len
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.state.borrow()
+    }
+
+    /// receiver notified of the changes of the
+    /// connection state
+    pub fn subscribe_state(
+        &self,
+    ) -> watch::Receiver<ConnectionState> {
+        self.state.clone()
+    }
+
+    /// send a job and wait for its ack data
+    async fn request(
+        &self,
+        frame: Frame,
+    ) -> Result<Frame> {
+        recv(self.submit(frame, None).await?)
+            .await
+    }
+
+    /// send a request download job, the parts of
+    /// `block` then served to the download block
+    /// jobs of the plc. Receive the download
+    /// ended job of the plc, or the ack data of
+    /// a refused request.
+    async fn download(
+        &self,
+        frame: Frame,
+        block: Vec<u8>,
+    ) -> Result<Frame> {
+        let (response, receiver) =
+            oneshot::channel();
+        self.send_request(Request {
+            frame,
+            retry: None,
+            block: Some(block),
+            subscription: None,
+            response,
+        })
+        .await?;
+        recv(receiver).await
+    }
+
+    /// send a cyclic read or message service
+    /// request, its pushes then sent to
+    /// `subscription` if the plc accepts it
+    async fn subscribe(
+        &self,
+        frame: Frame,
+        subscription: Subscriber,
+    ) -> Result<Frame> {
+        let (response, receiver) =
+            oneshot::channel();
+        self.send_request(Request {
+            frame,
+            retry: None,
+            block: None,
+            subscription: Some(subscription),
+            response,
+        })
+        .await?;
+        recv(receiver).await
+    }
+
+    /// queue a job to the connection, which
+    /// assigns its pdu reference. The ack data
+    /// is received by the returned receiver.
+    /// `retry` holds the areas of an idempotent
+    /// read, sent again after reconnection.
+    async fn submit(
+        &self,
+        frame: Frame,
+        retry: Option<Vec<Area>>,
+    ) -> Result<oneshot::Receiver<Result<Frame>>>
+    {
+        let (response, receiver) =
+            oneshot::channel();
+        self.send_request(Request {
+            frame,
+            retry,
+            block: None,
+            subscription: None,
+            response,
+        })
+        .await?;
+        Ok(receiver)
+    }
+
+    async fn send_request(
+        &self,
+        request: Request,
+    ) -> Result<()> {
+        self.requests.send(request).await.map_err(
+            |_| {
+                Error::ConnectionClosed(
+                    "connection task exited"
+                        .to_string(),
+                )
+            },
+        )
     }
 
     pub async fn write_bytes(
-        &mut self,
+        &self,
         db_number: Option<u16>,
         area: s7_comm::Area,
         byte_addr: u16,
         data: &[u8],
     ) -> Result<DataItemWriteResponse> {
         let frame = build_s7_write()
-            .pdu_ref(
-                self.options.tpdu_size.pdu_ref(),
-            )
             .write_bytes(
                 db_number, area, byte_addr, data,
             )
-            .build()?;
+            .build_frame();
 
         let items = self.write(frame).await?;
         if items.len() == 1 {
             Ok(items[0].clone())
         } else {
-            Err(Error::Err(format!(
+            Err(Error::InvalidPlcAnswer(format!(
                 "read bytes items.len={} != 1",
                 items.len()
             )))
@@ -148,7 +268,7 @@ impl S7Client {
     }
 
     pub async fn write_bit(
-        &mut self,
+        &self,
         db_number: Option<u16>,
         area: s7_comm::Area,
         byte_addr: u16,
@@ -156,19 +276,16 @@ impl S7Client {
         data: bool,
     ) -> Result<DataItemWriteResponse> {
         let frame = build_s7_write()
-            .pdu_ref(
-                self.options.tpdu_size.pdu_ref(),
-            )
             .write_bit(
                 db_number, area, byte_addr,
                 bit_addr, data,
             )
-            .build()?;
+            .build_frame();
         let items = self.write(frame).await?;
         if items.len() == 1 {
             Ok(items[0].clone())
         } else {
-            Err(Error::Err(format!(
+            Err(Error::InvalidPlcAnswer(format!(
                 "read bit items.len={} != 1",
                 items.len()
             )))
@@ -176,133 +293,211 @@ impl S7Client {
     }
 
     async fn write(
-        &mut self,
-        frame: BytesMut,
+        &self,
+        frame: Frame,
     ) -> Result<Vec<DataItemWriteResponse>> {
-        self.write_frame(frame).await?;
-        let frame =
-            self.read_frame().await?.payload();
-        if let PduType::DtData(comm) =
-            frame.pdu_type
-        {
-            if let Frame::AckData {
-                ack_data,
-                ..
-            } = comm.payload()
-            {
-                if let AckData::WriteVar(data) =
-                    ack_data
-                {
-                    return Ok(data.data_item());
-                }
-            }
-        }
-        return Err(Error::Err(format!(
-            "should recv read var"
-        )));
+        write_var_items(
+            self.request(frame).await?,
+        )
     }
 
     pub async fn read(
-        &mut self,
+        &self,
         area: &Area,
     ) -> Result<DataItemVal> {
-        let frame = build_framed_s7_read(
-            &self.options,
-            &[*area],
-        )?;
-        self.write_frame(frame).await?;
-        let frame =
-            self.read_frame().await?.payload();
-        if let PduType::DtData(comm) =
-            frame.pdu_type
-        {
-            if let Frame::AckData {
-                ack_data,
-                ..
-            } = comm.payload()
-            {
-                if let AckData::ReadVar(data) =
-                    ack_data
-                {
-                    let data_item =
-                        data.data_item();
-                    if data_item.len() != 1 {
-                        return Err(Error::Err(format!(
-                            "should recv one item, \
-                             but recv {}",
-                            data_item.len()
-                        )));
-                    }
-
-                    return Ok(
-                        data_item[0].clone()
-                    );
-                }
-            }
-        }
-        return Err(Error::Err(format!(
-            "should recv read var"
-        )));
+        let mut items =
+            self.read_vec(&[*area]).await?;
+        Ok(items.remove(0))
     }
 
+    /// read `areas`, split into jobs fitting the
+    /// negotiated pdu length. The items are
+    /// returned in the order of `areas`.
     pub async fn read_vec(
-        &mut self,
+        &self,
         areas: &[Area],
     ) -> Result<Vec<DataItemVal>> {
-        let frame = build_framed_s7_read(
-            &self.options,
-            areas,
-        )?;
-        self.write_frame(frame).await?;
-        let frame =
-            self.read_frame().await?.payload();
-        if let PduType::DtData(comm) =
-            frame.pdu_type
+        let jobs =
+            plan_read(areas, self.pdu_len())?;
+        // queue all jobs first, so they are
+        // pipelined by the connection
+        let mut receivers =
+            Vec::with_capacity(jobs.len());
+        for job in &jobs {
+            let job_areas: Vec<Area> = job
+                .iter()
+                .map(|x| x.area)
+                .collect();
+            receivers.push(
+                self.submit(
+                    build_s7_read_frame(
+                        &job_areas,
+                    ),
+                    Some(job_areas),
+                )
+                .await?,
+            );
+        }
+        let mut items =
+            Vec::with_capacity(areas.len());
+        for (job, receiver) in
+            jobs.into_iter().zip(receivers)
         {
-            if let Frame::AckData {
-                ack_data,
-                ..
-            } = comm.payload()
-            {
-                if let AckData::ReadVar(data) =
-                    ack_data
-                {
-                    return Ok(data.data_item());
-                }
+            let job_items = read_var_items(
+                recv(receiver).await?,
+            )?;
+            if job_items.len() != job.len() {
+                return Err(Error::InvalidPlcAnswer(format!(
+                    "should recv {} items, but recv \
+                     {}",
+                    job.len(),
+                    job_items.len()
+                )));
             }
+            items.extend(
+                job.into_iter().zip(job_items),
+            );
         }
-        return Err(Error::Err(format!(
-            "should recv read var"
-        )));
+        merge_read(areas.len(), items)
     }
 
-    async fn write_frame(
-        &mut self,
-        framed: BytesMut,
-    ) -> Result<()> {
-        timeout(
-            self.options.write_timeout,
-            self.connect.write_all(&framed),
-        )
-        .await
-        .map_err(|_| Error::WriteTimeout)??;
-        Ok(())
+    /// write `items`, split into jobs fitting the
+    /// negotiated pdu length. The length of each
+    /// data must match its area. The responses
+    /// are returned in the order of `items`.
+    pub async fn write_vec(
+        &self,
+        items: &[(Area, &[u8])],
+    ) -> Result<Vec<DataItemWriteResponse>> {
+        for (area, data) in items {
+            if data_len(area) != data.len() {
+                return Err(Error::InvalidInput(
+                    format!(
+                    "{:?} needs {} bytes, but {}",
+                    area,
+                    data_len(area),
+                    data.len()
+                ),
+                ));
+            }
+        }
+        let areas: Vec<Area> =
+            items.iter().map(|x| x.0).collect();
+        let jobs =
+            plan_write(&areas, self.pdu_len())?;
+        // queue all jobs first, so they are
+        // pipelined by the connection
+        let mut receivers =
+            Vec::with_capacity(jobs.len());
+        for job in &jobs {
+            let mut builder = build_s7_write();
+            for chunk in job {
+                let data = &items[chunk.index].1
+                    [chunk.range.clone()];
+                let val = match *chunk.area {
+                    DataSizeType::Bit { .. } => {
+                        DataItemVal::init_with_bit(
+                            ReturnCode::Reserved,
+                            data[0] > 0,
+                        )
+                    },
+                    DataSizeType::Byte { .. } => {
+                        DataItemVal::init_with_bytes(
+                            ReturnCode::Reserved,
+                            data,
+                        )
+                    },
+                };
+                builder = builder.add_item((
+                    chunk.area.into(),
+                    val,
+                ));
+            }
+            receivers.push(
+                self.submit(
+                    builder.build_frame(),
+                    None,
+                )
+                .await?,
+            );
+        }
+        let mut responses =
+            Vec::with_capacity(items.len());
+        for (job, receiver) in
+            jobs.into_iter().zip(receivers)
+        {
+            let job_responses = write_var_items(
+                recv(receiver).await?,
+            )?;
+            if job_responses.len() != job.len() {
+                return Err(Error::InvalidPlcAnswer(format!(
+                    "should recv {} items, but recv \
+                     {}",
+                    job.len(),
+                    job_responses.len()
+                )));
+            }
+            responses.extend(
+                job.into_iter()
+                    .zip(job_responses),
+            );
+        }
+        merge_write(items.len(), responses)
     }
 
-    async fn read_frame(
-        &mut self,
-    ) -> Result<TpktFrame<CoptFrame<Frame>>> {
-        Ok(timeout(
-            self.options.read_timeout,
-            read_framed(&mut self.connect),
-        )
-        .await
-        .map_err(|_| Error::WriteTimeout)??)
+    /// read a value of an elementary data type
+    /// starting at the address of `area`
+    pub async fn read_value<T: S7Value>(
+        &self,
+        area: &Area,
+    ) -> Result<T> {
+        let data_type = T::data_type(area)?;
+        let area = data_type.area(area)?;
+        let item = self.read(&area).await?;
+        if !item.return_code.is_ok() {
+            return Err(Error::Item {
+                index: 0,
+                area,
+                return_code: item.return_code,
+            });
+        }
+        T::from_value(Value::decode(
+            data_type, &item.data,
+        )?)
+    }
+
+    /// write a value of an elementary data type
+    /// starting at the address of `area`
+    pub async fn write_value<T: S7Value>(
+        &self,
+        area: &Area,
+        value: T,
+    ) -> Result<()> {
+        let data_type = T::data_type(area)?;
+        let area = data_type.area(area)?;
+        let data = value
+            .into_value()
+            .encode(data_type)?;
+        let mut resp = self
+            .write_vec(&[(area, data.as_slice())])
+            .await?;
+        let resp = resp.remove(0);
+        if !resp.return_code.is_ok() {
+            return Err(Error::Item {
+                index: 0,
+                area,
+                return_code: resp.return_code,
+            });
+        }
+        Ok(())
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Options {
+    /// the timeout of the tcp connect, at
+    /// connect and at each reconnection
+    pub connect_timeout: Duration,
     pub read_timeout: Duration,
     pub write_timeout: Duration,
     address: IpAddr,
@@ -312,6 +507,22 @@ pub struct Options {
     //PDULength variable to store pdu length
     // after connect
     pdu_len: u16,
+    /// jobs sent in parallel by the client,
+    /// the negotiated value after connect
+    pub max_amq_calling: u16,
+    /// jobs sent in parallel by the plc, the
+    /// negotiated value after connect
+    pub max_amq_called: u16,
+    /// reconnect when the connection breaks,
+    /// disabled by default
+    pub reconnect: Option<Reconnect>,
+    /// the session password, set after connect
+    /// and after each reconnection of a
+    /// protected plc
+    pub password: Option<String>,
+    /// the cotp connection negotiated at
+    /// connect
+    cotp: Option<CotpNegotiation>,
 }
 
 impl Options {
@@ -321,6 +532,9 @@ impl Options {
         conn_mode: ConnectMode,
     ) -> Options {
         Self {
+            connect_timeout: Duration::from_secs(
+                3,
+            ),
             read_timeout: Duration::from_millis(
                 500,
             ),
@@ -332,63 +546,82 @@ impl Options {
             conn_mode,
             pdu_len: 480,
             tpdu_size: TpduSize::L2048,
+            max_amq_calling: 8,
+            max_amq_called: 8,
+            reconnect: None,
+            password: None,
+            cotp: None,
         }
     }
-}
 
-async fn read_framed(
-    req: &mut TcpStream,
-) -> Result<TpktFrame<CoptFrame<Frame>>> {
-    let mut buf = [0u8; 1000];
-    let mut bytes = BytesMut::new();
-    let mut decoder =
-        TpktDecoder(CoptDecoder(S7CommDecoder));
-    loop {
-        let size = req.read(&mut buf).await?;
-        bytes.extend_from_slice(
-            buf[0..size].as_ref(),
-        );
-        if let Some(frame) =
-            decoder.decode(&mut bytes)?
-        {
-            return Ok(frame);
-        }
+    /// the pdu length, the negotiated value
+    /// after connect
+    pub fn pdu_len(&self) -> u16 {
+        self.pdu_len
+    }
+
+    /// the cotp connection, negotiated at
+    /// connect
+    pub fn cotp(
+        &self,
+    ) -> Option<&CotpNegotiation> {
+        self.cotp.as_ref()
     }
 }
 
-fn build_framed_s7_read(
-    options: &Options,
-    areas: &[Area],
-) -> Result<BytesMut> {
-    let mut builder = build_s7_read()
-        .pdu_ref(options.tpdu_size.pdu_ref());
+async fn recv(
+    receiver: oneshot::Receiver<Result<Frame>>,
+) -> Result<Frame> {
+    receiver.await.map_err(|_| {
+        Error::ConnectionClosed(
+            "connection task exited".to_string(),
+        )
+    })?
+}
+
+fn build_s7_read_frame(areas: &[Area]) -> Frame {
+    let mut builder = build_s7_read();
     for area in areas {
         builder =
             builder.add_item((*area).into());
     }
-    Ok(builder.build()?)
+    builder.build_frame()
 }
 
-fn build_framed_copt_connect_request(
-    options: &Options,
-) -> Result<BytesMut> {
-    Ok(build_copt_connect_request()
-        .source_ref([0, 1])
-        .destination_ref([0, 0])
-        .class_and_others(0, false, false)
-        .pdu_size(TpduSize::L1024)
-        .src_tsap(options.conn_mode.local_tsap())
-        .dst_tsap(options.conn_mode.remote_tsap())
-        .build_to_request()?)
+fn read_var_items(
+    frame: Frame,
+) -> Result<Vec<DataItemVal>> {
+    if let Frame::AckData {
+        header,
+        ack_data: AckData::ReadVar(data),
+    } = frame
+    {
+        return match Error::from_code(&header) {
+            Some(err) => Err(err),
+            None => Ok(data.data_item()),
+        };
+    }
+    Err(Error::InvalidPlcAnswer(format!(
+        "should recv read var, but not {:?}",
+        frame
+    )))
 }
 
-fn build_framed_s7_setup(
-    options: &Options,
-) -> Result<BytesMut> {
-    Ok(build_s7_setup()
-        .max_amq_called(1)
-        .max_amq_calling(1)
-        .pdu_length(options.pdu_len)
-        .pdu_ref(options.tpdu_size.pdu_ref())
-        .build()?)
+fn write_var_items(
+    frame: Frame,
+) -> Result<Vec<DataItemWriteResponse>> {
+    if let Frame::AckData {
+        header,
+        ack_data: AckData::WriteVar(data),
+    } = frame
+    {
+        return match Error::from_code(&header) {
+            Some(err) => Err(err),
+            None => Ok(data.data_item()),
+        };
+    }
+    Err(Error::InvalidPlcAnswer(format!(
+        "should recv write var, but not {:?}",
+        frame
+    )))
 }
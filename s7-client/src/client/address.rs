@@ -0,0 +1,649 @@
+use crate::{Area, BitAddr, DataSizeType};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// An address in STEP 7 syntax which could not be
+/// parsed. `position` is the byte offset of the
+/// failing character.
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[error(
+    "invalid address `{input}` at position \
+     {position}: {reason}"
+)]
+pub struct AddressError {
+    pub input:    String,
+    pub position: usize,
+    pub reason:   String
+}
+
+/// Siemens absolute addressing, e.g.
+/// `DB1.DBX0.6`, `DB1.DBW2`, `MW20`, `I3.0`,
+/// `QD4`, `T5`, `C3`, `DB10.DBB100 BYTE 20` or
+/// `P#DB1.DBX0.0 BYTE 20`. German mnemonics (`E`,
+/// `A`, `Z`) are accepted as well.
+impl FromStr for Area {
+    type Err = AddressError;
+
+    fn from_str(
+        s: &str
+    ) -> Result<Self, Self::Err> {
+        Parser::new(s).parse()
+    }
+}
+
+/// The canonical STEP 7 spelling, which parses
+/// back into the same area. Byte ranges other
+/// than 1, 2 or 4 bytes are written as any
+/// pointer, e.g. `P#DB1.DBX0.0 BYTE 20`.
+impl fmt::Display for Area {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>
+    ) -> fmt::Result {
+        let prefix = match self {
+            Area::ProcessInput(_) => "I",
+            Area::ProcessOutput(_) => "Q",
+            Area::Merker(_) => "M",
+            Area::V(_) => "V",
+            Area::DataBausteine(db_number, _) => {
+                return fmt_db(
+                    f, *db_number, self
+                );
+            },
+            Area::Timer(ds) => {
+                return fmt_counted(
+                    f, "T", "TIMER", ds
+                );
+            },
+            Area::Counter(ds) => {
+                return fmt_counted(
+                    f, "C", "COUNTER", ds
+                );
+            },
+        };
+        match **self {
+            DataSizeType::Bit {
+                addr,
+                bit_addr
+            } => {
+                write!(
+                    f,
+                    "{}{}.{}",
+                    prefix, addr, bit_addr as u8
+                )
+            },
+            DataSizeType::Byte { addr, len } => {
+                match len {
+                    1 => write!(
+                        f,
+                        "{}B{}",
+                        prefix, addr
+                    ),
+                    2 => write!(
+                        f,
+                        "{}W{}",
+                        prefix, addr
+                    ),
+                    4 => write!(
+                        f,
+                        "{}D{}",
+                        prefix, addr
+                    ),
+                    len => {
+                        write!(
+                            f,
+                            "P#{}{}.0 BYTE {}",
+                            prefix, addr, len
+                        )
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn fmt_db(
+    f: &mut fmt::Formatter<'_>,
+    db_number: u16,
+    ds: &DataSizeType
+) -> fmt::Result {
+    match *ds {
+        DataSizeType::Bit { addr, bit_addr } => {
+            write!(
+                f,
+                "DB{}.DBX{}.{}",
+                db_number, addr, bit_addr as u8
+            )
+        },
+        DataSizeType::Byte { addr, len } => {
+            match len {
+                1 => write!(
+                    f,
+                    "DB{}.DBB{}",
+                    db_number, addr
+                ),
+                2 => write!(
+                    f,
+                    "DB{}.DBW{}",
+                    db_number, addr
+                ),
+                4 => write!(
+                    f,
+                    "DB{}.DBD{}",
+                    db_number, addr
+                ),
+                len => write!(
+                    f,
+                    "P#DB{}.DBX{}.0 BYTE {}",
+                    db_number, addr, len
+                )
+            }
+        },
+    }
+}
+
+fn fmt_counted(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    unit: &str,
+    ds: &DataSizeType
+) -> fmt::Result {
+    match ds.len() {
+        1 => write!(
+            f,
+            "{}{}",
+            prefix,
+            ds.byte_addr()
+        ),
+        len => write!(
+            f,
+            "P#{}{} {} {}",
+            prefix,
+            ds.byte_addr(),
+            unit,
+            len
+        )
+    }
+}
+
+/// Width of an access to a memory area
+enum Width {
+    Bit(u16, BitAddr),
+    Bytes(u16, u16)
+}
+
+struct Parser<'a> {
+    input:    &'a str,
+    /// upper-cased copy of input, same byte
+    /// offsets
+    upper:    String,
+    pos:      usize,
+    /// whether a repetition like `BYTE 10` was
+    /// parsed
+    repeated: bool
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            upper: input.to_ascii_uppercase(),
+            pos: 0,
+            repeated: false
+        }
+    }
+
+    fn parse(
+        mut self
+    ) -> Result<Area, AddressError> {
+        self.skip_spaces();
+        let any_pointer = self.eat("P#");
+        let area = if self.eat("DB") {
+            let db_number =
+                self.number("DB number")?;
+            if db_number == 0 {
+                return Err(self.error_at(
+                    self.pos - 1,
+                    "DB number must not be 0"
+                ));
+            }
+            self.expect(".")?;
+            self.expect("DB")?;
+            let width = self.width(false)?;
+            Area::DataBausteine(
+                db_number,
+                self.sized(width)?
+            )
+        } else if self.eat("T") {
+            Area::Timer(self.counted("TIMER")?)
+        } else if self.eat("C") || self.eat("Z") {
+            Area::Counter(
+                self.counted("COUNTER")?
+            )
+        } else if self.eat("I") || self.eat("E") {
+            Area::ProcessInput(self.memory()?)
+        } else if self.eat("Q") || self.eat("A") {
+            Area::ProcessOutput(self.memory()?)
+        } else if self.eat("M") {
+            Area::Merker(self.memory()?)
+        } else if self.eat("V") {
+            Area::V(self.memory()?)
+        } else {
+            return Err(self.error(
+                "expected one of DB, I, E, Q, \
+                 A, M, V, T, C, Z"
+            ));
+        };
+        self.skip_spaces();
+        if any_pointer && !self.repeated {
+            return Err(self.error(
+                "expected a repetition like \
+                 `BYTE 10` after an any pointer"
+            ));
+        }
+        if self.pos < self.upper.len() {
+            return Err(self
+                .error("unexpected character"));
+        }
+        Ok(area)
+    }
+
+    /// I/Q/M/V area after the area letter
+    fn memory(
+        &mut self
+    ) -> Result<DataSizeType, AddressError> {
+        let width = self.width(true)?;
+        self.sized(width)
+    }
+
+    /// `X<byte>.<bit>`, `B<byte>`, `W<byte>` or
+    /// `D<byte>`. Memory areas may omit the
+    /// `X` of bit addresses.
+    fn width(
+        &mut self,
+        implicit_bit: bool
+    ) -> Result<Width, AddressError> {
+        let width = if self.eat("X") {
+            None
+        } else if self.eat("B") {
+            Some(1)
+        } else if self.eat("W") {
+            Some(2)
+        } else if self.eat("D") {
+            Some(4)
+        } else if implicit_bit
+            && self.peek_digit()
+        {
+            None
+        } else if implicit_bit {
+            return Err(self.error(
+                "expected a byte address or one \
+                 of X, B, W, D"
+            ));
+        } else {
+            return Err(self.error(
+                "expected one of X, B, W, D"
+            ));
+        };
+        let addr = self.number("byte address")?;
+        match width {
+            Some(len) => {
+                Ok(Width::Bytes(addr, len))
+            },
+            None => {
+                self.expect(".")?;
+                let pos = self.pos;
+                let bit =
+                    self.number("bit address")?;
+                let bit_addr =
+                    BitAddr::try_from(bit)
+                        .map_err(|_| {
+                            self.error_at(
+                                pos,
+                                "bit address \
+                                 must be 0 to 7"
+                            )
+                        })?;
+                Ok(Width::Bit(addr, bit_addr))
+            }
+        }
+    }
+
+    /// apply an optional `BYTE n` repetition to a
+    /// width
+    fn sized(
+        &mut self,
+        width: Width
+    ) -> Result<DataSizeType, AddressError> {
+        let start = self.pos;
+        let Some((unit_len, count)) = self
+            .repetition(&[
+                ("BYTE", 1),
+                ("CHAR", 1),
+                ("WORD", 2),
+                ("INT", 2),
+                ("DWORD", 4),
+                ("DINT", 4),
+                ("REAL", 4)
+            ])?
+        else {
+            return Ok(match width {
+                Width::Bit(addr, bit_addr) => {
+                    DataSizeType::Bit {
+                        addr,
+                        bit_addr
+                    }
+                },
+                Width::Bytes(addr, len) => {
+                    DataSizeType::Byte {
+                        addr,
+                        len
+                    }
+                },
+            });
+        };
+        let addr = match width {
+            Width::Bit(addr, BitAddr::Addr0) => {
+                addr
+            },
+            Width::Bit(..) => {
+                return Err(self.error_at(
+                    start,
+                    "a repetition must start at \
+                     bit 0"
+                ));
+            },
+            Width::Bytes(addr, _) => addr
+        };
+        let len = unit_len * count as u32;
+        let len =
+            u16::try_from(len).map_err(|_| {
+                self.error_at(
+                    start,
+                    "repetition is too large"
+                )
+            })?;
+        Ok(DataSizeType::Byte { addr, len })
+    }
+
+    /// timer or counter number with an optional
+    /// repetition
+    fn counted(
+        &mut self,
+        unit: &str
+    ) -> Result<DataSizeType, AddressError> {
+        let addr = self.number("number")?;
+        let len = match self
+            .repetition(&[(unit, 1)])?
+        {
+            Some((_, count)) => count,
+            None => 1
+        };
+        Ok(DataSizeType::Byte { addr, len })
+    }
+
+    /// ` <unit> <count>`, a count of 0 is kept so
+    /// that every displayed area parses again
+    fn repetition(
+        &mut self,
+        units: &[(&str, u32)]
+    ) -> Result<Option<(u32, u16)>, AddressError>
+    {
+        let start = self.pos;
+        self.skip_spaces();
+        if self.pos == start
+            || self.pos == self.upper.len()
+        {
+            return Ok(None);
+        }
+        let Some((unit, unit_len)) =
+            units.iter().find(|(unit, _)| {
+                self.upper[self.pos..]
+                    .starts_with(unit)
+            })
+        else {
+            let names: Vec<&str> = units
+                .iter()
+                .map(|(unit, _)| *unit)
+                .collect();
+            return Err(self.error(&format!(
+                "expected one of {}",
+                names.join(", ")
+            )));
+        };
+        self.pos += unit.len();
+        if !self.skip_spaces() {
+            return Err(
+                self.error("expected a space")
+            );
+        }
+        let count = self.number("count")?;
+        self.repeated = true;
+        Ok(Some((*unit_len, count)))
+    }
+
+    fn number(
+        &mut self,
+        name: &str
+    ) -> Result<u16, AddressError> {
+        let start = self.pos;
+        while self.peek_digit() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error(&format!(
+                "expected {}",
+                name
+            )));
+        }
+        self.upper[start..self.pos]
+            .parse()
+            .map_err(|_| {
+                self.error_at(
+                    start,
+                    &format!(
+                        "{} exceeds 65535",
+                        name
+                    )
+                )
+            })
+    }
+
+    fn peek_digit(&self) -> bool {
+        self.upper[self.pos..]
+            .chars()
+            .next()
+            .is_some_and(|x| x.is_ascii_digit())
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.upper[self.pos..]
+            .starts_with(token)
+        {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(
+        &mut self,
+        token: &str
+    ) -> Result<(), AddressError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!(
+                "expected `{}`",
+                token
+            )))
+        }
+    }
+
+    /// skip spaces, return whether any was
+    /// skipped
+    fn skip_spaces(&mut self) -> bool {
+        let start = self.pos;
+        while self.upper[self.pos..]
+            .starts_with(' ')
+        {
+            self.pos += 1;
+        }
+        start != self.pos
+    }
+
+    fn error(
+        &self,
+        reason: &str
+    ) -> AddressError {
+        self.error_at(self.pos, reason)
+    }
+
+    fn error_at(
+        &self,
+        position: usize,
+        reason: &str
+    ) -> AddressError {
+        let reason = match self.input[position..]
+            .chars()
+            .next()
+        {
+            Some(found) => format!(
+                "{}, found `{}`",
+                reason, found
+            ),
+            None => format!(
+                "{}, found end of input",
+                reason
+            )
+        };
+        AddressError {
+            input: self.input.to_string(),
+            position,
+            reason
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Area, BitAddr, DataSizeType};
+
+    #[test]
+    fn check_parse() {
+        let area: Area =
+            "DB1.DBX0.6".parse().unwrap();
+        assert_eq!(
+            area,
+            Area::DataBausteine(
+                1,
+                DataSizeType::Bit {
+                    addr:     0,
+                    bit_addr: BitAddr::Addr6
+                }
+            )
+        );
+        let area: Area = "mw20".parse().unwrap();
+        assert_eq!(
+            area,
+            Area::Merker(DataSizeType::Byte {
+                addr: 20,
+                len:  2
+            })
+        );
+        let area: Area = "DB10.DBB100 BYTE 20"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            area,
+            Area::DataBausteine(
+                10,
+                DataSizeType::Byte {
+                    addr: 100,
+                    len:  20
+                }
+            )
+        );
+        let area: Area = "P#DB1.DBX0.0 WORD 3"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            area,
+            Area::DataBausteine(
+                1,
+                DataSizeType::Byte {
+                    addr: 0,
+                    len:  6
+                }
+            )
+        );
+        let area: Area = "Z3".parse().unwrap();
+        assert_eq!(
+            area,
+            Area::Counter(DataSizeType::Byte {
+                addr: 3,
+                len:  1
+            })
+        );
+    }
+
+    #[test]
+    fn check_round_trip() {
+        for addr in [
+            "DB1.DBX0.6",
+            "DB1.DBB2",
+            "DB1.DBW2",
+            "DB1.DBD2",
+            "P#DB1.DBX10.0 BYTE 20",
+            "P#DB1.DBX10.0 BYTE 0",
+            "I3.0",
+            "IB3",
+            "QD4",
+            "MW20",
+            "P#M10.0 BYTE 3",
+            "V1.7",
+            "T5",
+            "P#C5 COUNTER 3",
+            "P#M10.0 BYTE 0",
+            "P#T5 TIMER 0"
+        ] {
+            let area: Area =
+                addr.parse().unwrap();
+            assert_eq!(area.to_string(), addr);
+        }
+    }
+
+    #[test]
+    fn check_error() {
+        let err = "DB1.DBQ0"
+            .parse::<Area>()
+            .unwrap_err();
+        assert_eq!(err.position, 6);
+        assert_eq!(
+            err.reason,
+            "expected one of X, B, W, D, found \
+             `Q`"
+        );
+        let err =
+            "M0.8".parse::<Area>().unwrap_err();
+        assert_eq!(err.position, 3);
+        let err = "DB0.DBB1"
+            .parse::<Area>()
+            .unwrap_err();
+        assert_eq!(err.position, 2);
+        let err = "P#DB1.DBX0.1 BYTE 2"
+            .parse::<Area>()
+            .unwrap_err();
+        assert_eq!(err.position, 12);
+        assert!(
+            "P#M10.0".parse::<Area>().is_err()
+        );
+        assert!(
+            "MW20 BYTE".parse::<Area>().is_err()
+        );
+        assert!(
+            "MW70000".parse::<Area>().is_err()
+        );
+    }
+}
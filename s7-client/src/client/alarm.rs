@@ -0,0 +1,255 @@
+use super::{
+    S7Client,
+    connection::Subscriber,
+    items::check_return_code,
+    subscription::{Cancel, Pushes},
+    user_data::user_data_response
+};
+use crate::{Error, ItemResult, Result};
+use futures_core::Stream;
+use s7_comm::{
+    ALARM_ACK, ALARM_QUERY, AlarmAck, AlarmKind,
+    AlarmMessage, AlarmNotification, AlarmType,
+    DateAndTime, EVENTS_ALARM, Frame,
+    FunctionGroup, MessageService, ReturnCode,
+    UserDataItem, encode_alarm_query
+};
+use std::{
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll}
+};
+use tokio::sync::mpsc;
+
+/// notifications buffered for a slow receiver,
+/// the later ones dropped meanwhile
+const ALARM_CAPACITY: usize = 64;
+/// the name of the subscriber of the alarms
+const ALARM_USER: &str = "s7client";
+/// bytes of an alarm ack request besides its
+/// items: header, parameter, data head, function
+/// and count
+const ACK_OVERHEAD: u16 = 28;
+/// bytes of an item of an alarm ack request
+const ACK_ITEM_LEN: u16 = 10;
+
+/// the id of the next alarm subscription
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Alarms notified by the plc: the messages
+/// of the alarms which changed, at the time of
+/// the plc.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlarmEvent {
+    pub kind:      AlarmKind,
+    pub timestamp: DateAndTime,
+    pub messages:  Vec<AlarmMessage>
+}
+
+/// The alarms notified by the plc. The
+/// notifications end with the connection, after
+/// an error, and the plc stops them when the
+/// subscription is unsubscribed or dropped,
+/// unless a newer subscription replaced it.
+pub struct AlarmSubscription {
+    alarm_type: AlarmType,
+    events:     Pushes<AlarmEvent>
+}
+
+impl S7Client {
+    /// subscribe the alarms of `alarm_type`, and
+    /// the acknowledgements of other stations. A
+    /// connection has one subscription, replaced
+    /// by a new one, and the replaced one no
+    /// longer ends the alarms.
+    pub async fn subscribe_alarms(
+        &self,
+        alarm_type: AlarmType
+    ) -> Result<AlarmSubscription> {
+        let frame = message_service_frame(
+            alarm_type, true
+        )?;
+        let (sender, events) =
+            mpsc::channel(ALARM_CAPACITY);
+        // held until the plc answers, so an
+        // unsubscribe of the replaced
+        // subscription is sent before, or not
+        let mut current =
+            self.alarms.lock().await;
+        user_data_response(
+            self.subscribe(
+                frame,
+                Subscriber::Alarms(sender)
+            )
+            .await?
+        )?;
+        let id = NEXT_ID
+            .fetch_add(1, Ordering::Relaxed);
+        *current = Some(id);
+        Ok(AlarmSubscription {
+            alarm_type,
+            events: Pushes::new(
+                self.clone(),
+                Cancel::Alarms(alarm_type, id),
+                events
+            )
+        })
+    }
+
+    /// acknowledge the signals of alarms, with a
+    /// result for each of `acks`
+    pub async fn ack_alarms(
+        &self,
+        acks: &[AlarmAck]
+    ) -> Result<Vec<ItemResult<()>>> {
+        let max_acks = (self
+            .pdu_len()
+            .saturating_sub(ACK_OVERHEAD)
+            / ACK_ITEM_LEN)
+            .min(u8::MAX as u16);
+        if acks.is_empty()
+            || acks.len() > max_acks as usize
+        {
+            return Err(Error::InvalidInput(
+                format!(
+                    "an alarm ack has 1 to {} \
+                     items, but not {}",
+                    max_acks,
+                    acks.len()
+                )
+            ));
+        }
+        let data = self
+            .user_data(
+                FunctionGroup::Cpu,
+                ALARM_ACK,
+                UserDataItem::init(
+                    ReturnCode::Success,
+                    &AlarmAck::encode_list(acks)
+                )
+            )
+            .await?;
+        let return_codes =
+            AlarmAck::decode_response(&data)
+                .map_err(|err| {
+                    Error::InvalidPlcAnswer(
+                        err.to_string()
+                    )
+                })?;
+        if return_codes.len() != acks.len() {
+            return Err(Error::InvalidPlcAnswer(
+                format!(
+                    "alarm ack results.len={} \
+                     != {}",
+                    return_codes.len(),
+                    acks.len()
+                )
+            ));
+        }
+        Ok(return_codes
+            .iter()
+            .map(check_return_code)
+            .collect())
+    }
+
+    /// the active alarms of `alarm_type`, at the
+    /// time of the plc
+    pub async fn query_alarms(
+        &self,
+        alarm_type: AlarmType
+    ) -> Result<AlarmNotification> {
+        let data = self
+            .user_data(
+                FunctionGroup::Cpu,
+                ALARM_QUERY,
+                UserDataItem::init(
+                    ReturnCode::Success,
+                    &encode_alarm_query(
+                        alarm_type
+                    )
+                )
+            )
+            .await?;
+        AlarmNotification::decode(&data).map_err(
+            |err| {
+                Error::InvalidPlcAnswer(
+                    err.to_string()
+                )
+            }
+        )
+    }
+}
+
+impl AlarmSubscription {
+    pub fn alarm_type(&self) -> AlarmType {
+        self.alarm_type
+    }
+
+    /// the next notification, none after the
+    /// notifications ended
+    pub async fn recv(
+        &mut self
+    ) -> Option<Result<AlarmEvent>> {
+        self.events.recv().await
+    }
+
+    /// end the subscription, if not replaced by
+    /// a newer one
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.events.unsubscribe().await
+    }
+}
+
+impl Stream for AlarmSubscription {
+    type Item = Result<AlarmEvent>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>
+    ) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+/// the message service request starting, or
+/// ending, the alarms of `alarm_type`
+fn message_service_frame(
+    alarm_type: AlarmType,
+    initiate: bool
+) -> Result<Frame> {
+    let service = MessageService {
+        events: if initiate {
+            EVENTS_ALARM
+        } else {
+            0
+        },
+        user:   ALARM_USER.to_string(),
+        alarms: Some((alarm_type, initiate))
+    };
+    Ok(Frame::user_data(0)
+        .message_service(&service)
+        .map_err(|x| {
+            Error::InvalidInput(x.to_string())
+        })?
+        .build())
+}
+
+/// end the alarms of the subscription `id`,
+/// nothing if a newer subscription replaced it
+pub(super) async fn unsubscribe(
+    client: &S7Client,
+    alarm_type: AlarmType,
+    id: u64
+) -> Result<()> {
+    let mut current = client.alarms.lock().await;
+    if *current != Some(id) {
+        return Ok(());
+    }
+    *current = None;
+    let frame =
+        message_service_frame(alarm_type, false)?;
+    user_data_response(
+        client.request(frame).await?
+    )?;
+    Ok(())
+}
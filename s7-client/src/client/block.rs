@@ -0,0 +1,401 @@
+use super::{S7Client, security::check_password};
+use crate::{Error, ErrorCode, Result};
+use s7_comm::{
+    AckData, BLOCK_NOT_FOUND, BlockCount,
+    BlockFile, BlockHeader, BlockInfo,
+    BlockListEntry, BlockType, FileSystem, Frame,
+    FunctionGroup, GET_BLOCK_INFO, Job,
+    LIST_BLOCKS, LIST_BLOCKS_OF_TYPE, ReturnCode,
+    UserDataItem
+};
+
+/// The explicit confirmation of a download or a
+/// delete, naming again the block overwritten or
+/// deleted. A call on another block is refused
+/// with [`Error::NotConfirmed`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Confirmation {
+    block_type: BlockType,
+    number:     u16
+}
+
+impl Confirmation {
+    pub fn block(
+        block_type: BlockType,
+        number: u16
+    ) -> Self {
+        Self { block_type, number }
+    }
+
+    fn check(
+        &self,
+        block_type: BlockType,
+        number: u16
+    ) -> Result<()> {
+        if *self
+            != Self::block(block_type, number)
+        {
+            return Err(Error::NotConfirmed(
+                block_type, number
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl S7Client {
+    /// the count of the blocks of each type
+    pub async fn count_blocks(
+        &self
+    ) -> Result<Vec<BlockCount>> {
+        let data = self
+            .user_data(
+                FunctionGroup::Block,
+                LIST_BLOCKS,
+                UserDataItem::no_data()
+            )
+            .await?;
+        Ok(BlockCount::decode_list(&data))
+    }
+
+    /// the blocks of `block_type`, as OBs or DBs
+    pub async fn list_blocks(
+        &self,
+        block_type: BlockType
+    ) -> Result<Vec<BlockListEntry>> {
+        check_block_type(block_type)?;
+        let data = self
+            .user_data(
+                FunctionGroup::Block,
+                LIST_BLOCKS_OF_TYPE,
+                UserDataItem::init(
+                    ReturnCode::Success,
+                    &[0x30, block_type.into()]
+                )
+            )
+            .await?;
+        Ok(BlockListEntry::decode_list(&data))
+    }
+
+    /// the info of a block in the active file
+    /// system: author, family, version,
+    /// checksum, sizes and timestamps
+    pub async fn block_info(
+        &self,
+        block_type: BlockType,
+        number: u16
+    ) -> Result<BlockInfo> {
+        check_block_type(block_type)?;
+        let file = BlockFile::init(
+            block_type,
+            number,
+            FileSystem::Active
+        );
+        let data = self
+            .user_data(
+                FunctionGroup::Block,
+                GET_BLOCK_INFO,
+                UserDataItem::init(
+                    ReturnCode::Success,
+                    &file.name()
+                )
+            )
+            .await
+            .map_err(|x| match x {
+                Error::Protocol(code)
+                    if code.value()
+                        == BLOCK_NOT_FOUND =>
+                {
+                    Error::InvalidBlockNumber(
+                        number
+                    )
+                },
+                x => x
+            })?;
+        BlockInfo::decode(&data).map_err(|x| {
+            Error::InvalidValue(x.to_string())
+        })
+    }
+
+    /// upload a block of the active file system
+    /// as stored in the load memory: the header,
+    /// the MC7 code (see [`s7_comm::mc7_code`]),
+    /// the interface and the footer
+    pub async fn upload_block(
+        &self,
+        block_type: BlockType,
+        number: u16
+    ) -> Result<Vec<u8>> {
+        check_block_type(block_type)?;
+        let frame = Frame::job_upload(0)
+            .start_upload(BlockFile::init(
+                block_type,
+                number,
+                FileSystem::Active
+            ))
+            .build();
+        let (session_id, block_len) =
+            match upload_ack(
+                self.request(frame).await?,
+                number
+            )? {
+                AckData::StartUpload(ack) => (
+                    ack.session_id(),
+                    ack.block_len()
+                ),
+                _ => {
+                    return Err(
+                    Error::UploadSequenceFailed
+                );
+                }
+            };
+        let rs = self
+            .upload(session_id, number, block_len)
+            .await;
+        // the session is ended even after a
+        // failed upload
+        let frame = Frame::job_upload(0)
+            .end_upload(session_id)
+            .build();
+        let end = self.request(frame).await;
+        let block = rs?;
+        match upload_ack(end?, number)? {
+            AckData::EndUpload => Ok(block),
+            _ => Err(Error::UploadSequenceFailed)
+        }
+    }
+
+    /// download a block, as uploaded by
+    /// [`S7Client::upload_block`], and insert it
+    /// in the active file system, replacing the
+    /// block of the same type and number.
+    /// `confirmation` names that block.
+    pub async fn download_block(
+        &self,
+        block: &[u8],
+        confirmation: Confirmation
+    ) -> Result<()> {
+        let header = BlockHeader::decode(block)
+            .map_err(|_| {
+            Error::InvalidBlockSize(block.len())
+        })?;
+        let (block_type, number) =
+            (header.block_type(), header.number);
+        check_writable(block_type)?;
+        confirmation.check(block_type, number)?;
+        if header.load_size as usize
+            != block.len()
+        {
+            return Err(Error::InvalidBlockSize(
+                block.len()
+            ));
+        }
+        let frame = Frame::job_download(0)
+            .request_download(
+                BlockFile::init(
+                    block_type,
+                    number,
+                    FileSystem::Passive
+                ),
+                header.load_size,
+                header.mc7_size as u32
+            )
+            .build();
+        match self
+            .download(frame, block.to_vec())
+            .await?
+        {
+            Frame::Job {
+                job: Job::DownloadEnded(_),
+                ..
+            } => {},
+            Frame::AckData { header, .. } => {
+                check_password(&header)?;
+                return Err(
+                    Error::DownloadSequenceFailed
+                );
+            },
+            _ => {
+                return Err(
+                    Error::DownloadSequenceFailed
+                );
+            }
+        }
+        let frame = Frame::job_pi_service(0)
+            .insert_block(block_type, number)
+            .build();
+        if !pi_service_accepted(
+            self.request(frame).await?
+        )? {
+            return Err(Error::InsertRefused);
+        }
+        Ok(())
+    }
+
+    /// delete a block of the plc. `confirmation`
+    /// names the block.
+    pub async fn delete_block(
+        &self,
+        block_type: BlockType,
+        number: u16,
+        confirmation: Confirmation
+    ) -> Result<()> {
+        check_writable(block_type)?;
+        confirmation.check(block_type, number)?;
+        let frame = Frame::job_pi_service(0)
+            .delete_block(block_type, number)
+            .build();
+        if !pi_service_accepted(
+            self.request(frame).await?
+        )? {
+            return Err(Error::DeleteRefused);
+        }
+        Ok(())
+    }
+
+    /// the parts of the block, until the last.
+    /// The parts may not exceed the length
+    /// announced at the start of the upload,
+    /// nor be empty before the last.
+    async fn upload(
+        &self,
+        session_id: u32,
+        number: u16,
+        block_len: u32
+    ) -> Result<Vec<u8>> {
+        let mut block = Vec::new();
+        loop {
+            let frame = Frame::job_upload(0)
+                .upload(session_id)
+                .build();
+            match upload_ack(
+                self.request(frame).await?,
+                number
+            )? {
+                AckData::Upload(ack) => {
+                    let more = ack.more();
+                    let data = ack.data();
+                    if more && data.is_empty() {
+                        return Err(
+                            Error::InvalidPlcAnswer(
+                                "empty upload part"
+                                    .to_string()
+                            )
+                        );
+                    }
+                    block.extend(data);
+                    if block.len()
+                        > block_len as usize
+                    {
+                        return Err(
+                            Error::InvalidPlcAnswer(
+                                format!(
+                                    "upload exceeds \
+                                     the block length \
+                                     {}",
+                                    block_len
+                                )
+                            )
+                        );
+                    }
+                    if !more {
+                        return Ok(block);
+                    }
+                },
+                _ => {
+                    return Err(
+                        Error::UploadSequenceFailed
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_block_type(
+    block_type: BlockType
+) -> Result<()> {
+    match block_type {
+        BlockType::NotSupport(_) => Err(
+            Error::InvalidBlockType(block_type)
+        ),
+        _ => Ok(())
+    }
+}
+
+/// the system blocks, SFC and SFB, are not
+/// written
+fn check_writable(
+    block_type: BlockType
+) -> Result<()> {
+    match block_type {
+        BlockType::SFC | BlockType::SFB => Err(
+            Error::InvalidBlockType(block_type)
+        ),
+        _ => check_block_type(block_type)
+    }
+}
+
+fn pi_service_accepted(
+    frame: Frame
+) -> Result<bool> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data: AckData::PiService(_)
+        } => {
+            check_password(&header)?;
+            Ok(header.error_class() == 0
+                && header.error_code() == 0)
+        },
+        frame => {
+            Err(Error::InvalidPlcAnswer(format!(
+                "should recv pi service, but \
+                 not {:?}",
+                frame
+            )))
+        },
+    }
+}
+
+/// the ack data of an upload job of the block
+/// `number`
+fn upload_ack(
+    frame: Frame,
+    number: u16
+) -> Result<AckData> {
+    let Frame::AckData { header, ack_data } =
+        frame
+    else {
+        return Err(Error::InvalidPlcAnswer(
+            format!(
+                "should recv upload, but not \
+                 {:?}",
+                frame
+            )
+        ));
+    };
+    check_password(&header)?;
+    let error = ErrorCode::from(&header).value();
+    match (error, ack_data) {
+        (
+            0,
+            ack_data @ AckData::StartUpload(_)
+        )
+        | (0, ack_data @ AckData::Upload(_))
+        | (0, ack_data @ AckData::EndUpload) => {
+            Ok(ack_data)
+        },
+        (BLOCK_NOT_FOUND, _) => {
+            Err(Error::InvalidBlockNumber(number))
+        },
+        (0, ack_data) => {
+            Err(Error::InvalidPlcAnswer(format!(
+                "should recv upload, but not \
+                 {:?}",
+                ack_data
+            )))
+        },
+        _ => Err(Error::UploadSequenceFailed)
+    }
+}
@@ -0,0 +1,47 @@
+use super::S7Client;
+use crate::{Error, Result};
+use s7_comm::{
+    DateAndTime, FunctionGroup, READ_CLOCK,
+    ReturnCode, SET_CLOCK, UserDataItem
+};
+
+impl S7Client {
+    /// the time of the plc clock
+    pub async fn get_plc_time(
+        &self
+    ) -> Result<DateAndTime> {
+        let data = self
+            .user_data(
+                FunctionGroup::Time,
+                READ_CLOCK,
+                UserDataItem::no_data()
+            )
+            .await?;
+        DateAndTime::decode_clock(&data).map_err(
+            |x| {
+                Error::InvalidValue(x.to_string())
+            }
+        )
+    }
+
+    /// set the plc clock to `time`
+    pub async fn set_plc_time(
+        &self,
+        time: &DateAndTime
+    ) -> Result<()> {
+        let data =
+            time.encode_clock().map_err(|x| {
+                Error::InvalidInput(x.to_string())
+            })?;
+        self.user_data(
+            FunctionGroup::Time,
+            SET_CLOCK,
+            UserDataItem::init(
+                ReturnCode::Success,
+                &data
+            )
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,2058 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    net::SocketAddr
+};
+
+use super::{
+    AlarmEvent, ConnectionState,
+    build_s7_read_frame, plan::plan_read,
+    security::set_password_frame,
+    user_data::user_data_response
+};
+use crate::{
+    Area, CotpNegotiation, Options,
+    build_copt_connect_request, build_s7_setup,
+    codec::S7Encoder, error::*
+};
+use bytes::BytesMut;
+use copt::{
+    CoptDecoder, CoptFrame, DisconnectReason,
+    Parameter, PduType
+};
+use log::{debug, warn};
+use s7_comm::{
+    AckData, AlarmKind, AlarmNotification,
+    BlockFile, CyclicData, DataItemVal, Frame,
+    FunctionGroup, Header, HearderAckData, Job,
+    ReturnCode, S7CommDecoder, UploadAckData,
+    UserDataItem, UserDataParameter,
+    UserDataType
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf}
+    },
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot, watch
+    },
+    time::{Instant, sleep_until, timeout}
+};
+use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktDecoder, TpktFrame};
+
+/// the cotp reference of the client
+const SOURCE_REF: [u8; 2] = [0, 1];
+
+/// bytes of a download block ack besides the
+/// part of the block: header, parameter and data
+/// head
+const DOWNLOAD_OVERHEAD: u16 = 18;
+
+/// Receives the items pushed by the plc for a
+/// cyclic read.
+pub(crate) type PushSender =
+    mpsc::Sender<Result<Vec<DataItemVal>>>;
+
+/// Receives the alarm notifications pushed by
+/// the plc.
+pub(crate) type AlarmSender =
+    mpsc::Sender<Result<AlarmEvent>>;
+
+/// A disconnection requested by a client handle,
+/// answered once the connection is released.
+pub(crate) type Disconnect =
+    oneshot::Sender<Result<()>>;
+
+/// The receiver of the pushes of a subscription.
+pub(crate) enum Subscriber {
+    Cyclic(PushSender),
+    Alarms(AlarmSender)
+}
+
+/// A job sent by a client handle to the
+/// connection task.
+pub(crate) struct Request {
+    pub frame:        Frame,
+    /// the areas of an idempotent read job, sent
+    /// again after reconnection if they still
+    /// fit the negotiated pdu length
+    pub retry:        Option<Vec<Area>>,
+    /// the block of a request download job,
+    /// served to the download block jobs of the
+    /// plc
+    pub block:        Option<Vec<u8>>,
+    /// the receiver of the pushes of a cyclic
+    /// read or of the alarms, registered if the
+    /// plc accepts the subscription
+    pub subscription: Option<Subscriber>,
+    pub response: oneshot::Sender<Result<Frame>>
+}
+
+pub(crate) struct Pending {
+    /// none for a keep alive probe
+    response:
+        Option<oneshot::Sender<Result<Frame>>>,
+    pub deadline: Instant,
+    /// the encoded job
+    job:          BytesMut,
+    /// the areas of a read job sent again after
+    /// reconnection
+    retry:        Option<Vec<Area>>,
+    /// the file and the block of a request
+    /// download job
+    download:     Option<(BlockFile, Vec<u8>)>,
+    /// the receiver of a subscription
+    subscription: Option<Subscriber>
+}
+
+/// A download accepted by the plc, which
+/// requests the parts of the block and then ends
+/// the download.
+struct Download {
+    /// the file named by the download jobs of
+    /// the plc
+    file:     BlockFile,
+    parts:    VecDeque<Vec<u8>>,
+    /// answered with the download ended job
+    response: oneshot::Sender<Result<Frame>>,
+    deadline: Instant
+}
+
+impl Pending {
+    pub(crate) fn fail(self, err: Error) {
+        if let Some(response) = self.response {
+            let _ = response.send(Err(err));
+        }
+    }
+}
+
+/// Owns the tcp stream of an established
+/// connection. Jobs are sent with unique pdu
+/// references, at most `max_amq_calling` of them
+/// unacknowledged, and each ack data is routed
+/// back to its job by the pdu reference.
+pub(crate) struct Connection {
+    /// the options with the values negotiated
+    /// by this connection
+    options:       Options,
+    reader:        OwnedReadHalf,
+    writer:        OwnedWriteHalf,
+    buf:           BytesMut,
+    decoder:
+        TpktDecoder<CoptDecoder<S7CommDecoder>>,
+    encoder:       S7Encoder,
+    pending:       HashMap<u16, Pending>,
+    /// the jobs resent after reconnection, sent
+    /// as the in-flight jobs allow
+    backlog:       VecDeque<Pending>,
+    /// the download in progress, a single one
+    /// for the connection
+    download:      Option<Download>,
+    /// the receivers of the cyclic reads, by job
+    /// id
+    subscriptions: HashMap<u8, PushSender>,
+    /// the receiver of the alarms, replaced by a
+    /// new subscription
+    alarms:        Option<AlarmSender>,
+    pdu_ref:       u16,
+    /// the cotp reference of the plc
+    plc_ref:       [u8; 2],
+    /// last time a frame was sent or received
+    last_io:       Instant
+}
+
+impl Connection {
+    /// connect to the plc and negotiate the
+    /// tpdu size, pdu length and max amq
+    /// requested by `options`, then set the
+    /// session password if any. The negotiated
+    /// values are kept by the connection, the
+    /// requested ones asked again at
+    /// reconnection.
+    pub(crate) async fn connect(
+        options: &Options
+    ) -> Result<Self> {
+        let mut options = options.clone();
+        let mut stream = timeout(
+            options.connect_timeout,
+            TcpStream::connect(SocketAddr::new(
+                options.address,
+                options.port
+            ))
+        )
+        .await
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                "connect timeout"
+            )
+        })??;
+        let plc_ref = copt_connect(
+            &mut stream,
+            &mut options
+        )
+        .await?;
+        s7_setup(&mut stream, &mut options)
+            .await?;
+        if let Some(password) = &options.password
+        {
+            login(
+                &mut stream,
+                &options,
+                password
+            )
+            .await?;
+        }
+        let (reader, writer) =
+            stream.into_split();
+        Ok(Self {
+            reader,
+            writer,
+            buf: BytesMut::new(),
+            decoder: TpktDecoder(
+                CoptDecoder::new(S7CommDecoder)
+            ),
+            encoder: S7Encoder::new(
+                options.tpdu_size
+            ),
+            pending: HashMap::new(),
+            backlog: VecDeque::new(),
+            download: None,
+            subscriptions: HashMap::new(),
+            alarms: None,
+            pdu_ref: options.tpdu_size.pdu_ref(),
+            plc_ref,
+            last_io: Instant::now(),
+            options
+        })
+    }
+
+    /// the options, with the values negotiated
+    /// by this connection
+    pub(crate) fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// serve the requests until all client
+    /// handles are dropped or one disconnects, or
+    /// return the error which broke the
+    /// connection
+    pub(crate) async fn run(
+        &mut self,
+        requests: &mut mpsc::Receiver<Request>,
+        disconnects: &mut mpsc::Receiver<
+            Disconnect
+        >
+    ) -> Result<()> {
+        let max_in_flight =
+            self.options.max_amq_calling.max(1)
+                as usize;
+        let keep_alive = self
+            .options
+            .reconnect
+            .as_ref()
+            .and_then(|x| x.keep_alive);
+        loop {
+            while self.pending.len()
+                < max_in_flight
+            {
+                let Some(pending) =
+                    self.backlog.pop_front()
+                else {
+                    break;
+                };
+                let pdu_ref = self.next_pdu_ref();
+                self.write_job(pdu_ref, pending)
+                    .await?;
+            }
+            let deadline = self
+                .pending
+                .values()
+                .map(|x| x.deadline)
+                .chain(
+                    self.download
+                        .as_ref()
+                        .map(|x| x.deadline)
+                )
+                .min();
+            let idle_deadline = self.last_io
+                + keep_alive.unwrap_or_default();
+            tokio::select! {
+                request = requests.recv(),
+                    if self.pending.len() < max_in_flight => {
+                    let Some(request) = request else {
+                        return Ok(());
+                    };
+                    self.send(request).await?;
+                },
+                Some(disconnect) = disconnects.recv() => {
+                    let rs = self.disconnect().await;
+                    self.close(&Error::ConnectionClosed(
+                        "disconnected by the client".to_string(),
+                    ));
+                    let _ = disconnect.send(rs);
+                    return Ok(());
+                },
+                rs = self.reader.read_buf(&mut self.buf) => {
+                    if rs? == 0 {
+                        return Err(Error::ConnectionClosed(
+                            "closed by the peer".to_string(),
+                        ));
+                    }
+                    self.dispatch().await?;
+                },
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)),
+                    if deadline.is_some() => {
+                    self.expire()?;
+                },
+                _ = sleep_until(idle_deadline),
+                    if keep_alive.is_some() && self.pending.is_empty() => {
+                    self.probe().await?;
+                },
+            }
+        }
+    }
+
+    async fn send(
+        &mut self,
+        request: Request
+    ) -> Result<()> {
+        let Request {
+            mut frame,
+            retry,
+            block,
+            subscription,
+            response
+        } = request;
+        let download = match (&frame, block) {
+            (
+                Frame::Job {
+                    job: Job::RequestDownload(job),
+                    ..
+                },
+                Some(block)
+            ) => Some((job.file(), block)),
+            _ => None
+        };
+        // a single download for the connection,
+        // whose parts are served to the download
+        // jobs of the plc naming its file
+        if download.is_some()
+            && (self.download.is_some()
+                || self.pending.values().any(
+                    |x| x.download.is_some()
+                ))
+        {
+            let _ = response.send(Err(
+                Error::DownloadInProgress
+            ));
+            return Ok(());
+        }
+        let pdu_ref = self.next_pdu_ref();
+        frame.set_pdu_ref(pdu_ref);
+        let mut dst = BytesMut::new();
+        if let Err(err) = self.encoder.encode(
+            TpktFrame::new(
+                CoptFrame::builder_of_dt_data(
+                    frame
+                )
+                .build(0, true)
+            ),
+            &mut dst
+        ) {
+            // nothing is written, the connection
+            // is still usable
+            let _ =
+                response.send(Err(err.into()));
+            return Ok(());
+        }
+        let retry = retry.filter(|_| {
+            self.options
+                .reconnect
+                .as_ref()
+                .is_some_and(|x| x.retry_reads)
+        });
+        self.write_job(
+            pdu_ref,
+            Pending {
+                response: Some(response),
+                deadline: Instant::now(),
+                job: dst,
+                retry,
+                download,
+                subscription
+            }
+        )
+        .await
+    }
+
+    /// read the probe area, to detect a broken
+    /// connection while idle
+    async fn probe(&mut self) -> Result<()> {
+        let Some(probe) = self
+            .options
+            .reconnect
+            .as_ref()
+            .map(|x| x.probe)
+        else {
+            return Ok(());
+        };
+        let pdu_ref = self.next_pdu_ref();
+        let mut frame =
+            build_s7_read_frame(&[probe]);
+        frame.set_pdu_ref(pdu_ref);
+        let mut dst = BytesMut::new();
+        self.encoder.encode(
+            TpktFrame::new(
+                CoptFrame::builder_of_dt_data(
+                    frame
+                )
+                .build(0, true)
+            ),
+            &mut dst
+        )?;
+        self.write_job(
+            pdu_ref,
+            Pending {
+                response:     None,
+                deadline:     Instant::now(),
+                job:          dst,
+                retry:        None,
+                download:     None,
+                subscription: None
+            }
+        )
+        .await
+    }
+
+    /// queue the reads which were in flight when
+    /// the previous connection broke, encoded
+    /// again for this connection. A read which
+    /// no longer fits the negotiated pdu length
+    /// fails, as one job can't be split.
+    pub(crate) fn resend(
+        &mut self,
+        jobs: Vec<Pending>
+    ) {
+        for mut pending in jobs {
+            let Some(areas) = &pending.retry
+            else {
+                continue;
+            };
+            match self.encode_read(areas) {
+                Ok(job) => {
+                    pending.job = job;
+                    self.backlog
+                        .push_back(pending);
+                },
+                Err(err) => pending.fail(err)
+            }
+        }
+    }
+
+    /// encode a read job of `areas`, if its
+    /// request and response fit the pdu length
+    fn encode_read(
+        &mut self,
+        areas: &[Area]
+    ) -> Result<BytesMut> {
+        let jobs = plan_read(
+            areas,
+            self.options.pdu_len
+        )?;
+        if jobs.len() != 1
+            || jobs[0].len() != areas.len()
+        {
+            return Err(Error::ConnectionClosed(
+                format!(
+                    "the read no longer fits \
+                     the pdu length {} \
+                     negotiated at reconnection",
+                    self.options.pdu_len
+                )
+            ));
+        }
+        let mut dst = BytesMut::new();
+        self.encoder.encode(
+            TpktFrame::new(
+                CoptFrame::builder_of_dt_data(
+                    build_s7_read_frame(areas)
+                )
+                .build(0, true)
+            ),
+            &mut dst
+        )?;
+        Ok(dst)
+    }
+
+    /// write the encoded job of `pending` with
+    /// `pdu_ref`, and wait for its ack data
+    /// until the read timeout. The job stays
+    /// pending if the write fails, to be handled
+    /// by `close`.
+    async fn write_job(
+        &mut self,
+        pdu_ref: u16,
+        mut pending: Pending
+    ) -> Result<()> {
+        // pdu reference in the s7 header, after
+        // tpkt and copt
+        pending.job[11..13].copy_from_slice(
+            &pdu_ref.to_be_bytes()
+        );
+        let job = pending.job.clone();
+        pending.deadline = Instant::now()
+            + self.options.read_timeout;
+        self.pending.insert(pdu_ref, pending);
+        // a partially written frame breaks the
+        // stream, so a timeout closes the
+        // connection
+        timeout(
+            self.options.write_timeout,
+            self.writer.write_all(&job)
+        )
+        .await
+        .map_err(|_| Error::WriteTimeout)??;
+        self.last_io = Instant::now();
+        Ok(())
+    }
+
+    /// send a disconnect request, and wait until
+    /// the plc confirms it or closes the
+    /// connection, as class 0 releases the
+    /// connection with the tcp one
+    async fn disconnect(&mut self) -> Result<()> {
+        let mut dst = BytesMut::new();
+        self.encoder.encode(
+            TpktFrame::new(
+                CoptFrame::disconnect_request(
+                    self.plc_ref,
+                    SOURCE_REF,
+                    DisconnectReason::Normal
+                )
+            ),
+            &mut dst
+        )?;
+        timeout(
+            self.options.write_timeout,
+            self.writer.write_all(&dst)
+        )
+        .await
+        .map_err(|_| Error::WriteTimeout)??;
+        let read_timeout =
+            self.options.read_timeout;
+        let released = async {
+            loop {
+                if self
+                    .reader
+                    .read_buf(&mut self.buf)
+                    .await?
+                    == 0
+                {
+                    return Ok(());
+                }
+                while let Some(frame) = self
+                    .decoder
+                    .decode(&mut self.buf)?
+                {
+                    if let PduType::DisconnectConfirm(
+                        _
+                    ) = frame.payload().pdu_type
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        timeout(read_timeout, released)
+            .await
+            .map_err(|_| Error::ReadTimeout)?
+    }
+
+    fn next_pdu_ref(&mut self) -> u16 {
+        loop {
+            self.pdu_ref =
+                self.pdu_ref.wrapping_add(1);
+            if !self
+                .pending
+                .contains_key(&self.pdu_ref)
+            {
+                return self.pdu_ref;
+            }
+        }
+    }
+
+    /// route the received ack data to their
+    /// jobs and the pushes to their cyclic reads,
+    /// and answer the jobs of the plc
+    async fn dispatch(&mut self) -> Result<()> {
+        self.last_io = Instant::now();
+        while let Some(frame) =
+            self.decoder.decode(&mut self.buf)?
+        {
+            let comm = match frame
+                .payload()
+                .pdu_type
+            {
+                PduType::DtData(comm) => comm,
+                PduType::DisconnectRequest(
+                    comm
+                ) => {
+                    return Err(
+                        Error::Disconnected(
+                            comm.reason
+                        )
+                    );
+                },
+                PduType::Error(comm) => {
+                    return Err(
+                        Error::CotpRejected(
+                            comm.cause
+                        )
+                    );
+                },
+                _ => {
+                    debug!(
+                        "ignore a non data tpdu"
+                    );
+                    continue;
+                }
+            };
+            let frame = comm.payload();
+            if let Frame::Job { header, job } =
+                frame
+            {
+                self.serve_download(header, job)
+                    .await?;
+                continue;
+            }
+            if let Frame::UserData {
+                parameter,
+                data,
+                ..
+            } = &frame
+            {
+                if parameter.ty
+                    == UserDataType::Push
+                {
+                    self.serve_push(
+                        parameter,
+                        data.as_ref()
+                    );
+                    continue;
+                }
+            }
+            let pdu_ref = frame.pdu_ref();
+            match self.pending.remove(&pdu_ref) {
+                Some(Pending {
+                    response: Some(response),
+                    download: Some((file, block)),
+                    ..
+                }) if download_accepted(
+                    &frame
+                ) =>
+                {
+                    let len = self
+                        .options
+                        .pdu_len
+                        .saturating_sub(
+                            DOWNLOAD_OVERHEAD
+                        )
+                        .max(1)
+                        as usize;
+                    self.download = Some(
+                        Download {
+                            file,
+                            parts: block
+                                .chunks(len)
+                                .map(|x| {
+                                    x.to_vec()
+                                })
+                                .collect(),
+                            response,
+                            deadline: Instant::now(
+                            )
+                                + self
+                                    .options
+                                    .read_timeout
+                        }
+                    );
+                },
+                Some(Pending {
+                    response: Some(response),
+                    subscription:
+                        Some(subscription),
+                    ..
+                }) => {
+                    self.subscribe(
+                        &frame,
+                        subscription
+                    );
+                    let _ =
+                        response.send(Ok(frame));
+                },
+                Some(Pending {
+                    response: Some(response),
+                    ..
+                }) => {
+                    let _ =
+                        response.send(Ok(frame));
+                },
+                Some(_) => {
+                    debug!(
+                        "keep alive probe acked"
+                    )
+                },
+                None => warn!(
+                    "recv a frame with unknown \
+                     pdu_ref {}: {:?}",
+                    pdu_ref, frame
+                )
+            }
+        }
+        Ok(())
+    }
+
+    /// register the receiver of a subscription
+    /// accepted by the plc, the first items of a
+    /// cyclic read sent before any push
+    fn subscribe(
+        &mut self,
+        frame: &Frame,
+        subscription: Subscriber
+    ) {
+        let Frame::UserData {
+            parameter,
+            data: Some(data),
+            ..
+        } = frame
+        else {
+            return;
+        };
+        if parameter
+            .data_unit
+            .is_some_and(|x| x.error_code != 0)
+            || data.return_code
+                != ReturnCode::Success
+        {
+            return;
+        }
+        let subscription = match subscription {
+            Subscriber::Cyclic(subscription) => {
+                subscription
+            },
+            Subscriber::Alarms(alarms) => {
+                self.alarms = Some(alarms);
+                return;
+            }
+        };
+        if let Ok(cyclic) =
+            CyclicData::decode(&data.data)
+        {
+            let _ = subscription
+                .try_send(Ok(cyclic.items));
+        }
+        self.subscriptions.insert(
+            parameter.sequence_number,
+            subscription
+        );
+    }
+
+    /// route a push of the plc to the receiver of
+    /// its cyclic read, or of the alarms. A push
+    /// is dropped if the receiver lags, and the
+    /// subscription forgotten if it is dropped.
+    fn serve_push(
+        &mut self,
+        parameter: &UserDataParameter,
+        data: Option<&UserDataItem>
+    ) {
+        match parameter.function_group {
+            FunctionGroup::CyclicData => {},
+            FunctionGroup::Cpu => {
+                return self.serve_alarm(
+                    parameter, data
+                );
+            },
+            _ => {
+                debug!(
+                    "ignore a push: {:?}",
+                    parameter
+                );
+                return;
+            }
+        }
+        let job_id = parameter.sequence_number;
+        let Some(subscription) =
+            self.subscriptions.get(&job_id)
+        else {
+            debug!(
+                "ignore a push of the unknown \
+                 cyclic read {}",
+                job_id
+            );
+            return;
+        };
+        let items = data
+            .map(|x| CyclicData::decode(&x.data))
+            .unwrap_or_else(|| {
+                CyclicData::decode(&[])
+            })
+            .map(|x| x.items)
+            .map_err(|err| {
+                Error::InvalidPlcAnswer(
+                    err.to_string()
+                )
+            });
+        match subscription.try_send(items) {
+            Ok(()) => {},
+            Err(TrySendError::Full(_)) => warn!(
+                "cyclic read {} lags, a push \
+                 dropped",
+                job_id
+            ),
+            Err(TrySendError::Closed(_)) => {
+                self.subscriptions
+                    .remove(&job_id);
+            }
+        }
+    }
+
+    /// route an alarm notification of the plc to
+    /// the receiver of the alarms
+    fn serve_alarm(
+        &mut self,
+        parameter: &UserDataParameter,
+        data: Option<&UserDataItem>
+    ) {
+        let Some(alarms) = self.alarms.as_ref()
+        else {
+            debug!(
+                "ignore an alarm without \
+                 subscription: {:?}",
+                parameter
+            );
+            return;
+        };
+        let kind = AlarmKind::from(
+            parameter.subfunction
+        );
+        let event = data
+            .map(|x| {
+                AlarmNotification::decode(&x.data)
+            })
+            .unwrap_or_else(|| {
+                AlarmNotification::decode(&[])
+            })
+            .map(|x| AlarmEvent {
+                kind,
+                timestamp: x.timestamp,
+                messages: x.messages
+            })
+            .map_err(|err| {
+                Error::InvalidPlcAnswer(
+                    err.to_string()
+                )
+            });
+        match alarms.try_send(event) {
+            Ok(()) => {},
+            Err(TrySendError::Full(_)) => warn!(
+                "alarms lag, a notification \
+                 dropped"
+            ),
+            Err(TrySendError::Closed(_)) => {
+                self.alarms = None;
+            }
+        }
+    }
+
+    /// answer a download block job of the plc
+    /// with the next part of the block, or the
+    /// download ended job, which completes the
+    /// download
+    async fn serve_download(
+        &mut self,
+        header: Header,
+        job: Job
+    ) -> Result<()> {
+        let file = match &job {
+            Job::DownloadBlock(x)
+            | Job::DownloadEnded(x) => x.file(),
+            _ => {
+                warn!(
+                    "ignore a job of the plc: \
+                     {:?}",
+                    job
+                );
+                return Ok(());
+            }
+        };
+        let Some(download) = self
+            .download
+            .as_mut()
+            .filter(|x| x.file == file)
+        else {
+            warn!(
+                "recv a job without download: \
+                 {:?}",
+                job
+            );
+            return Ok(());
+        };
+        download.deadline = Instant::now()
+            + self.options.read_timeout;
+        let ack_data =
+            if let Job::DownloadBlock(_) = job {
+                let part = download
+                    .parts
+                    .pop_front()
+                    .unwrap_or_default();
+                AckData::DownloadBlock(
+                    UploadAckData::init(
+                        !download
+                            .parts
+                            .is_empty(),
+                        &part
+                    )
+                )
+            } else {
+                AckData::DownloadEnded
+            };
+        let (parameter_len, data_len) =
+            match &ack_data {
+                AckData::DownloadBlock(data) => (
+                    data.bytes_len_parameter(),
+                    data.bytes_len_data()
+                ),
+                _ => (1, 0)
+            };
+        let mut dst = BytesMut::new();
+        self.encoder.encode(
+            TpktFrame::new(
+                CoptFrame::builder_of_dt_data(
+                    Frame::AckData {
+                        header:
+                            HearderAckData::init(
+                                header.pdu_ref,
+                                parameter_len,
+                                data_len,
+                                0,
+                                0
+                            ),
+                        ack_data
+                    }
+                )
+                .build(0, true)
+            ),
+            &mut dst
+        )?;
+        timeout(
+            self.options.write_timeout,
+            self.writer.write_all(&dst)
+        )
+        .await
+        .map_err(|_| Error::WriteTimeout)??;
+        self.last_io = Instant::now();
+        if let Job::DownloadEnded(_) = job {
+            if let Some(download) =
+                self.download.take()
+            {
+                let _ = download.response.send(
+                    Ok(Frame::Job {
+                        header,
+                        job
+                    })
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// fail the jobs whose ack data is overdue.
+    /// An overdue keep alive probe breaks the
+    /// connection.
+    fn expire(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let expired: Vec<u16> = self
+            .pending
+            .iter()
+            .filter(|(_, x)| x.deadline <= now)
+            .map(|(pdu_ref, _)| *pdu_ref)
+            .collect();
+        if self
+            .download
+            .as_ref()
+            .is_some_and(|x| x.deadline <= now)
+        {
+            if let Some(download) =
+                self.download.take()
+            {
+                let _ = download.response.send(
+                    Err(Error::ReadTimeout)
+                );
+            }
+        }
+        let mut probe_expired = false;
+        for pdu_ref in expired {
+            if let Some(pending) =
+                self.pending.remove(&pdu_ref)
+            {
+                probe_expired |=
+                    pending.response.is_none();
+                pending.fail(Error::ReadTimeout);
+            }
+        }
+        if probe_expired {
+            return Err(Error::ConnectionClosed(
+                "keep alive probe timed out"
+                    .to_string()
+            ));
+        }
+        Ok(())
+    }
+
+    /// fail the unacknowledged jobs, except the
+    /// ones which can be sent again after
+    /// reconnection, which are returned
+    pub(crate) fn close(
+        &mut self,
+        err: &Error
+    ) -> Vec<Pending> {
+        if let Some(download) =
+            self.download.take()
+        {
+            let _ = download.response.send(Err(
+                Error::ConnectionClosed(
+                    err.to_string()
+                )
+            ));
+        }
+        // the cyclic reads and the alarms end
+        // with the connection
+        for (_, subscription) in
+            self.subscriptions.drain()
+        {
+            let _ = subscription.try_send(Err(
+                Error::ConnectionClosed(
+                    err.to_string()
+                )
+            ));
+        }
+        if let Some(alarms) = self.alarms.take() {
+            let _ = alarms.try_send(Err(
+                Error::ConnectionClosed(
+                    err.to_string()
+                )
+            ));
+        }
+        let mut retry = Vec::new();
+        let pending = self
+            .pending
+            .drain()
+            .map(|(_, x)| x)
+            .chain(self.backlog.drain(..));
+        for pending in pending {
+            if pending.response.is_none() {
+                continue;
+            }
+            if pending.retry.is_some() {
+                retry.push(pending);
+            } else {
+                pending.fail(
+                    Error::ConnectionClosed(
+                        err.to_string()
+                    )
+                );
+            }
+        }
+        retry
+    }
+}
+
+/// the ack data of a request download job
+/// accepted by the plc
+fn download_accepted(frame: &Frame) -> bool {
+    matches!(
+        frame,
+        Frame::AckData {
+            header,
+            ack_data: AckData::RequestDownload
+        } if header.error_class() == 0
+            && header.error_code() == 0
+    )
+}
+
+/// Serve the requests with `connection`, and
+/// reconnect when it breaks if
+/// `options.reconnect` is set, until a client
+/// handle disconnects.
+pub(crate) async fn serve(
+    options: Options,
+    mut connection: Connection,
+    mut requests: mpsc::Receiver<Request>,
+    mut disconnects: mpsc::Receiver<Disconnect>,
+    state: watch::Sender<ConnectionState>,
+    negotiated: watch::Sender<Options>
+) {
+    loop {
+        let err = match connection
+            .run(&mut requests, &mut disconnects)
+            .await
+        {
+            Ok(()) => break,
+            Err(err) => err
+        };
+        warn!("connection broken: {}", err);
+        let mut retry = connection.close(&err);
+        let Some(reconnect) =
+            options.reconnect.clone()
+        else {
+            for pending in retry {
+                pending.fail(
+                    Error::ConnectionClosed(
+                        err.to_string()
+                    )
+                );
+            }
+            break;
+        };
+        let _ = state
+            .send(ConnectionState::Reconnecting);
+        let Some(new_connection) = reconnect
+            .reconnect(
+                &options,
+                &mut requests,
+                &mut disconnects,
+                &mut retry
+            )
+            .await
+        else {
+            break;
+        };
+        connection = new_connection;
+        // the handles plan their jobs from the
+        // values negotiated again
+        let _ = negotiated
+            .send(connection.options().clone());
+        let _ = state
+            .send(ConnectionState::Connected);
+        connection.resend(retry);
+    }
+    let _ = state.send(ConnectionState::Closed);
+}
+
+/// connect the cotp connection, and return the
+/// reference of the plc
+async fn copt_connect(
+    stream: &mut TcpStream,
+    options: &mut Options
+) -> Result<[u8; 2]> {
+    let frame = build_copt_connect_request()
+        .source_ref(SOURCE_REF)
+        .destination_ref([0, 0])
+        .class_and_others(0, false, false)
+        .pdu_size(options.tpdu_size)
+        .src_tsap(options.conn_mode.local_tsap())
+        .dst_tsap(options.conn_mode.remote_tsap())
+        .build_to_request()?;
+    write_frame(stream, options, frame).await?;
+    let frame = read_frame(stream, options)
+        .await?
+        .payload();
+    match frame.pdu_type {
+        PduType::ConnectConfirm(comm) => {
+            debug!("{:?}", comm);
+            let mut cotp = CotpNegotiation {
+                tpdu_size:   options.tpdu_size,
+                local_tsap:  options
+                    .conn_mode
+                    .local_tsap()
+                    .to_vec(),
+                remote_tsap: options
+                    .conn_mode
+                    .remote_tsap()
+                    .to_vec(),
+                local_ref:   SOURCE_REF,
+                remote_ref:  comm.source_ref,
+                class:       comm.class,
+                parameters:  Vec::new()
+            };
+            for parameter in comm.parameters {
+                match parameter {
+                    Parameter::TpduSize(size) => {
+                        cotp.tpdu_size = size
+                    },
+                    Parameter::SrcTsap(tsap) => {
+                        cotp.local_tsap = tsap
+                    },
+                    Parameter::DstTsap(tsap) => {
+                        cotp.remote_tsap = tsap
+                    },
+                    parameter => cotp
+                        .parameters
+                        .push(parameter)
+                }
+            }
+            options.tpdu_size = cotp.tpdu_size;
+            let remote_ref = cotp.remote_ref;
+            options.cotp = Some(cotp);
+            Ok(remote_ref)
+        },
+        // the connect request refused
+        PduType::DisconnectRequest(comm) => {
+            Err(Error::Disconnected(comm.reason))
+        },
+        PduType::Error(comm) => {
+            Err(Error::CotpRejected(comm.cause))
+        },
+        pdu_type => Err(Error::Cotp(format!(
+            "should recv connect confirm, but \
+             not {:?}",
+            pdu_type
+        )))
+    }
+}
+
+async fn s7_setup(
+    stream: &mut TcpStream,
+    options: &mut Options
+) -> Result<()> {
+    let frame = build_s7_setup()
+        .max_amq_called(options.max_amq_called)
+        .max_amq_calling(options.max_amq_calling)
+        .pdu_length(options.pdu_len)
+        .pdu_ref(options.tpdu_size.pdu_ref())
+        .build()?;
+    write_frame(stream, options, frame).await?;
+    let frame = read_frame(stream, options)
+        .await?
+        .payload();
+    let payload = match frame.pdu_type {
+        PduType::DtData(comm) => comm.payload(),
+        pdu_type => {
+            return Err(Error::InvalidPlcAnswer(
+                format!(
+                    "should recv setup \
+                     communication, but not {:?}",
+                    pdu_type
+                )
+            ));
+        }
+    };
+    let Frame::AckData { header, ack_data } =
+        payload
+    else {
+        return Err(Error::InvalidPlcAnswer(
+            format!(
+                "should recv setup \
+                 communication, but not {:?}",
+                payload
+            )
+        ));
+    };
+    if let Some(err) = Error::from_code(&header) {
+        return Err(err);
+    }
+    let AckData::SetupCommunication(data) =
+        ack_data
+    else {
+        return Err(Error::InvalidPlcAnswer(
+            format!(
+                "should recv setup \
+                 communication, but not {:?}",
+                ack_data
+            )
+        ));
+    };
+    debug!("{:?}", data);
+    options.pdu_len = data.pdu_length();
+    options.max_amq_calling =
+        data.max_amq_calling();
+    options.max_amq_called =
+        data.max_amq_called();
+    Ok(())
+}
+
+async fn login(
+    stream: &mut TcpStream,
+    options: &Options,
+    password: &str
+) -> Result<()> {
+    let mut dst = BytesMut::new();
+    S7Encoder::default().encode(
+        TpktFrame::new(
+            CoptFrame::builder_of_dt_data(
+                set_password_frame(password)?
+            )
+            .build(0, true)
+        ),
+        &mut dst
+    )?;
+    write_frame(stream, options, dst).await?;
+    let frame = read_frame(stream, options)
+        .await?
+        .payload();
+    let PduType::DtData(comm) = frame.pdu_type
+    else {
+        return Err(Error::InvalidPlcAnswer(
+            format!(
+                "should recv set password, but \
+                 not {:?}",
+                frame
+            )
+        ));
+    };
+    user_data_response(comm.payload())?;
+    Ok(())
+}
+
+async fn write_frame(
+    stream: &mut TcpStream,
+    options: &Options,
+    framed: BytesMut
+) -> Result<()> {
+    timeout(
+        options.write_timeout,
+        stream.write_all(&framed)
+    )
+    .await
+    .map_err(|_| Error::WriteTimeout)??;
+    Ok(())
+}
+
+async fn read_frame(
+    stream: &mut TcpStream,
+    options: &Options
+) -> Result<TpktFrame<CoptFrame<Frame>>> {
+    timeout(
+        options.read_timeout,
+        read_framed(stream)
+    )
+    .await
+    .map_err(|_| Error::ReadTimeout)?
+}
+
+async fn read_framed(
+    req: &mut TcpStream
+) -> Result<TpktFrame<CoptFrame<Frame>>> {
+    let mut bytes = BytesMut::new();
+    let mut decoder = TpktDecoder(
+        CoptDecoder::new(S7CommDecoder)
+    );
+    loop {
+        if req.read_buf(&mut bytes).await? == 0 {
+            return Err(Error::ConnectionClosed(
+                "closed by the peer".to_string()
+            ));
+        }
+        if let Some(frame) =
+            decoder.decode(&mut bytes)?
+        {
+            return Ok(frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        super::user_data::MAX_FRAGMENTS,
+        read_framed
+    };
+    use crate::{
+        Area, ConnectMode, ConnectionState,
+        ConnectionType, DataSizeType, Error,
+        Options, Reconnect, S7Client,
+        build_s7_read, codec::S7Encoder
+    };
+    use bytes::BytesMut;
+    use copt::{
+        CoptFrame, DisconnectReason, PduType
+    };
+    use s7_comm::{
+        AckData, BlockFile, BlockType,
+        DataItemVal, DataUnit, FileSystem, Frame,
+        FunctionGroup, HearderAckData, Job,
+        ReadVarAckData, ReturnCode,
+        StartUploadAckData, UploadAckData,
+        UserDataItem
+    };
+    use std::{net::SocketAddr, time::Duration};
+    use tokio::{
+        io::AsyncWriteExt,
+        net::{TcpListener, TcpStream},
+        sync::oneshot
+    };
+    use tokio_util::codec::Encoder;
+    use tpkt::TpktFrame;
+
+    /// connect confirm with tpdu size 1024
+    const CONNECT_CONFIRM: [u8; 22] = [
+        0x03, 0x00, 0x00, 0x16, 0x11, 0xd0, 0x00,
+        0x01, 0x00, 0x01, 0x00, 0xc0, 0x01, 0x0a,
+        0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x01,
+        0x02
+    ];
+    /// setup communication ack with max amq 2 and
+    /// pdu length 240
+    const SETUP_ACK: [u8; 27] = [
+        0x03, 0x00, 0x00, 0x1b, 0x02, 0xf0, 0x80,
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x08, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x00,
+        0x00, 0x02, 0x00, 0x02, 0x00, 0xf0
+    ];
+
+    async fn read_job(
+        stream: &mut TcpStream
+    ) -> Frame {
+        let frame = read_framed(stream)
+            .await
+            .unwrap()
+            .payload();
+        let PduType::DtData(comm) =
+            frame.pdu_type
+        else {
+            panic!("should recv dt data");
+        };
+        comm.payload()
+    }
+
+    /// answer each item of a read job with its
+    /// item count
+    fn read_ack(job: Frame) -> BytesMut {
+        let Frame::Job {
+            header,
+            job: Job::ReadVar(job)
+        } = job
+        else {
+            panic!("should recv read var");
+        };
+        let count =
+            (job.bytes_len_parameter() - 2) / 12;
+        let mut ack_data =
+            ReadVarAckData::default();
+        for _ in 0..count {
+            ack_data = ack_data.add_response(
+                DataItemVal::init_with_bytes(
+                    ReturnCode::Success,
+                    &[count as u8]
+                )
+            );
+        }
+        let data_len = ack_data.bytes_len_data();
+        encode_ack(
+            header.pdu_ref,
+            2,
+            data_len,
+            AckData::ReadVar(ack_data)
+        )
+    }
+
+    /// the ack data frame answering the job of
+    /// `pdu_ref`
+    fn encode_ack(
+        pdu_ref: u16,
+        parameter_len: u16,
+        data_len: u16,
+        ack_data: AckData
+    ) -> BytesMut {
+        let frame = Frame::AckData {
+            header: HearderAckData::init(
+                pdu_ref,
+                parameter_len,
+                data_len,
+                0,
+                0
+            ),
+            ack_data
+        };
+        encode(frame)
+    }
+
+    fn encode(frame: Frame) -> BytesMut {
+        let mut dst = BytesMut::new();
+        S7Encoder::default()
+            .encode(
+                TpktFrame::new(
+                    CoptFrame::builder_of_dt_data(frame)
+                        .build(0, true),
+                ),
+                &mut dst,
+            )
+            .unwrap();
+        dst
+    }
+
+    /// accept a connection and answer the
+    /// connect request and setup communication
+    async fn accept(
+        listener: &TcpListener
+    ) -> TcpStream {
+        accept_pdu_len(listener, 240).await
+    }
+
+    /// accept a connection, agreeing `pdu_len`
+    /// at setup communication
+    async fn accept_pdu_len(
+        listener: &TcpListener,
+        pdu_len: u16
+    ) -> TcpStream {
+        let (mut stream, _) =
+            listener.accept().await.unwrap();
+        read_framed(&mut stream).await.unwrap();
+        stream
+            .write_all(&CONNECT_CONFIRM)
+            .await
+            .unwrap();
+        let Frame::Job {
+            job: Job::SetupCommunication(setup),
+            ..
+        } = read_job(&mut stream).await
+        else {
+            panic!(
+                "should recv setup communication"
+            );
+        };
+        // the requested length, also at
+        // reconnection
+        assert_eq!(setup.pdu_length(), 480);
+        let mut setup_ack = SETUP_ACK;
+        setup_ack[25..].copy_from_slice(
+            &pdu_len.to_be_bytes()
+        );
+        stream
+            .write_all(&setup_ack)
+            .await
+            .unwrap();
+        stream
+    }
+
+    fn options(addr: SocketAddr) -> Options {
+        Options::new(
+            addr.ip(),
+            addr.port(),
+            ConnectMode::init_tsap(
+                ConnectionType::PG,
+                0x0100,
+                0x0102
+            )
+        )
+    }
+
+    fn db_byte(addr: u16) -> Area {
+        Area::DataBausteine(
+            1,
+            DataSizeType::Byte { addr, len: 1 }
+        )
+    }
+
+    #[tokio::test]
+    async fn check_pipelined_jobs() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut stream =
+                accept(&listener).await;
+            // both jobs are in flight before the
+            // first ack, which answers the last
+            // job
+            let first =
+                read_job(&mut stream).await;
+            let second =
+                read_job(&mut stream).await;
+            assert_ne!(
+                first.pdu_ref(),
+                second.pdu_ref()
+            );
+            stream
+                .write_all(&read_ack(second))
+                .await
+                .unwrap();
+            stream
+                .write_all(&read_ack(first))
+                .await
+                .unwrap();
+        });
+
+        let client =
+            S7Client::connect(options(addr))
+                .await
+                .unwrap();
+        assert_eq!(
+            client.options().max_amq_calling,
+            2
+        );
+        assert_eq!(
+            client.options().pdu_len(),
+            240
+        );
+        let other = client.clone();
+        let (one, areas) = (
+            db_byte(0),
+            [db_byte(1), db_byte(2)]
+        );
+        let (one, two) = tokio::join!(
+            client.read(&one),
+            other.read_vec(&areas)
+        );
+        assert_eq!(one.unwrap().data, vec![1]);
+        let two = two.unwrap();
+        assert_eq!(two.len(), 2);
+        assert_eq!(two[1].data, vec![2]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_reconnect() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (reconnect, reconnecting) =
+            oneshot::channel();
+        let server = tokio::spawn(async move {
+            // drop the connection with a read in
+            // flight
+            let mut stream =
+                accept(&listener).await;
+            read_job(&mut stream).await;
+            drop(stream);
+            reconnecting.await.unwrap();
+            // a pdu length of a single item read
+            // job
+            let mut stream =
+                accept_pdu_len(&listener, 30)
+                    .await;
+            for _ in 0..3 {
+                let job =
+                    read_job(&mut stream).await;
+                stream
+                    .write_all(&read_ack(job))
+                    .await
+                    .unwrap();
+            }
+            // keep the connection until the
+            // client is dropped
+            let _ =
+                read_framed(&mut stream).await;
+        });
+
+        let mut options = options(addr);
+        options.read_timeout =
+            Duration::from_secs(2);
+        options.reconnect = Some(Reconnect {
+            keep_alive: None,
+            ..Default::default()
+        });
+        let client = S7Client::connect(options)
+            .await
+            .unwrap();
+        let mut state = client.subscribe_state();
+        state.borrow_and_update();
+        let read = tokio::spawn({
+            let client = client.clone();
+            async move {
+                client.read(&db_byte(0)).await
+            }
+        });
+        state.changed().await.unwrap();
+        assert_eq!(
+            *state.borrow_and_update(),
+            ConnectionState::Reconnecting
+        );
+        reconnect.send(()).unwrap();
+        let item = read.await.unwrap().unwrap();
+        assert_eq!(item.data, vec![1]);
+        assert_eq!(
+            *state.borrow_and_update(),
+            ConnectionState::Connected
+        );
+        // planned from the pdu length agreed at
+        // the reconnection, a job for each item
+        assert_eq!(
+            client.options().pdu_len(),
+            30
+        );
+        let items = client
+            .read_vec(&[db_byte(1), db_byte(2)])
+            .await
+            .unwrap();
+        assert_eq!(items[0].data, vec![1]);
+        assert_eq!(items[1].data, vec![1]);
+        drop(client);
+        state.changed().await.unwrap();
+        assert_eq!(
+            *state.borrow(),
+            ConnectionState::Closed
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_resend_after_smaller_pdu() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // drop the connection with both reads
+            // in flight
+            let mut stream =
+                accept(&listener).await;
+            read_job(&mut stream).await;
+            read_job(&mut stream).await;
+            drop(stream);
+            // only the single item read fits, and
+            // is sent again
+            let mut stream =
+                accept_pdu_len(&listener, 30)
+                    .await;
+            let job = read_job(&mut stream).await;
+            stream
+                .write_all(&read_ack(job))
+                .await
+                .unwrap();
+            let _ =
+                read_framed(&mut stream).await;
+        });
+
+        let mut options = options(addr);
+        options.read_timeout =
+            Duration::from_secs(2);
+        options.reconnect = Some(Reconnect {
+            keep_alive: None,
+            ..Default::default()
+        });
+        let client = S7Client::connect(options)
+            .await
+            .unwrap();
+        let other = client.clone();
+        let (one, areas) = (
+            db_byte(0),
+            [db_byte(1), db_byte(2)]
+        );
+        let (one, two) = tokio::join!(
+            client.read(&one),
+            other.read_vec(&areas)
+        );
+        assert_eq!(one.unwrap().data, vec![1]);
+        assert!(matches!(
+            two,
+            Err(Error::ConnectionClosed(_))
+        ));
+        drop((client, other));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_refused_connect() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) =
+                listener.accept().await.unwrap();
+            read_framed(&mut stream)
+                .await
+                .unwrap();
+            // disconnect request, the tsap not
+            // reachable
+            stream
+                .write_all(&[
+                    0x03, 0x00, 0x00, 0x0b, 0x06,
+                    0x80, 0x00, 0x01, 0x00, 0x0c,
+                    0x02
+                ])
+                .await
+                .unwrap();
+        });
+
+        let Err(err) =
+            S7Client::connect(options(addr))
+                .await
+        else {
+            panic!("should be refused");
+        };
+        assert!(matches!(
+            err,
+            Error::Disconnected(
+                DisconnectReason::NotAttachedToTsap
+            )
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Disconnected: tsap not reachable"
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_single_download() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (requested, request_download) =
+            oneshot::channel();
+        let server = tokio::spawn(async move {
+            let mut stream =
+                accept(&listener).await;
+            let job = read_job(&mut stream).await;
+            requested.send(job).unwrap();
+            // keep the download requested until
+            // the client is dropped
+            let _ =
+                read_framed(&mut stream).await;
+        });
+
+        let client =
+            S7Client::connect(options(addr))
+                .await
+                .unwrap();
+        let frame = |number| {
+            Frame::job_download(0)
+                .request_download(
+                    BlockFile::init(
+                        BlockType::DB,
+                        number,
+                        FileSystem::Passive
+                    ),
+                    100,
+                    10
+                )
+                .build()
+        };
+        let first = tokio::spawn({
+            let client = client.clone();
+            let frame = frame(1);
+            async move {
+                client
+                    .download(frame, vec![0; 100])
+                    .await
+            }
+        });
+        assert!(matches!(
+            request_download.await.unwrap(),
+            Frame::Job {
+                job: Job::RequestDownload(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            client
+                .download(frame(2), vec![1; 100])
+                .await,
+            Err(Error::DownloadInProgress)
+        ));
+        drop(client);
+        assert!(first.await.unwrap().is_err());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_upload_beyond_block_len() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut stream =
+                accept(&listener).await;
+            // a block of 4 bytes, uploaded in
+            // parts of 4 bytes which never end
+            let job = read_job(&mut stream).await;
+            let start =
+                StartUploadAckData::init(7, 4);
+            stream
+                .write_all(&encode_ack(
+                    job.pdu_ref(),
+                    start.bytes_len_parameter(),
+                    0,
+                    AckData::StartUpload(start)
+                ))
+                .await
+                .unwrap();
+            for _ in 0..2 {
+                let job =
+                    read_job(&mut stream).await;
+                let part = UploadAckData::init(
+                    true,
+                    &[1, 2, 3, 4]
+                );
+                let (parameter_len, data_len) = (
+                    part.bytes_len_parameter(),
+                    part.bytes_len_data()
+                );
+                stream
+                    .write_all(&encode_ack(
+                        job.pdu_ref(),
+                        parameter_len,
+                        data_len,
+                        AckData::Upload(part)
+                    ))
+                    .await
+                    .unwrap();
+            }
+            // the end upload, then the client is
+            // dropped
+            let job = read_job(&mut stream).await;
+            assert!(matches!(
+                job,
+                Frame::Job {
+                    job: Job::EndUpload(_),
+                    ..
+                }
+            ));
+        });
+
+        let client =
+            S7Client::connect(options(addr))
+                .await
+                .unwrap();
+        assert!(matches!(
+            client
+                .upload_block(BlockType::DB, 1)
+                .await,
+            Err(Error::InvalidPlcAnswer(_))
+        ));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_endless_fragments() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut stream =
+                accept(&listener).await;
+            // each fragment followed by another,
+            // until the client gives up
+            let mut requests = 0;
+            while let Ok(frame) =
+                read_framed(&mut stream).await
+            {
+                let PduType::DtData(comm) =
+                    frame.payload().pdu_type
+                else {
+                    panic!("should recv dt data");
+                };
+                let request = comm.payload();
+                requests += 1;
+                let fragment = Frame::user_data(
+                    request.pdu_ref()
+                )
+                .response(FunctionGroup::Block, 2)
+                .data_unit(DataUnit {
+                    reference:  1,
+                    last:       false,
+                    error_code: 0
+                })
+                .data(UserDataItem::init(
+                    ReturnCode::Success,
+                    &[0x30, 0x41, 0x00, 0x01]
+                ))
+                .build();
+                stream
+                    .write_all(&encode(fragment))
+                    .await
+                    .unwrap();
+            }
+            requests
+        });
+
+        let client =
+            S7Client::connect(options(addr))
+                .await
+                .unwrap();
+        assert!(matches!(
+            client
+                .list_blocks(BlockType::DB)
+                .await,
+            Err(Error::InvalidPlcAnswer(_))
+        ));
+        drop(client);
+        assert_eq!(
+            server.await.unwrap(),
+            MAX_FRAGMENTS
+        );
+    }
+
+    #[tokio::test]
+    async fn check_refused_setup() {
+        let listener =
+            TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap();
+        let addr = listener.local_addr().unwrap();
+        // a setup ack with an error class, then a
+        // read var ack instead of the setup ack
+        let mut refused = SETUP_ACK.to_vec();
+        refused[17] = 0x85;
+        let other = read_ack(
+            build_s7_read()
+                .add_item(db_byte(0).into())
+                .build_frame()
+        )
+        .to_vec();
+        let server = tokio::spawn(async move {
+            for ack in [refused, other] {
+                let (mut stream, _) = listener
+                    .accept()
+                    .await
+                    .unwrap();
+                read_framed(&mut stream)
+                    .await
+                    .unwrap();
+                stream
+                    .write_all(&CONNECT_CONFIRM)
+                    .await
+                    .unwrap();
+                read_framed(&mut stream)
+                    .await
+                    .unwrap();
+                stream
+                    .write_all(&ack)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let Err(err) =
+            S7Client::connect(options(addr))
+                .await
+        else {
+            panic!("should be refused");
+        };
+        assert!(matches!(
+            err,
+            Error::Protocol(_)
+        ));
+        let Err(err) =
+            S7Client::connect(options(addr))
+                .await
+        else {
+            panic!("should be refused");
+        };
+        assert!(matches!(
+            err,
+            Error::InvalidPlcAnswer(_)
+        ));
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+use super::{S7Client, security::check_password};
+use crate::{CpuMode, Error, Result};
+use s7_comm::{
+    ALREADY_RUN, ALREADY_STOP, AckData, Frame,
+    GARB, MODU, P_PROGRAM
+};
+
+impl S7Client {
+    /// start the plc, keeping the process image
+    /// and the data blocks
+    pub async fn plc_hot_start(
+        &self
+    ) -> Result<()> {
+        self.plc_start(&[]).await
+    }
+
+    /// start the plc, resetting the non retentive
+    /// memory
+    pub async fn plc_cold_start(
+        &self
+    ) -> Result<()> {
+        self.plc_start(b"C ").await
+    }
+
+    async fn plc_start(
+        &self,
+        parameter: &[u8]
+    ) -> Result<()> {
+        let frame = Frame::job_pi_service(0)
+            .service(P_PROGRAM)
+            .parameter(parameter)
+            .build();
+        match plc_control_ack(
+            self.request(frame).await?
+        )? {
+            None => Ok(()),
+            Some(Some(ALREADY_RUN)) => {
+                Err(Error::AlreadyRun)
+            },
+            Some(_) => Err(Error::CannotStartPlc)
+        }
+    }
+
+    pub async fn plc_stop(&self) -> Result<()> {
+        let frame = Frame::job_plc_stop(0)
+            .service(P_PROGRAM)
+            .build();
+        match plc_control_ack(
+            self.request(frame).await?
+        )? {
+            None => Ok(()),
+            Some(Some(ALREADY_STOP)) => {
+                Err(Error::AlreadyStop)
+            },
+            Some(_) => Err(Error::CannotStopPlc)
+        }
+    }
+
+    /// copy the ram to the rom, the plc in stop
+    pub async fn copy_ram_to_rom(
+        &self
+    ) -> Result<()> {
+        let frame = Frame::job_pi_service(0)
+            .service(MODU)
+            .parameter(b"EP")
+            .build();
+        match plc_control_ack(
+            self.request(frame).await?
+        )? {
+            None => Ok(()),
+            Some(_) => {
+                Err(Error::CannotCopyRamToRom)
+            },
+        }
+    }
+
+    /// compress the memory of the plc
+    pub async fn compress(&self) -> Result<()> {
+        let frame = Frame::job_pi_service(0)
+            .service(GARB)
+            .build();
+        match plc_control_ack(
+            self.request(frame).await?
+        )? {
+            None => Ok(()),
+            Some(_) => Err(Error::CannotCompress)
+        }
+    }
+
+    /// the operating mode of the cpu, from the
+    /// szl 0x0424
+    pub async fn cpu_status(
+        &self
+    ) -> Result<CpuMode> {
+        Ok(self.read_cpu_state().await?.mode)
+    }
+}
+
+/// `None` for an accepted job, or the state of
+/// the refused job
+fn plc_control_ack(
+    frame: Frame
+) -> Result<Option<Option<u8>>> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data:
+                AckData::PiService(data)
+                | AckData::PlcStop(data)
+        } => {
+            check_password(&header)?;
+            if header.error_class() == 0
+                && header.error_code() == 0
+            {
+                Ok(None)
+            } else {
+                Ok(Some(data.state()))
+            }
+        },
+        frame => {
+            Err(Error::InvalidPlcAnswer(format!(
+                "should recv plc control, but \
+                 not {:?}",
+                frame
+            )))
+        },
+    }
+}
@@ -0,0 +1,146 @@
+use super::{
+    S7Client,
+    connection::Subscriber,
+    subscription::{Cancel, Pushes},
+    user_data::user_data_response
+};
+use crate::{Error, Result};
+use futures_core::Stream;
+use s7_comm::{
+    CYCLIC_UNSUBSCRIBE, CyclicInterval,
+    DataItemVal, Frame, FunctionGroup,
+    ItemRequest, ReturnCode, UserDataItem,
+    encode_unsubscribe
+};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration
+};
+use tokio::sync::mpsc;
+
+/// pushes buffered for a slow receiver, the
+/// later ones dropped meanwhile
+const PUSH_CAPACITY: usize = 64;
+/// bytes of a cyclic read request besides its
+/// items: header, parameter, data head, count
+/// and interval
+const CYCLIC_OVERHEAD: u16 = 26;
+/// bytes of an item of a cyclic read request
+const ITEM_LEN: u16 = 12;
+
+/// The items pushed by the plc for a cyclic
+/// read, at its interval. The pushes end with
+/// the connection, after an error, and the plc
+/// stops them when the subscription is
+/// unsubscribed or dropped.
+pub struct Subscription {
+    job_id: u8,
+    pushes: Pushes<Vec<DataItemVal>>
+}
+
+impl S7Client {
+    /// register a cyclic read of `items`, pushed
+    /// by the plc every `interval`, rounded to
+    /// the time bases of the plc: 100 ms, 1 s or
+    /// 10 s. Each push has an item for each of
+    /// `items`, the first one being the values
+    /// at registration.
+    pub async fn subscribe_cyclic(
+        &self,
+        items: Vec<ItemRequest>,
+        interval: Duration
+    ) -> Result<Subscription> {
+        let max_items = self
+            .pdu_len()
+            .saturating_sub(CYCLIC_OVERHEAD)
+            / ITEM_LEN;
+        if items.is_empty()
+            || items.len() > max_items as usize
+        {
+            return Err(Error::InvalidInput(
+                format!(
+                    "a cyclic read has 1 to {} \
+                     items, but not {}",
+                    max_items,
+                    items.len()
+                )
+            ));
+        }
+        let frame = Frame::user_data(0)
+            .cyclic_read(
+                CyclicInterval::from_duration(
+                    interval
+                ),
+                items
+            )
+            .build();
+        let (sender, pushes) =
+            mpsc::channel(PUSH_CAPACITY);
+        let (parameter, _) = user_data_response(
+            self.subscribe(
+                frame,
+                Subscriber::Cyclic(sender)
+            )
+            .await?
+        )?;
+        let job_id = parameter.sequence_number;
+        Ok(Subscription {
+            job_id,
+            pushes: Pushes::new(
+                self.clone(),
+                Cancel::Cyclic(job_id),
+                pushes
+            )
+        })
+    }
+}
+
+impl Subscription {
+    /// the id of the cyclic read, assigned by the
+    /// plc
+    pub fn job_id(&self) -> u8 {
+        self.job_id
+    }
+
+    /// the items of the next push, none after
+    /// the pushes ended
+    pub async fn recv(
+        &mut self
+    ) -> Option<Result<Vec<DataItemVal>>> {
+        self.pushes.recv().await
+    }
+
+    /// cancel the cyclic read
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.pushes.unsubscribe().await
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Result<Vec<DataItemVal>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>
+    ) -> Poll<Option<Self::Item>> {
+        self.pushes.poll_recv(cx)
+    }
+}
+
+pub(super) async fn unsubscribe(
+    client: &S7Client,
+    job_id: u8
+) -> Result<()> {
+    client
+        .user_data(
+            FunctionGroup::CyclicData,
+            CYCLIC_UNSUBSCRIBE,
+            UserDataItem::init(
+                ReturnCode::Success,
+                &encode_unsubscribe(job_id)
+            )
+        )
+        .await?;
+    Ok(())
+}
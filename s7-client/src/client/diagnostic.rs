@@ -0,0 +1,453 @@
+use super::{S7Client, szl::SzlRecord};
+use crate::{Error, Result};
+use s7_comm::DateAndTime;
+use std::fmt;
+
+/// An event of the diagnostic buffer, szl
+/// 0x00A0, as listed by the diagnostic buffer
+/// of STEP 7.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiagnosticEvent {
+    /// the class in the high nibble, the
+    /// identifiers in the next one
+    pub event_id:  u16,
+    /// the priority class of the OB
+    pub priority:  u8,
+    pub ob_number: u8,
+    pub dat_id:    u16,
+    /// additional information, depending on the
+    /// event
+    pub info1:     u16,
+    pub info2:     u32,
+    /// none for a DATE_AND_TIME not valid
+    pub timestamp: Option<DateAndTime>
+}
+
+impl SzlRecord for DiagnosticEvent {
+    const LIST: u8 = 0xa0;
+
+    fn decode(record: &[u8]) -> Result<Self> {
+        if record.len() < 20 {
+            return Err(Error::InvalidValue(
+                format!(
+                    "record of szl {:#04x} \
+                     needs 20 bytes, but {}",
+                    Self::LIST,
+                    record.len()
+                )
+            ));
+        }
+        Ok(Self {
+            event_id:  u16::from_be_bytes([
+                record[0], record[1]
+            ]),
+            priority:  record[2],
+            ob_number: record[3],
+            dat_id:    u16::from_be_bytes([
+                record[4], record[5]
+            ]),
+            info1:     u16::from_be_bytes([
+                record[6], record[7]
+            ]),
+            info2:     u32::from_be_bytes([
+                record[8], record[9], record[10],
+                record[11]
+            ]),
+            timestamp: DateAndTime::decode(
+                &record[12..20]
+            )
+            .ok()
+        })
+    }
+}
+
+impl DiagnosticEvent {
+    /// the description of the class of the
+    /// event
+    pub fn class_text(&self) -> &'static str {
+        match self.event_id >> 12 {
+            0x1 => "Standard OB event",
+            0x2 => "Synchronous error",
+            0x3 => "Asynchronous error",
+            0x4 => "Mode transition",
+            0x5 => "Run-time event",
+            0x6 => "Communication event",
+            0x7 => {
+                "Event of fail-safe or \
+                 fault-tolerant systems"
+            },
+            0x8 => "Diagnostic event of a module",
+            0x9 => "Predefined user event",
+            0xa | 0xb => "User event",
+            _ => "Unknown event"
+        }
+    }
+
+    /// the description of the event, the class
+    /// description for an unknown event
+    pub fn text(&self) -> &'static str {
+        match self.event_id {
+            0x1381 => {
+                "Request for manual warm restart"
+            },
+            0x1382 => {
+                "Request for automatic warm \
+                 restart"
+            },
+            0x1383 => {
+                "Request for manual hot restart"
+            },
+            0x1384 => {
+                "Request for automatic hot \
+                 restart"
+            },
+            0x1385 => {
+                "Request for manual cold restart"
+            },
+            0x1387 => {
+                "Request for automatic cold \
+                 restart"
+            },
+            0x2521 => "BCD conversion error",
+            0x2522 => {
+                "Area length error when reading"
+            },
+            0x2523 => {
+                "Area length error when writing"
+            },
+            0x2524 => "Area error when reading",
+            0x2525 => "Area error when writing",
+            0x2526 => "Timer number error",
+            0x2527 => "Counter number error",
+            0x2528 => {
+                "Alignment error when reading"
+            },
+            0x2529 => {
+                "Alignment error when writing"
+            },
+            0x2530 => {
+                "Write error when accessing the \
+                 DB"
+            },
+            0x2531 => {
+                "Write error when accessing the \
+                 DI"
+            },
+            0x2532 => {
+                "Block number error when opening \
+                 a DB"
+            },
+            0x2533 => {
+                "Block number error when opening \
+                 a DI"
+            },
+            0x2534 => {
+                "Block number error when calling \
+                 an FC"
+            },
+            0x2535 => {
+                "Block number error when calling \
+                 an FB"
+            },
+            0x253a => "DB not loaded",
+            0x253c => "FC not loaded",
+            0x253e => "FB not loaded",
+            0x3501 => "Cycle time exceeded",
+            0x3502 => {
+                "User interface (OB or FRB) \
+                 request error"
+            },
+            0x3505 => {
+                "Time-of-day interrupt(s) skipped"
+            },
+            0x3507 => {
+                "Multiple OB request errors \
+                 caused internal buffer overflow"
+            },
+            0x3921 => {
+                "BATTF: failure on at least one \
+                 backup battery of the central \
+                 rack"
+            },
+            0x3821 => {
+                "BATTF: failure on at least one \
+                 backup battery of the central \
+                 rack, problem eliminated"
+            },
+            0x3922 => {
+                "BAF: failure of backup voltage \
+                 on central rack"
+            },
+            0x3822 => {
+                "BAF: failure of backup voltage \
+                 on central rack, problem \
+                 eliminated"
+            },
+            0x4300 => "Backed-up power on",
+            0x4301 => {
+                "Mode transition from STOP to \
+                 STARTUP"
+            },
+            0x4302 => {
+                "Mode transition from STARTUP to \
+                 RUN"
+            },
+            0x4303 => {
+                "STOP caused by stop switch \
+                 being activated"
+            },
+            0x4304 => {
+                "STOP caused by PG STOP \
+                 operation or by SFB 20 \"STOP\""
+            },
+            0x4305 => "HOLD: breakpoint reached",
+            0x4306 => "HOLD: breakpoint exited",
+            0x4307 => {
+                "Memory reset started by PG \
+                 operation"
+            },
+            0x4308 => {
+                "Memory reset started by switch \
+                 setting"
+            },
+            0x4309 => {
+                "Memory reset started \
+                 automatically (power on not \
+                 backed up)"
+            },
+            0x4357 => "Module watchdog started",
+            0x4358 => {
+                "All modules are ready for \
+                 operation"
+            },
+            0x4520 => "DEFECT: STOP not possible",
+            0x4521 => {
+                "DEFECT: failure of instruction \
+                 processing processor"
+            },
+            0x4522 => {
+                "DEFECT: failure of clock chip"
+            },
+            0x4523 => {
+                "DEFECT: failure of clock pulse \
+                 generator"
+            },
+            0x4524 => {
+                "DEFECT: failure of timer update \
+                 function"
+            },
+            0x4530 => {
+                "DEFECT: memory test error in \
+                 internal memory"
+            },
+            0x4550 => {
+                "DEFECT: internal system error"
+            },
+            0x4555 => {
+                "No restart possible, monitoring \
+                 time elapsed"
+            },
+            0x4562 => {
+                "STOP caused by programming \
+                 error (OB not loaded or not \
+                 possible)"
+            },
+            0x4563 => {
+                "STOP caused by I/O access error \
+                 (OB not loaded or not possible)"
+            },
+            0x4568 => {
+                "STOP caused by time error (OB \
+                 not loaded or not possible)"
+            },
+            0x456a => {
+                "STOP caused by diagnostic \
+                 interrupt (OB not loaded or not \
+                 possible)"
+            },
+            0x456b => {
+                "STOP caused by \
+                 removing/inserting module (OB \
+                 not loaded or not possible)"
+            },
+            0x456c => {
+                "STOP caused by CPU hardware \
+                 error (OB not loaded or not \
+                 possible, or no FRB)"
+            },
+            0x456d => {
+                "STOP caused by program sequence \
+                 error (OB not loaded or not \
+                 possible)"
+            },
+            0x456e => {
+                "STOP caused by communication \
+                 error (OB not loaded or not \
+                 possible)"
+            },
+            0x456f => {
+                "STOP caused by rack failure OB \
+                 (OB not loaded or not possible)"
+            },
+            0x4570 => {
+                "STOP caused by process \
+                 interrupt (OB not loaded or not \
+                 possible)"
+            },
+            0x4571 => {
+                "STOP caused by nesting stack \
+                 error"
+            },
+            0x4576 => {
+                "STOP caused by error when \
+                 allocating the local data"
+            },
+            0x4578 => {
+                "STOP caused by unknown opcode"
+            },
+            0x457a => {
+                "STOP caused by code length error"
+            },
+            0x457f => {
+                "STOP caused by STOP command"
+            },
+            0x4580 => {
+                "STOP: back-up buffer contents \
+                 inconsistent (no transition to \
+                 RUN)"
+            },
+            0x49a0 => {
+                "STOP caused by parameter \
+                 assignment error: startup \
+                 blocked"
+            },
+            0x49a1 => {
+                "STOP caused by parameter \
+                 assignment error: memory reset \
+                 request"
+            },
+            0x49a4 => {
+                "STOP: inconsistency in \
+                 configuration data"
+            },
+            0x6500 => {
+                "Connection ID exists twice on \
+                 module"
+            },
+            0x6501 => {
+                "Connection resources inadequate"
+            },
+            0x6502 => {
+                "Error in the connection \
+                 description"
+            },
+            _ => self.class_text()
+        }
+    }
+}
+
+/// One line of the diagnostic buffer, as
+/// `2026-10-18 12:30:45.123  Event ID: 16# 4302
+/// Mode transition from STARTUP to RUN`.
+impl fmt::Display for DiagnosticEvent {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>
+    ) -> fmt::Result {
+        match &self.timestamp {
+            Some(x) => write!(
+                f,
+                "{:04}-{:02}-{:02} \
+                 {:02}:{:02}:{:02}.{:03}",
+                x.year,
+                x.month,
+                x.day,
+                x.hour,
+                x.minute,
+                x.second,
+                x.millisecond
+            )?,
+            None => write!(f, "{:23}", "-")?
+        }
+        write!(
+            f,
+            "  Event ID: 16# {:04X}  {}",
+            self.event_id,
+            self.text()
+        )
+    }
+}
+
+impl S7Client {
+    /// the events of the diagnostic buffer, the
+    /// latest first
+    pub async fn diagnostic_buffer(
+        &self
+    ) -> Result<Vec<DiagnosticEvent>> {
+        self.read_szl(0x00a0, 0x0000)
+            .await?
+            .decode_records()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_diagnostic_event() {
+        let record = [
+            0x43, 0x02, 0xff, 0x64, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02,
+            0x26, 0x10, 0x18, 0x12, 0x30, 0x45,
+            0x12, 0x31
+        ];
+        let event =
+            DiagnosticEvent::decode(&record)
+                .unwrap();
+        assert_eq!(event.event_id, 0x4302);
+        assert_eq!(event.priority, 0xff);
+        assert_eq!(event.ob_number, 100);
+        assert_eq!(event.info1, 1);
+        assert_eq!(event.info2, 2);
+        assert_eq!(
+            event.timestamp,
+            Some(
+                DateAndTime::new(
+                    2026, 10, 18, 12, 30, 45, 123
+                )
+                .unwrap()
+            )
+        );
+        assert_eq!(
+            event.to_string(),
+            "2026-10-18 12:30:45.123  Event ID: \
+             16# 4302  Mode transition from \
+             STARTUP to RUN"
+        );
+
+        // an unknown event of a known class,
+        // without valid timestamp
+        let mut record = record;
+        record[..2]
+            .copy_from_slice(&[0x55, 0xff]);
+        record[13] = 0x13;
+        let event =
+            DiagnosticEvent::decode(&record)
+                .unwrap();
+        assert_eq!(event.timestamp, None);
+        assert_eq!(
+            event.text(),
+            "Run-time event"
+        );
+        assert!(event.to_string().ends_with(
+            "16# 55FF  Run-time event"
+        ));
+        assert!(
+            DiagnosticEvent::decode(
+                &record[..19]
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use super::{
+    S7Client,
+    plan::{data_len, plan_read, plan_write}
+};
+use crate::{
+    Area, DataType, ItemError, ItemResult,
+    Result, Value
+};
+use s7_comm::ReturnCode;
+
+impl S7Client {
+    /// read `areas`, each item failing on its
+    /// own. The results are aligned with `areas`,
+    /// and an error of the whole read is the
+    /// failure of a job, as a broken connection.
+    pub async fn read_areas(
+        &self,
+        areas: &[Area]
+    ) -> Result<Vec<ItemResult<Vec<u8>>>> {
+        let mut results: Vec<
+            ItemResult<Vec<u8>>
+        > = areas
+            .iter()
+            .map(|x| self.check_read(x))
+            .map(|x| x.map(|_| Vec::new()))
+            .collect();
+        let valid = valid_indexes(&results);
+        let valid_areas: Vec<Area> = valid
+            .iter()
+            .map(|x| areas[*x])
+            .collect();
+        let items =
+            self.read_vec(&valid_areas).await?;
+        for (index, item) in
+            valid.into_iter().zip(items)
+        {
+            results[index] = check_return_code(
+                &item.return_code
+            )
+            .map(|_| item.data.into());
+        }
+        Ok(results)
+    }
+
+    /// read a value of each data type starting at
+    /// the address of its area, each item failing
+    /// on its own
+    pub async fn read_values(
+        &self,
+        items: &[(Area, DataType)]
+    ) -> Result<Vec<ItemResult<Value>>> {
+        let areas: Vec<ItemResult<Area>> = items
+            .iter()
+            .map(|(area, data_type)| {
+                let area =
+                    data_type.area(area)?;
+                self.check_read(&area)?;
+                Ok(area)
+            })
+            .collect();
+        let valid: Vec<Area> = areas
+            .iter()
+            .filter_map(|x| {
+                x.as_ref().ok().copied()
+            })
+            .collect();
+        let mut data = self
+            .read_areas(&valid)
+            .await?
+            .into_iter();
+        Ok(areas
+            .into_iter()
+            .zip(items)
+            .map(|(area, (_, data_type))| {
+                area?;
+                let data =
+                    data.next().unwrap_or_else(
+                        || Ok(Vec::new())
+                    )?;
+                Ok(Value::decode(
+                    *data_type, &data
+                )?)
+            })
+            .collect())
+    }
+
+    /// write the data of each area, each item
+    /// failing on its own. The results are
+    /// aligned with `items`, and an error of the
+    /// whole write is the failure of a job.
+    pub async fn write_areas(
+        &self,
+        items: &[(Area, &[u8])]
+    ) -> Result<Vec<ItemResult<()>>> {
+        let mut results: Vec<ItemResult<()>> =
+            items
+                .iter()
+                .map(|(area, data)| {
+                    self.check_write(area, data)
+                })
+                .collect();
+        let valid = valid_indexes(&results);
+        let valid_items: Vec<(Area, &[u8])> =
+            valid
+                .iter()
+                .map(|x| items[*x])
+                .collect();
+        let responses =
+            self.write_vec(&valid_items).await?;
+        for (index, response) in
+            valid.into_iter().zip(responses)
+        {
+            results[index] = check_return_code(
+                &response.return_code
+            );
+        }
+        Ok(results)
+    }
+
+    /// write each value as its data type,
+    /// starting at the address of its area,
+    /// each item failing on its own
+    pub async fn write_values(
+        &self,
+        items: &[(Area, DataType, Value)]
+    ) -> Result<Vec<ItemResult<()>>> {
+        let encoded: Vec<
+            ItemResult<(Area, Vec<u8>)>
+        > = items
+            .iter()
+            .map(|(area, data_type, value)| {
+                Ok((
+                    data_type.area(area)?,
+                    value.encode(*data_type)?
+                ))
+            })
+            .collect();
+        let valid: Vec<(Area, &[u8])> = encoded
+            .iter()
+            .filter_map(|x| x.as_ref().ok())
+            .map(|(area, data)| {
+                (*area, data.as_slice())
+            })
+            .collect();
+        let mut responses = self
+            .write_areas(&valid)
+            .await?
+            .into_iter();
+        Ok(encoded
+            .into_iter()
+            .map(|x| {
+                x?;
+                responses.next().unwrap_or(Ok(()))
+            })
+            .collect())
+    }
+
+    /// an area which can be read, or the error of
+    /// its item
+    fn check_read(
+        &self,
+        area: &Area
+    ) -> ItemResult<()> {
+        plan_read(
+            std::slice::from_ref(area),
+            self.pdu_len()
+        )?;
+        Ok(())
+    }
+
+    fn check_write(
+        &self,
+        area: &Area,
+        data: &[u8]
+    ) -> ItemResult<()> {
+        if data_len(area) != data.len() {
+            return Err(ItemError::InvalidInput(
+                format!(
+                    "{:?} needs {} bytes, but {}",
+                    area,
+                    data_len(area),
+                    data.len()
+                )
+            ));
+        }
+        plan_write(
+            std::slice::from_ref(area),
+            self.pdu_len()
+        )?;
+        Ok(())
+    }
+}
+
+/// the indexes of the items without error
+fn valid_indexes<T>(
+    results: &[ItemResult<T>]
+) -> Vec<usize> {
+    results
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.is_ok())
+        .map(|(index, _)| index)
+        .collect()
+}
+
+pub(super) fn check_return_code(
+    return_code: &ReturnCode
+) -> ItemResult<()> {
+    if return_code.is_ok() {
+        Ok(())
+    } else {
+        Err(ItemError::ReturnCode(
+            return_code.clone()
+        ))
+    }
+}
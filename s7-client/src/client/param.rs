@@ -1,3 +1,4 @@
+use copt::{Parameter, TpduSize};
 use serde::{Deserialize, Serialize};
 
 /// Client Connection Type
@@ -119,3 +120,22 @@ impl ConnectMode {
         ]
     }
 }
+
+/// The cotp connection negotiated with the plc,
+/// as confirmed by the plc.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CotpNegotiation {
+    pub tpdu_size:   TpduSize,
+    /// the tsap of the client
+    pub local_tsap:  Vec<u8>,
+    /// the tsap of the plc
+    pub remote_tsap: Vec<u8>,
+    /// the reference of the client
+    pub local_ref:   [u8; 2],
+    /// the reference of the plc
+    pub remote_ref:  [u8; 2],
+    pub class:       u8,
+    /// the other parameters of the connect
+    /// confirm
+    pub parameters:  Vec<Parameter>
+}
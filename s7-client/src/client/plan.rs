@@ -0,0 +1,438 @@
+use crate::{Area, DataSizeType, Error, Result};
+use s7_comm::{
+    DataItemVal, DataItemWriteResponse,
+    DataTransportSize,
+};
+use std::ops::Range;
+
+/// header of a job
+const JOB_HEADER_LEN: usize = 10;
+/// header of an ack data, with error class and
+/// error code
+const ACK_HEADER_LEN: usize = 12;
+/// function and item count
+const PARAM_HEAD_LEN: usize = 2;
+/// variable specification of an item
+const ITEM_REQUEST_LEN: usize = 12;
+/// return code, transport size and length of a
+/// data item
+const DATA_ITEM_HEAD_LEN: usize = 4;
+/// return code of a write response item
+const WRITE_RESPONSE_LEN: usize = 1;
+/// items of one job accepted by the cpu
+const MAX_ITEMS: usize = 20;
+
+/// Part of a requested area, transferred by one
+/// item of a job.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Chunk {
+    /// index of the requested area
+    pub index: usize,
+    pub area: Area,
+    /// bytes of the requested area's data
+    pub range: Range<usize>,
+}
+
+/// Bytes of data transferred for `area`.
+pub(crate) fn data_len(area: &Area) -> usize {
+    match (area, **area) {
+        (_, DataSizeType::Bit { .. }) => 1,
+        (
+            Area::Timer(_) | Area::Counter(_),
+            ds,
+        ) => ds.len() as usize * 2,
+        (_, ds) => ds.len() as usize,
+    }
+}
+
+/// ReadVar jobs of at most `pdu_len` bytes,
+/// request and response, reading `areas`.
+pub(crate) fn plan_read(
+    areas: &[Area],
+    pdu_len: u16,
+) -> Result<Vec<Vec<Chunk>>> {
+    plan(
+        areas,
+        pdu_len as usize,
+        ACK_HEADER_LEN
+            + PARAM_HEAD_LEN
+            + DATA_ITEM_HEAD_LEN,
+        |_| ITEM_REQUEST_LEN,
+        |chunk| {
+            DATA_ITEM_HEAD_LEN
+                + with_fill_byte(chunk)
+        },
+    )
+}
+
+/// WriteVar jobs of at most `pdu_len` bytes,
+/// request and response, writing `areas`.
+pub(crate) fn plan_write(
+    areas: &[Area],
+    pdu_len: u16,
+) -> Result<Vec<Vec<Chunk>>> {
+    plan(
+        areas,
+        pdu_len as usize,
+        JOB_HEADER_LEN
+            + PARAM_HEAD_LEN
+            + ITEM_REQUEST_LEN
+            + DATA_ITEM_HEAD_LEN,
+        |chunk| {
+            ITEM_REQUEST_LEN
+                + DATA_ITEM_HEAD_LEN
+                + with_fill_byte(chunk)
+        },
+        |_| WRITE_RESPONSE_LEN,
+    )
+}
+
+/// Split areas which don't fit into one item,
+/// then pack the items into as few jobs as
+/// possible, keeping their order. `overhead` is
+/// the size of a job with a single empty item.
+fn plan(
+    areas: &[Area],
+    pdu_len: usize,
+    overhead: usize,
+    request_len: impl Fn(&Chunk) -> usize,
+    response_len: impl Fn(&Chunk) -> usize,
+) -> Result<Vec<Vec<Chunk>>> {
+    // even, so an item never needs a fill byte
+    let max_item_len =
+        pdu_len.saturating_sub(overhead) & !1;
+    if max_item_len == 0 {
+        return Err(Error::InvalidInput(
+            format!(
+                "pdu length {} is too small",
+                pdu_len
+            ),
+        ));
+    }
+    let mut jobs = Vec::new();
+    let mut job: Vec<Chunk> = Vec::new();
+    let mut job_request_len =
+        JOB_HEADER_LEN + PARAM_HEAD_LEN;
+    let mut job_response_len =
+        ACK_HEADER_LEN + PARAM_HEAD_LEN;
+    for (index, area) in areas.iter().enumerate()
+    {
+        for chunk in
+            split(index, area, max_item_len)?
+        {
+            let chunk_request_len =
+                request_len(&chunk);
+            let chunk_response_len =
+                response_len(&chunk);
+            if !job.is_empty()
+                && (job.len() == MAX_ITEMS
+                    || job_request_len
+                        + chunk_request_len
+                        > pdu_len
+                    || job_response_len
+                        + chunk_response_len
+                        > pdu_len)
+            {
+                jobs.push(std::mem::take(
+                    &mut job,
+                ));
+                job_request_len = JOB_HEADER_LEN
+                    + PARAM_HEAD_LEN;
+                job_response_len = ACK_HEADER_LEN
+                    + PARAM_HEAD_LEN;
+            }
+            job_request_len += chunk_request_len;
+            job_response_len +=
+                chunk_response_len;
+            job.push(chunk);
+        }
+    }
+    if !job.is_empty() {
+        jobs.push(job);
+    }
+    Ok(jobs)
+}
+
+fn with_fill_byte(chunk: &Chunk) -> usize {
+    chunk.range.len() + chunk.range.len() % 2
+}
+
+/// Split `area` into chunks of at most `max_len`
+/// bytes.
+fn split(
+    index: usize,
+    area: &Area,
+    max_len: usize,
+) -> Result<Vec<Chunk>> {
+    let DataSizeType::Byte { addr, len } = **area
+    else {
+        return Ok(vec![Chunk {
+            index,
+            area: *area,
+            range: 0..1,
+        }]);
+    };
+    // timers and counters are 2 bytes each
+    let unit =
+        data_len(area) / (len as usize).max(1);
+    let max_units =
+        (max_len / unit.max(1)).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let units =
+            (len as usize - start).min(max_units);
+        let chunk_addr =
+            u16::try_from(addr as usize + start)
+                .map_err(|_| {
+                    Error::InvalidInput(format!(
+                "{:?} exceeds the address range",
+                area
+            ))
+                })?;
+        chunks.push(Chunk {
+            index,
+            area: area.with_data_size(
+                DataSizeType::Byte {
+                    addr: chunk_addr,
+                    len: units as u16,
+                },
+            ),
+            range: start * unit
+                ..(start + units) * unit,
+        });
+        start += units;
+        if start >= len as usize {
+            break;
+        }
+    }
+    Ok(chunks)
+}
+
+/// Reassemble the items of the chunks into one
+/// item for each of the `len` requested areas.
+/// Failing chunks decide the return code of their
+/// area, an area without any chunk is an error.
+pub(crate) fn merge_read(
+    len: usize,
+    items: Vec<(Chunk, DataItemVal)>,
+) -> Result<Vec<DataItemVal>> {
+    let mut merged: Vec<Option<DataItemVal>> =
+        vec![None; len];
+    for (chunk, item) in items {
+        let Some(val) = &mut merged[chunk.index]
+        else {
+            merged[chunk.index] = Some(item);
+            continue;
+        };
+        if val.return_code.is_ok() {
+            val.return_code = item.return_code;
+        }
+        val.data =
+            [val.data.as_ref(), &item.data]
+                .concat()
+                .into();
+        let bytes_len = val.data.len();
+        let length = match val.transport_size_type
+        {
+            DataTransportSize::NoBit => {
+                bytes_len << 3
+            }
+            _ => bytes_len,
+        };
+        val.length = u16::try_from(length)
+            .unwrap_or(u16::MAX);
+    }
+    merged
+        .into_iter()
+        .enumerate()
+        .map(|(index, val)| {
+            val.ok_or_else(|| {
+                Error::InvalidPlcAnswer(format!(
+                    "no data item of area {}",
+                    index
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Merge the write responses of the chunks into
+/// one response for each of the `len` requested
+/// areas, an area without any chunk is an error.
+pub(crate) fn merge_write(
+    len: usize,
+    items: Vec<(Chunk, DataItemWriteResponse)>,
+) -> Result<Vec<DataItemWriteResponse>> {
+    let mut merged: Vec<
+        Option<DataItemWriteResponse>,
+    > = vec![None; len];
+    for (chunk, item) in items {
+        match &mut merged[chunk.index] {
+            Some(merged)
+                if merged.return_code.is_ok() =>
+            {
+                merged.return_code =
+                    item.return_code;
+            }
+            Some(_) => {}
+            None => {
+                merged[chunk.index] = Some(item)
+            }
+        }
+    }
+    merged
+        .into_iter()
+        .enumerate()
+        .map(|(index, val)| {
+            val.ok_or_else(|| {
+                Error::InvalidPlcAnswer(format!(
+                    "no write response of area {}",
+                    index
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        merge_read, merge_write, plan_read,
+        plan_write,
+    };
+    use crate::{Area, DataSizeType};
+    use s7_comm::{
+        DataItemVal, DataItemWriteResponse,
+        ReturnCode,
+    };
+
+    fn db_bytes(addr: u16, len: u16) -> Area {
+        Area::DataBausteine(
+            1,
+            DataSizeType::Byte { addr, len },
+        )
+    }
+
+    #[test]
+    fn check_split_large_read() {
+        let jobs =
+            plan_read(&[db_bytes(10, 1000)], 240)
+                .unwrap();
+        // 240 - 12 - 2 - 4 = 222 bytes for each
+        // job
+        assert_eq!(jobs.len(), 5);
+        assert_eq!(
+            jobs[0][0].area,
+            db_bytes(10, 222)
+        );
+        assert_eq!(
+            jobs[4][0].area,
+            db_bytes(898, 112)
+        );
+        assert_eq!(jobs[4][0].range, 888..1000);
+    }
+
+    #[test]
+    fn check_pack_small_reads() {
+        let areas: Vec<Area> = (0..30)
+            .map(|x| db_bytes(x * 10, 3))
+            .collect();
+        let jobs =
+            plan_read(&areas, 480).unwrap();
+        // at most 20 items for each job
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].len(), 20);
+        // request: 12 + 12 * n <= 240 => 19 items
+        let jobs =
+            plan_read(&areas, 240).unwrap();
+        assert_eq!(jobs[0].len(), 19);
+        // response: 14 + (4 + 30) * n <= 240 => 6
+        // items
+        let areas: Vec<Area> = (0..10)
+            .map(|x| db_bytes(x * 100, 30))
+            .collect();
+        let jobs =
+            plan_read(&areas, 240).unwrap();
+        assert_eq!(jobs[0].len(), 6);
+    }
+
+    #[test]
+    fn check_plan_write() {
+        let jobs = plan_write(
+            &[db_bytes(0, 300), db_bytes(400, 5)],
+            240,
+        )
+        .unwrap();
+        // 240 - 10 - 2 - 12 - 4 = 212 bytes for
+        // each item
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0][0].range, 0..212);
+        assert_eq!(jobs[1][0].range, 212..300);
+        assert_eq!(jobs[1][1].index, 1);
+    }
+
+    #[test]
+    fn check_merge_read() {
+        let areas =
+            [db_bytes(0, 300), db_bytes(400, 1)];
+        let jobs =
+            plan_read(&areas, 240).unwrap();
+        let items = jobs
+            .into_iter()
+            .flatten()
+            .map(|chunk| {
+                let data = vec![
+                    chunk.index as u8;
+                    chunk.range.len()
+                ];
+                let item =
+                    DataItemVal::init_with_bytes(
+                        ReturnCode::Success,
+                        &data,
+                    );
+                (chunk, item)
+            })
+            .collect();
+        let merged =
+            merge_read(2, items).unwrap();
+        assert_eq!(merged[0].data, vec![0; 300]);
+        assert_eq!(merged[0].length, 300 << 3);
+        assert_eq!(merged[1].data, vec![1]);
+    }
+
+    #[test]
+    fn check_merge_read_missing_area() {
+        let areas =
+            [db_bytes(0, 1), db_bytes(1, 1)];
+        let mut jobs =
+            plan_read(&areas, 240).unwrap();
+        let chunk = jobs.remove(0).remove(0);
+        let item = DataItemVal::init_with_bytes(
+            ReturnCode::Success,
+            &[0],
+        );
+        assert!(merge_read(
+            2,
+            vec![(chunk, item)]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn check_merge_write_missing_area() {
+        let areas =
+            [db_bytes(0, 1), db_bytes(1, 1)];
+        let mut jobs =
+            plan_write(&areas, 240).unwrap();
+        let chunk = jobs.remove(0).remove(0);
+        let item = DataItemWriteResponse::init(
+            ReturnCode::Success,
+        );
+        assert!(merge_write(
+            2,
+            vec![(chunk, item)]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,402 @@
+use super::S7Client;
+use crate::{
+    ItemError, TagError, TagTable, Value
+};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, SystemTime}
+};
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval}
+};
+
+/// events buffered for a slow receiver, the
+/// reads of the poller waiting meanwhile
+const EVENT_CAPACITY: usize = 256;
+
+/// Quality of the value of a tag.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quality {
+    Good,
+    /// the plc refused the item of the tag, or
+    /// its data is not a value of its type
+    Bad(ItemError),
+    /// the read failed, as the connection broke
+    CommLost
+}
+
+/// A change of the value or of the quality of a
+/// tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagEvent {
+    pub name:      String,
+    /// the value read, or the last good value of
+    /// a tag of bad quality
+    pub value:     Option<Value>,
+    pub quality:   Quality,
+    /// the time of the read
+    pub timestamp: SystemTime
+}
+
+/// Tags read at the same interval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanGroup {
+    pub interval: Duration,
+    pub tags:     Vec<String>
+}
+
+/// Reads the tags of a table at the interval of
+/// their scan group, and reports their changes.
+/// Each group reads its tags with
+/// [`S7Client::read_tags`], as few items and
+/// jobs as the pdu length allows.
+pub struct Poller {
+    client: S7Client,
+    table:  Arc<TagTable>,
+    groups: Vec<ScanGroup>
+}
+
+impl Poller {
+    pub fn new(
+        client: S7Client,
+        table: TagTable
+    ) -> Self {
+        Self {
+            client,
+            table: Arc::new(table),
+            groups: Vec::new()
+        }
+    }
+
+    /// read `names` every `interval`, with the
+    /// other tags of this interval. A tag is
+    /// scanned by one group, and the interval
+    /// may not be zero.
+    pub fn scan(
+        mut self,
+        interval: Duration,
+        names: &[&str]
+    ) -> std::result::Result<Self, TagError> {
+        if interval.is_zero() {
+            return Err(TagError::InvalidInput(
+                "zero scan interval".to_string()
+            ));
+        }
+        for name in names {
+            if self.table.get(name).is_none() {
+                return Err(
+                    TagError::UnknownTag(
+                        name.to_string()
+                    )
+                );
+            }
+            if self.groups.iter().any(|x| {
+                x.tags.iter().any(|x| x == name)
+            }) {
+                return Err(
+                    TagError::DuplicateTag(
+                        name.to_string()
+                    )
+                );
+            }
+        }
+        let names =
+            names.iter().map(|x| x.to_string());
+        match self
+            .groups
+            .iter_mut()
+            .find(|x| x.interval == interval)
+        {
+            Some(group) => {
+                group.tags.extend(names)
+            },
+            None => self.groups.push(ScanGroup {
+                interval,
+                tags: names.collect()
+            })
+        }
+        Ok(self)
+    }
+
+    pub fn groups(&self) -> &[ScanGroup] {
+        &self.groups
+    }
+
+    /// start a task reading each group. The first
+    /// read of each tag is reported, then its
+    /// changes.
+    pub fn start(self) -> Polling {
+        let (sender, events) =
+            mpsc::channel(EVENT_CAPACITY);
+        let tasks = self
+            .groups
+            .into_iter()
+            .filter(|x| !x.tags.is_empty())
+            .map(|group| {
+                tokio::spawn(poll(
+                    self.client.clone(),
+                    self.table.clone(),
+                    group,
+                    sender.clone()
+                ))
+            })
+            .collect();
+        Polling { events, tasks }
+    }
+}
+
+/// The running poller, stopped when dropped.
+pub struct Polling {
+    events: mpsc::Receiver<TagEvent>,
+    tasks:  Vec<JoinHandle<()>>
+}
+
+impl Polling {
+    /// the next change, none after the poller
+    /// stopped
+    pub async fn recv(
+        &mut self
+    ) -> Option<TagEvent> {
+        self.events.recv().await
+    }
+
+    /// stop reading, the events already
+    /// reported still received
+    pub fn stop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for Polling {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// read `group` at its interval until the
+/// receiver of the events is dropped
+async fn poll(
+    client: S7Client,
+    table: Arc<TagTable>,
+    group: ScanGroup,
+    events: mpsc::Sender<TagEvent>
+) {
+    let names: Vec<&str> = group
+        .tags
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let mut last: BTreeMap<&str, TagState> =
+        BTreeMap::new();
+    let mut ticks = interval(group.interval);
+    ticks.set_missed_tick_behavior(
+        MissedTickBehavior::Skip
+    );
+    loop {
+        ticks.tick().await;
+        let timestamp = SystemTime::now();
+        let values = client
+            .read_tags(&table, &names)
+            .await;
+        for name in &names {
+            let (value, quality) = match &values {
+                Ok(values) => match values
+                    .get(*name)
+                {
+                    Some(Ok(value)) => (
+                        Some(value.clone()),
+                        Quality::Good
+                    ),
+                    Some(Err(err)) => (
+                        None,
+                        Quality::Bad(err.clone())
+                    ),
+                    None => continue
+                },
+                Err(_) => {
+                    (None, Quality::CommLost)
+                },
+            };
+            let deadband = table
+                .get(name)
+                .map_or(0.0, |x| x.deadband);
+            let previous = last.get(name);
+            let Some(state) = TagState::update(
+                previous, value, quality,
+                deadband
+            ) else {
+                continue;
+            };
+            let event = TagEvent {
+                name: name.to_string(),
+                value: state.value.clone(),
+                quality: state.quality.clone(),
+                timestamp
+            };
+            last.insert(name, state);
+            if events.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// The last reported value and quality of a tag.
+#[derive(Debug, Clone, PartialEq)]
+struct TagState {
+    value:   Option<Value>,
+    quality: Quality
+}
+
+impl TagState {
+    /// the state to report after a read, none if
+    /// the tag has not changed: same quality, and
+    /// a numeric value within the deadband, or
+    /// another value equal
+    fn update(
+        previous: Option<&TagState>,
+        value: Option<Value>,
+        quality: Quality,
+        deadband: f64
+    ) -> Option<TagState> {
+        let Some(previous) = previous else {
+            return Some(TagState {
+                value,
+                quality
+            });
+        };
+        if quality != Quality::Good {
+            return (quality != previous.quality)
+                .then(|| TagState {
+                    value: previous.value.clone(),
+                    quality
+                });
+        }
+        let changed = previous.quality != quality
+            || match (&previous.value, &value) {
+                (Some(old), Some(new)) => {
+                    match (
+                        old.as_f64(),
+                        new.as_f64()
+                    ) {
+                        (
+                            Some(old),
+                            Some(new)
+                        ) => {
+                            (new - old).abs()
+                                > deadband
+                                || (old.is_nan()
+                                    != new
+                                        .is_nan())
+                        },
+                        _ => old != new
+                    }
+                },
+                (old, new) => old != new
+            };
+        changed.then_some(TagState {
+            value,
+            quality
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Quality, TagState};
+    use crate::{ItemError, Value};
+    use s7_comm::ReturnCode;
+
+    fn good(value: Value) -> TagState {
+        TagState {
+            value:   Some(value),
+            quality: Quality::Good
+        }
+    }
+
+    #[test]
+    fn change_detection() {
+        let first = TagState::update(
+            None,
+            Some(Value::Int(10)),
+            Quality::Good,
+            2.0
+        );
+        assert_eq!(
+            first,
+            Some(good(Value::Int(10)))
+        );
+        let previous = good(Value::Int(10));
+        // within the deadband
+        assert_eq!(
+            TagState::update(
+                Some(&previous),
+                Some(Value::Int(12)),
+                Quality::Good,
+                2.0
+            ),
+            None
+        );
+        assert_eq!(
+            TagState::update(
+                Some(&previous),
+                Some(Value::Int(7)),
+                Quality::Good,
+                2.0
+            ),
+            Some(good(Value::Int(7)))
+        );
+        // values without number compare equal
+        let text =
+            good(Value::String("a".into()));
+        assert_eq!(
+            TagState::update(
+                Some(&text),
+                Some(Value::String("a".into())),
+                Quality::Good,
+                2.0
+            ),
+            None
+        );
+        // a bad quality keeps the last good value
+        let bad =
+            Quality::Bad(ItemError::ReturnCode(
+                ReturnCode::InvalidAddress
+            ));
+        let lost = TagState::update(
+            Some(&previous),
+            None,
+            bad.clone(),
+            2.0
+        )
+        .unwrap();
+        assert_eq!(
+            lost.value,
+            Some(Value::Int(10))
+        );
+        assert_eq!(
+            TagState::update(
+                Some(&lost),
+                None,
+                bad,
+                2.0
+            ),
+            None
+        );
+        // recovered, within the deadband
+        assert_eq!(
+            TagState::update(
+                Some(&lost),
+                Some(Value::Int(11)),
+                Quality::Good,
+                2.0
+            ),
+            Some(good(Value::Int(11)))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use super::connection::{
+    Connection, Disconnect, Pending, Request
+};
+use crate::{Area, DataSizeType, Error, Options};
+use log::warn;
+use tokio::{
+    sync::mpsc,
+    time::{Instant, sleep_until}
+};
+
+/// State of the connection shared by the client
+/// handles.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConnectionState {
+    Connected,
+    /// the connection broke, and a new one is
+    /// being established
+    Reconnecting,
+    /// all client handles are dropped, or the
+    /// connection broke without `reconnect`
+    Closed
+}
+
+/// Supervision of the connection, to reconnect
+/// when it breaks.
+#[derive(Debug, Clone)]
+pub struct Reconnect {
+    /// delay before the second attempt, doubled
+    /// after each failed attempt. The first
+    /// attempt is made at once.
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+    /// read `probe` after being idle for this
+    /// long, the connection is broken if it is
+    /// not acknowledged within the read timeout
+    pub keep_alive:  Option<Duration>,
+    pub probe:       Area,
+    /// send the reads which were in flight again
+    /// after reconnection, if it succeeds
+    /// within their read timeout
+    pub retry_reads: bool
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            min_backoff: Duration::from_millis(
+                500
+            ),
+            max_backoff: Duration::from_secs(30),
+            keep_alive:  Some(
+                Duration::from_secs(10)
+            ),
+            probe:       Area::Merker(
+                DataSizeType::Byte {
+                    addr: 0,
+                    len:  1
+                }
+            ),
+            retry_reads: true
+        }
+    }
+}
+
+impl Reconnect {
+    /// connect until an attempt succeeds, or all
+    /// client handles are dropped or one
+    /// disconnects. Requests made meanwhile fail
+    /// at once, and the jobs to retry fail at
+    /// their deadline.
+    pub(crate) async fn reconnect(
+        &self,
+        options: &Options,
+        requests: &mut mpsc::Receiver<Request>,
+        disconnects: &mut mpsc::Receiver<
+            Disconnect
+        >,
+        retry: &mut Vec<Pending>
+    ) -> Option<Connection> {
+        let mut backoff = Duration::ZERO;
+        loop {
+            let wake = Instant::now() + backoff;
+            let attempt = async {
+                sleep_until(wake).await;
+                Connection::connect(options).await
+            };
+            tokio::pin!(attempt);
+            loop {
+                let deadline = retry
+                    .iter()
+                    .map(|x| x.deadline)
+                    .min();
+                tokio::select! {
+                    rs = &mut attempt => match rs {
+                        Ok(connection) => return Some(connection),
+                        Err(err) => {
+                            warn!("reconnect fail: {}", err);
+                            break;
+                        },
+                    },
+                    Some(disconnect) = disconnects.recv() => {
+                        for pending in retry.drain(..) {
+                            pending.fail(Error::ConnectionClosed(
+                                "disconnected by the client".to_string(),
+                            ));
+                        }
+                        let _ = disconnect.send(Ok(()));
+                        return None;
+                    },
+                    request = requests.recv() => {
+                        let request = request?;
+                        let _ = request.response.send(Err(
+                            Error::ConnectionClosed(
+                                "reconnecting".to_string(),
+                            ),
+                        ));
+                    },
+                    _ = sleep_until(deadline.unwrap_or_else(Instant::now)),
+                        if deadline.is_some() => {
+                        let now = Instant::now();
+                        let (expired, kept): (Vec<_>, _) =
+                            std::mem::take(retry)
+                                .into_iter()
+                                .partition(|x| x.deadline <= now);
+                        *retry = kept;
+                        for pending in expired {
+                            pending.fail(Error::ReadTimeout);
+                        }
+                    },
+                }
+            }
+            backoff = (backoff * 2)
+                .max(self.min_backoff)
+                .min(self.max_backoff);
+        }
+    }
+}
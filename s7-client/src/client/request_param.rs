@@ -7,7 +7,13 @@ use std::ops::Deref;
 type S7Area = s7_comm::Area;
 // Area ID
 #[derive(
-    Debug, Copy, Clone, Serialize, Deserialize,
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
 )]
 #[allow(dead_code)]
 pub enum Area {
@@ -25,8 +31,13 @@ pub enum Area {
     /// This is your storage  : db number,
     /// DataSizeType
     DataBausteine(u16, DataSizeType),
-    V(DataSizeType), /* Counter,
-                      * Timer, */
+    V(DataSizeType),
+    /// timer number and count of timers,
+    /// always `DataSizeType::Byte`
+    Timer(DataSizeType),
+    /// counter number and count of counters,
+    /// always `DataSizeType::Byte`
+    Counter(DataSizeType),
 }
 
 impl Into<ItemRequest> for Area {
@@ -81,6 +92,25 @@ impl Into<ItemRequest> for Area {
                 ds.bit_addr(),
                 ds.len(),
             ),
+            // timers and counters are addressed
+            // by number, which is not shifted
+            // by the bit address
+            Area::Timer(ds) => ItemRequest::new(
+                TransportSize::Timer,
+                s7_comm::DbNumber::NotIn,
+                S7Area::Timer,
+                ds.byte_addr() >> 3,
+                (ds.byte_addr() & 0x07) as u8,
+                ds.len(),
+            ),
+            Area::Counter(ds) => ItemRequest::new(
+                TransportSize::Counter,
+                s7_comm::DbNumber::NotIn,
+                S7Area::Counter,
+                ds.byte_addr() >> 3,
+                (ds.byte_addr() & 0x07) as u8,
+                ds.len(),
+            ),
         }
     }
 }
@@ -98,8 +128,9 @@ impl Area {
             Area::V(_) => S7Area::DataBlocks,
             Area::DataBausteine(_, _) => {
                 S7Area::DataBlocks
-            }, /* Area::Counter => {0x1C}
-                * Area::Timer => {0x1D} */
+            },
+            Area::Timer(_) => S7Area::Timer,
+            Area::Counter(_) => S7Area::Counter,
         }
     }
 
@@ -108,12 +139,47 @@ impl Area {
             Area::ProcessInput(_) => 0,
             Area::ProcessOutput(_) => 0,
             Area::Merker(_) => 0,
+            Area::Timer(_) => 0,
+            Area::Counter(_) => 0,
             Area::V(_) => 1,
             Area::DataBausteine(db_number, _) => {
                 *db_number
             },
         }
     }
+
+    /// db number used by the write builders
+    pub fn db(&self) -> Option<u16> {
+        match self {
+            Area::V(_) | Area::DataBausteine(..) => {
+                Some(self.db_number())
+            },
+            _ => None,
+        }
+    }
+
+    /// the same area accessed with another
+    /// size
+    pub fn with_data_size(
+        &self,
+        ds: DataSizeType,
+    ) -> Self {
+        match self {
+            Area::ProcessInput(_) => {
+                Area::ProcessInput(ds)
+            },
+            Area::ProcessOutput(_) => {
+                Area::ProcessOutput(ds)
+            },
+            Area::Merker(_) => Area::Merker(ds),
+            Area::V(_) => Area::V(ds),
+            Area::DataBausteine(db_number, _) => {
+                Area::DataBausteine(*db_number, ds)
+            },
+            Area::Timer(_) => Area::Timer(ds),
+            Area::Counter(_) => Area::Counter(ds),
+        }
+    }
 }
 impl Deref for Area {
     type Target = DataSizeType;
@@ -125,11 +191,19 @@ impl Deref for Area {
             Area::Merker(val) => val,
             Area::V(val) => val,
             Area::DataBausteine(_, val) => val,
+            Area::Timer(val) => val,
+            Area::Counter(val) => val,
         }
     }
 }
 #[derive(
-    Debug, Copy, Clone, Serialize, Deserialize,
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
 )]
 #[repr(u8)]
 pub enum BitAddr {
@@ -165,7 +239,13 @@ impl TryFrom<u16> for BitAddr {
 }
 
 #[derive(
-    Debug, Copy, Clone, Serialize, Deserialize,
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
 )]
 pub enum DataSizeType {
     Bit { addr: u16, bit_addr: BitAddr },
@@ -0,0 +1,62 @@
+use super::{
+    S7Client, user_data::user_data_response
+};
+use crate::{Error, Result};
+use s7_comm::{Frame, HearderAckData};
+
+impl S7Client {
+    /// log in to a protected plc, the functions
+    /// of its protection level then allowed for
+    /// this connection. The password is not set
+    /// again after a reconnection, unlike
+    /// [`Options::password`](crate::Options).
+    pub async fn set_session_password(
+        &self,
+        password: &str
+    ) -> Result<()> {
+        let frame = set_password_frame(password)?;
+        user_data_response(
+            self.request(frame).await?
+        )?;
+        Ok(())
+    }
+
+    /// log out of a protected plc
+    pub async fn clear_session_password(
+        &self
+    ) -> Result<()> {
+        let frame = Frame::user_data(0)
+            .clear_password()
+            .build();
+        user_data_response(
+            self.request(frame).await?
+        )?;
+        Ok(())
+    }
+}
+
+/// the set password request of `password`, at
+/// most 8 ascii chars
+pub(super) fn set_password_frame(
+    password: &str
+) -> Result<Frame> {
+    Ok(Frame::user_data(0)
+        .set_password(password)
+        .map_err(|x| {
+            Error::InvalidInput(x.to_string())
+        })?
+        .build())
+}
+
+/// refuse the ack data of a job protected by the
+/// password
+pub(super) fn check_password(
+    header: &HearderAckData
+) -> Result<()> {
+    match Error::from_code(header) {
+        Some(Error::NeedPassword) => {
+            Err(Error::NeedPassword)
+        },
+        _ => Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use super::{S7Client, alarm, cyclic};
+use crate::Result;
+use log::warn;
+use s7_comm::AlarmType;
+use std::task::{Context, Poll};
+use tokio::{runtime::Handle, sync::mpsc};
+
+/// What a subscription ends on the plc.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Cancel {
+    /// the cyclic read of the job id
+    Cyclic(u8),
+    /// the alarms of a subscription, by its id
+    Alarms(AlarmType, u64)
+}
+
+impl Cancel {
+    async fn run(
+        self,
+        client: &S7Client
+    ) -> Result<()> {
+        match self {
+            Cancel::Cyclic(job_id) => {
+                cyclic::unsubscribe(
+                    client, job_id
+                )
+                .await
+            },
+            Cancel::Alarms(alarm_type, id) => {
+                alarm::unsubscribe(
+                    client, alarm_type, id
+                )
+                .await
+            },
+        }
+    }
+}
+
+/// The pushes of a subscription, ended on the
+/// plc when unsubscribed, or in the background
+/// when dropped within a runtime.
+pub(super) struct Pushes<T> {
+    client:   S7Client,
+    cancel:   Cancel,
+    receiver: mpsc::Receiver<Result<T>>,
+    active:   bool
+}
+
+impl<T> Pushes<T> {
+    pub(super) fn new(
+        client: S7Client,
+        cancel: Cancel,
+        receiver: mpsc::Receiver<Result<T>>
+    ) -> Self {
+        Self {
+            client,
+            cancel,
+            receiver,
+            active: true
+        }
+    }
+
+    pub(super) async fn recv(
+        &mut self
+    ) -> Option<Result<T>> {
+        self.receiver.recv().await
+    }
+
+    pub(super) fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>
+    ) -> Poll<Option<Result<T>>> {
+        self.receiver.poll_recv(cx)
+    }
+
+    pub(super) async fn unsubscribe(
+        mut self
+    ) -> Result<()> {
+        self.active = false;
+        self.receiver.close();
+        self.cancel.run(&self.client).await
+    }
+}
+
+impl<T> Drop for Pushes<T> {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+        let Ok(handle) = Handle::try_current()
+        else {
+            return;
+        };
+        let client = self.client.clone();
+        let cancel = self.cancel;
+        handle.spawn(async move {
+            if let Err(err) =
+                cancel.run(&client).await
+            {
+                warn!(
+                    "unsubscribe {:?} fail: {}",
+                    cancel, err
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,562 @@
+use super::S7Client;
+use crate::{Error, Result};
+use s7_comm::{
+    FunctionGroup, ReturnCode, UserDataItem
+};
+
+/// subfunction of the cpu functions reading a
+/// system status list
+const READ_SZL: u8 = 0x01;
+
+/// A system status list (SZL/SSL) read from the
+/// plc: `records` of `record_len` bytes each.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Szl {
+    pub id:         u16,
+    pub index:      u16,
+    pub record_len: u16,
+    pub records:    Vec<Vec<u8>>
+}
+
+impl Szl {
+    /// decode the data of a read szl response:
+    /// id, index, record length and record count,
+    /// followed by the records
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 {
+            return Err(Error::InvalidPlcAnswer(
+                format!(
+                    "szl's length is not \
+                     enough: {}",
+                    data.len()
+                )
+            ));
+        }
+        let id = u16::from_be_bytes([
+            data[0], data[1]
+        ]);
+        let index = u16::from_be_bytes([
+            data[2], data[3]
+        ]);
+        let record_len = u16::from_be_bytes([
+            data[4], data[5]
+        ]);
+        let count = u16::from_be_bytes([
+            data[6], data[7]
+        ]) as usize;
+        let records = if record_len == 0 {
+            Vec::new()
+        } else {
+            // tolerate a count exceeding the
+            // records
+            data[8..]
+                .chunks_exact(record_len as usize)
+                .take(count)
+                .map(|x| x.to_vec())
+                .collect()
+        };
+        Ok(Self {
+            id,
+            index,
+            record_len,
+            records
+        })
+    }
+
+    /// decode the records as `T`, which must be
+    /// the type of this list
+    pub fn decode_records<T: SzlRecord>(
+        &self
+    ) -> Result<Vec<T>> {
+        if self.id as u8 != T::LIST {
+            return Err(Error::InvalidValue(
+                format!(
+                    "szl {:#06x} is not the \
+                     list {:#04x}",
+                    self.id,
+                    T::LIST
+                )
+            ));
+        }
+        self.records
+            .iter()
+            .map(|x| T::decode(x))
+            .collect()
+    }
+}
+
+/// A record of a system status list.
+pub trait SzlRecord: Sized {
+    /// the number of the list, the low byte of
+    /// the szl id
+    const LIST: u8;
+
+    fn decode(record: &[u8]) -> Result<Self>;
+}
+
+/// Record of the module identification, szl
+/// 0x0011.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ModuleIdentification {
+    /// 0x0001 module, 0x0006 basic hardware,
+    /// 0x0007 basic firmware
+    pub index:        u16,
+    /// the order number (MLFB)
+    pub order_number: String,
+    pub module_type:  u16,
+    pub ausbg:        u16,
+    pub ausbe:        u16
+}
+
+impl ModuleIdentification {
+    /// the version, as `V3.2.6`
+    pub fn version(&self) -> String {
+        format!(
+            "V{}.{}.{}",
+            self.ausbg & 0xff,
+            self.ausbe >> 8,
+            self.ausbe & 0xff
+        )
+    }
+}
+
+impl SzlRecord for ModuleIdentification {
+    const LIST: u8 = 0x11;
+
+    fn decode(record: &[u8]) -> Result<Self> {
+        check_len(Self::LIST, record, 28)?;
+        Ok(Self {
+            index:        word(record, 0),
+            order_number: text(&record[2..22]),
+            module_type:  word(record, 22),
+            ausbg:        word(record, 24),
+            ausbe:        word(record, 26)
+        })
+    }
+}
+
+/// Record of the component identification, szl
+/// 0x001C.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ComponentIdentification {
+    /// 0x0001 plc name, 0x0002 module name,
+    /// 0x0003 plant designation, 0x0004
+    /// copyright, 0x0005 serial number, 0x0007
+    /// module type name, ...
+    pub index: u16,
+    pub text:  String
+}
+
+impl SzlRecord for ComponentIdentification {
+    const LIST: u8 = 0x1c;
+
+    fn decode(record: &[u8]) -> Result<Self> {
+        check_len(Self::LIST, record, 2)?;
+        Ok(Self {
+            index: word(record, 0),
+            text:  text(&record[2..])
+        })
+    }
+}
+
+/// Operating mode of the cpu.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CpuMode {
+    Stop,
+    Startup,
+    Run,
+    Hold,
+    NotSupport(u8)
+}
+
+impl From<u8> for CpuMode {
+    fn from(value: u8) -> Self {
+        match value {
+            0x01..=0x04 => CpuMode::Stop,
+            0x05..=0x07 => CpuMode::Startup,
+            0x08 | 0x09 => CpuMode::Run,
+            0x0a => CpuMode::Hold,
+            value => CpuMode::NotSupport(value)
+        }
+    }
+}
+
+/// Record of the cpu state, the last mode
+/// transition, szl 0x0424.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CpuState {
+    pub event_id:      u16,
+    pub mode:          CpuMode,
+    pub previous_mode: CpuMode,
+    /// DATE_AND_TIME of the transition
+    pub time:          [u8; 8]
+}
+
+impl SzlRecord for CpuState {
+    const LIST: u8 = 0x24;
+
+    fn decode(record: &[u8]) -> Result<Self> {
+        check_len(Self::LIST, record, 20)?;
+        let mut time = [0; 8];
+        time.copy_from_slice(&record[12..20]);
+        Ok(Self {
+            event_id: word(record, 0),
+            mode: CpuMode::from(record[3] & 0x0f),
+            previous_mode: CpuMode::from(
+                record[3] >> 4
+            ),
+            time
+        })
+    }
+}
+
+/// Record of the diagnostic buffer, szl 0x00A0,
+/// the latest event first. See
+/// [`DiagnosticEvent`](crate::DiagnosticEvent)
+/// for its fields and description.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiagnosticEntry {
+    pub event_id: u16,
+    /// additional information, depending on the
+    /// event
+    pub info:     [u8; 10],
+    /// DATE_AND_TIME of the event
+    pub time:     [u8; 8]
+}
+
+impl SzlRecord for DiagnosticEntry {
+    const LIST: u8 = 0xa0;
+
+    fn decode(record: &[u8]) -> Result<Self> {
+        check_len(Self::LIST, record, 20)?;
+        let mut info = [0; 10];
+        info.copy_from_slice(&record[2..12]);
+        let mut time = [0; 8];
+        time.copy_from_slice(&record[12..20]);
+        Ok(Self {
+            event_id: word(record, 0),
+            info,
+            time
+        })
+    }
+}
+
+/// Record of the general communication
+/// capabilities, szl 0x0131 index 0x0001.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CommunicationCapabilities {
+    pub index:           u16,
+    pub max_pdu_len:     u16,
+    pub max_connections: u16,
+    /// transmission rate of the mpi, in bit/s
+    pub mpi_rate:        u32,
+    /// transmission rate of the communication
+    /// bus, in bit/s
+    pub kbus_rate:       u32
+}
+
+impl SzlRecord for CommunicationCapabilities {
+    const LIST: u8 = 0x31;
+
+    fn decode(record: &[u8]) -> Result<Self> {
+        check_len(Self::LIST, record, 14)?;
+        Ok(Self {
+            index:           word(record, 0),
+            max_pdu_len:     word(record, 2),
+            max_connections: word(record, 4),
+            mpi_rate:        dword(record, 6),
+            kbus_rate:       dword(record, 10)
+        })
+    }
+}
+
+impl S7Client {
+    /// read the system status list `id` with
+    /// `index`
+    pub async fn read_szl(
+        &self,
+        id: u16,
+        index: u16
+    ) -> Result<Szl> {
+        let mut data = [0; 4];
+        data[..2]
+            .copy_from_slice(&id.to_be_bytes());
+        data[2..].copy_from_slice(
+            &index.to_be_bytes()
+        );
+        Szl::decode(
+            &self
+                .user_data(
+                    FunctionGroup::Cpu,
+                    READ_SZL,
+                    UserDataItem::init(
+                        ReturnCode::Success,
+                        &data
+                    )
+                )
+                .await?
+        )
+    }
+
+    pub async fn read_module_identification(
+        &self
+    ) -> Result<Vec<ModuleIdentification>> {
+        self.read_szl(0x0011, 0x0000)
+            .await?
+            .decode_records()
+    }
+
+    pub async fn read_component_identification(
+        &self
+    ) -> Result<Vec<ComponentIdentification>>
+    {
+        self.read_szl(0x001c, 0x0000)
+            .await?
+            .decode_records()
+    }
+
+    pub async fn read_cpu_state(
+        &self
+    ) -> Result<CpuState> {
+        self.read_szl(0x0424, 0x0000)
+            .await?
+            .decode_records()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Error::InvalidPlcAnswer(
+                    "szl 0x0424 has no record"
+                        .to_string()
+                )
+            })
+    }
+
+    pub async fn read_diagnostic_buffer(
+        &self
+    ) -> Result<Vec<DiagnosticEntry>> {
+        self.read_szl(0x00a0, 0x0000)
+            .await?
+            .decode_records()
+    }
+
+    pub async fn read_communication_capabilities(
+        &self
+    ) -> Result<CommunicationCapabilities> {
+        self.read_szl(0x0131, 0x0001)
+            .await?
+            .decode_records()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Error::InvalidPlcAnswer(
+                    "szl 0x0131 has no record"
+                        .to_string()
+                )
+            })
+    }
+}
+
+fn check_len(
+    list: u8,
+    record: &[u8],
+    len: usize
+) -> Result<()> {
+    if record.len() < len {
+        return Err(Error::InvalidValue(
+            format!(
+                "record of szl {:#04x} needs {} \
+                 bytes, but {}",
+                list,
+                len,
+                record.len()
+            )
+        ));
+    }
+    Ok(())
+}
+
+fn word(record: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([
+        record[offset],
+        record[offset + 1]
+    ])
+}
+
+fn dword(record: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        record[offset],
+        record[offset + 1],
+        record[offset + 2],
+        record[offset + 3]
+    ])
+}
+
+/// a text padded with spaces or nul
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn szl(
+        id: u16,
+        index: u16,
+        records: &[&[u8]]
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&id.to_be_bytes());
+        data.extend_from_slice(
+            &index.to_be_bytes()
+        );
+        data.extend_from_slice(
+            &(records[0].len() as u16)
+                .to_be_bytes()
+        );
+        data.extend_from_slice(
+            &(records.len() as u16).to_be_bytes()
+        );
+        for record in records {
+            data.extend_from_slice(record);
+        }
+        data
+    }
+
+    #[test]
+    fn check_module_identification() {
+        let mut module = vec![0x00, 0x01];
+        module.extend_from_slice(
+            b"6ES7 315-2EH14-0AB0 "
+        );
+        module.extend_from_slice(&[
+            0x00, 0xc0, 0x00, 0x04, 0x00, 0x01
+        ]);
+        let mut firmware = vec![0x00, 0x07];
+        firmware.extend_from_slice(&[0x20; 20]);
+        firmware.extend_from_slice(&[
+            0x00, 0xc0, 0x56, 0x03, 0x02, 0x06
+        ]);
+        let szl = Szl::decode(&szl(
+            0x0011,
+            0,
+            &[&module, &firmware]
+        ))
+        .unwrap();
+        assert_eq!(szl.record_len, 28);
+        let records: Vec<ModuleIdentification> =
+            szl.decode_records().unwrap();
+        assert_eq!(
+            records[0].order_number,
+            "6ES7 315-2EH14-0AB0"
+        );
+        assert_eq!(records[0].module_type, 0xc0);
+        assert_eq!(records[1].index, 7);
+        assert_eq!(
+            records[1].version(),
+            "V3.2.6"
+        );
+        assert!(
+            szl.decode_records::<CpuState>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn check_component_identification() {
+        let mut record = vec![0x00, 0x02];
+        record.extend_from_slice(
+            b"CPU 315-2 PN/DP"
+        );
+        record.resize(34, 0);
+        let records: Vec<
+            ComponentIdentification
+        > = Szl::decode(&szl(
+            0x001c,
+            0,
+            &[&record]
+        ))
+        .unwrap()
+        .decode_records()
+        .unwrap();
+        assert_eq!(records[0].index, 2);
+        assert_eq!(
+            records[0].text,
+            "CPU 315-2 PN/DP"
+        );
+    }
+
+    #[test]
+    fn check_cpu_state() {
+        let record = [
+            0x43, 0x02, 0xff, 0x48, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x23, 0x10, 0x18, 0x12, 0x30, 0x45,
+            0x12, 0x37
+        ];
+        let states: Vec<CpuState> = Szl::decode(
+            &szl(0x0424, 0, &[&record])
+        )
+        .unwrap()
+        .decode_records()
+        .unwrap();
+        assert_eq!(states[0].event_id, 0x4302);
+        assert_eq!(states[0].mode, CpuMode::Run);
+        assert_eq!(
+            states[0].previous_mode,
+            CpuMode::Stop
+        );
+        assert_eq!(states[0].time, record[12..]);
+    }
+
+    #[test]
+    fn check_diagnostic_buffer() {
+        let mut data = szl(
+            0x00a0,
+            0,
+            &[&[0x10; 20], &[0x20; 20]]
+        );
+        // a count exceeding the records
+        data[7] = 3;
+        let entries: Vec<DiagnosticEntry> =
+            Szl::decode(&data)
+                .unwrap()
+                .decode_records()
+                .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].event_id, 0x2020);
+        assert_eq!(entries[1].info, [0x20; 10]);
+    }
+
+    #[test]
+    fn check_communication_capabilities() {
+        let mut record = vec![
+            0x00, 0x01, 0x00, 0xf0, 0x00, 0x10,
+            0x00, 0x02, 0xdc, 0x6c, 0x00, 0xb7,
+            0x1b, 0x00,
+        ];
+        record.resize(40, 0);
+        let records: Vec<
+            CommunicationCapabilities
+        > = Szl::decode(&szl(
+            0x0131,
+            1,
+            &[&record]
+        ))
+        .unwrap()
+        .decode_records()
+        .unwrap();
+        assert_eq!(records[0].max_pdu_len, 240);
+        assert_eq!(
+            records[0].max_connections,
+            16
+        );
+        assert_eq!(records[0].mpi_rate, 187_500);
+        assert_eq!(
+            records[0].kbus_rate,
+            12_000_000
+        );
+    }
+}
@@ -0,0 +1,973 @@
+use super::S7Client;
+use crate::{
+    Area, DataSizeType, DataType, ItemError,
+    ItemResult, Result, Value
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap, fs, ops::Range,
+    path::Path
+};
+use thiserror::Error;
+
+/// bytes of a read item besides its data:
+/// variable specification of the request and
+/// data head of the response. Tags closer than
+/// this are read by one item.
+const ITEM_OVERHEAD: usize = 16;
+
+/// A tag table which could not be loaded.
+#[derive(Debug, Error)]
+pub enum TagError {
+    #[error(transparent)]
+    IoErr(#[from] std::io::Error),
+
+    #[error(transparent)]
+    TomlErr(#[from] toml::de::Error),
+
+    #[error(transparent)]
+    JsonErr(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    CsvErr(#[from] csv::Error),
+
+    #[error("InvalidTag `{name}`: {reason}")]
+    InvalidTag { name: String, reason: String },
+
+    #[error("DuplicateTag: {0}")]
+    DuplicateTag(String),
+
+    #[error("UnknownTag: {0}")]
+    UnknownTag(String),
+
+    #[error("UnknownFormat: {0}")]
+    UnknownFormat(String),
+
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String)
+}
+
+/// Linear scaling of a numeric tag: the value
+/// read times `gain`, plus `offset`.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub struct Scaling {
+    #[serde(default = "default_gain")]
+    pub gain:   f64,
+    #[serde(default)]
+    pub offset: f64
+}
+
+fn default_gain() -> f64 {
+    1.0
+}
+
+/// A tag as written in a tag file.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub struct TagDefinition {
+    pub name:        String,
+    /// STEP 7 address, as `DB1.DBW0` or `%MW10`
+    pub address:     String,
+    /// STEP 7 data type, as `Int` or
+    /// `String[20]`
+    #[serde(
+        rename = "type",
+        alias = "data_type"
+    )]
+    pub data_type:   String,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub scaling:     Option<Scaling>,
+    /// change of a numeric value, after scaling,
+    /// not reported by a
+    /// [`Poller`](super::Poller)
+    #[serde(default)]
+    pub deadband:    f64,
+    #[serde(default, alias = "comment")]
+    pub description: String
+}
+
+/// A validated tag: the area accessed by its
+/// data type at its address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name:        String,
+    pub area:        Area,
+    pub data_type:   DataType,
+    pub scaling:     Option<Scaling>,
+    pub deadband:    f64,
+    pub description: String
+}
+
+impl Tag {
+    pub fn from_definition(
+        definition: TagDefinition
+    ) -> std::result::Result<Self, TagError> {
+        let TagDefinition {
+            name,
+            address,
+            data_type,
+            scaling,
+            deadband,
+            description
+        } = definition;
+        let invalid = |reason: String| {
+            TagError::InvalidTag {
+                name: name.clone(),
+                reason
+            }
+        };
+        if name.trim().is_empty() {
+            return Err(invalid(
+                "empty name".to_string()
+            ));
+        }
+        let area = parse_address(&address)
+            .map_err(invalid)?;
+        let area = match area {
+            // the value of one timer or counter
+            Area::Timer(_) | Area::Counter(_) => {
+                if !matches!(
+                    data_type
+                        .to_ascii_uppercase()
+                        .as_str(),
+                    "TIMER" | "COUNTER" | "WORD"
+                ) {
+                    return Err(invalid(
+                        format!(
+                            "{} is a word",
+                            address
+                        )
+                    ));
+                }
+                let area = area.with_data_size(
+                    DataSizeType::Byte {
+                        addr: area.byte_addr(),
+                        len:  1
+                    }
+                );
+                check_end(&area)
+                    .map_err(invalid)?;
+                return Ok(Self {
+                    area,
+                    name,
+                    data_type: DataType::Word,
+                    scaling,
+                    deadband,
+                    description
+                });
+            },
+            area => area
+        };
+        let data_type: DataType = data_type
+            .parse()
+            .map_err(|x: crate::Error| {
+                invalid(x.to_string())
+            })?;
+        let area =
+            data_type.area(&area).map_err(
+                |x| invalid(x.to_string())
+            )?;
+        check_end(&area).map_err(invalid)?;
+        if scaling.is_some()
+            && !is_numeric(data_type)
+        {
+            return Err(invalid(format!(
+                "{:?} is not scaled",
+                data_type
+            )));
+        }
+        if deadband.is_nan() || deadband < 0.0 {
+            return Err(invalid(format!(
+                "invalid deadband {}",
+                deadband
+            )));
+        }
+        Ok(Self {
+            name,
+            area,
+            data_type,
+            scaling,
+            deadband,
+            description
+        })
+    }
+
+    /// the value of the data read at the area of
+    /// the tag, scaled into a `LReal`
+    pub fn value(
+        &self,
+        data: &[u8]
+    ) -> ItemResult<Value> {
+        let value =
+            Value::decode(self.data_type, data)?;
+        let Some(Scaling { gain, offset }) =
+            self.scaling
+        else {
+            return Ok(value);
+        };
+        match value.as_f64() {
+            Some(raw) => Ok(Value::LReal(
+                raw * gain + offset
+            )),
+            None => Ok(value)
+        }
+    }
+}
+
+/// The file of a tag table, in TOML or JSON.
+#[derive(Deserialize)]
+struct TagFile {
+    #[serde(alias = "tag")]
+    tags: Vec<TagDefinition>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTagFile {
+    Table(TagFile),
+    List(Vec<TagDefinition>)
+}
+
+/// Named tags, loaded from TOML, JSON or CSV and
+/// read by name with [`S7Client::read_tags`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagTable {
+    tags: BTreeMap<String, Tag>
+}
+
+impl TagTable {
+    /// the table of `definitions`, each one
+    /// validated, the names unique
+    pub fn new(
+        definitions: impl IntoIterator<
+            Item = TagDefinition
+        >
+    ) -> std::result::Result<Self, TagError> {
+        let mut tags = BTreeMap::new();
+        for definition in definitions {
+            let tag =
+                Tag::from_definition(definition)?;
+            if tags.contains_key(&tag.name) {
+                return Err(
+                    TagError::DuplicateTag(
+                        tag.name
+                    )
+                );
+            }
+            tags.insert(tag.name.clone(), tag);
+        }
+        Ok(Self { tags })
+    }
+
+    /// tags as an array of tables `[[tags]]`, or
+    /// `[[tag]]`, with the fields of
+    /// [`TagDefinition`]
+    pub fn from_toml(
+        s: &str
+    ) -> std::result::Result<Self, TagError> {
+        let file: TagFile = toml::from_str(s)?;
+        Self::new(file.tags)
+    }
+
+    /// tags as an array, or an object with the
+    /// array `tags`
+    pub fn from_json(
+        s: &str
+    ) -> std::result::Result<Self, TagError> {
+        match serde_json::from_str(s)? {
+            JsonTagFile::Table(file) => {
+                Self::new(file.tags)
+            },
+            JsonTagFile::List(tags) => {
+                Self::new(tags)
+            },
+        }
+    }
+
+    /// tags exported from STEP 7 or TIA Portal.
+    /// A header naming the columns `Name`,
+    /// `Address` or `Logical address`, `Data
+    /// type`, `Comment`, `Gain` and `Offset`
+    /// selects them, otherwise the columns are
+    /// those of a STEP 7 symbol table: name,
+    /// address, data type and comment. Rows
+    /// without address, as the tags of optimized
+    /// blocks, are refused.
+    pub fn from_csv(
+        s: &str
+    ) -> std::result::Result<Self, TagError> {
+        let delimiter = if s
+            .lines()
+            .next()
+            .is_some_and(|x| x.contains(';'))
+        {
+            b';'
+        } else {
+            b','
+        };
+        let mut reader =
+            csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .delimiter(delimiter)
+                .from_reader(s.as_bytes());
+        let mut records = reader.records();
+        let mut columns = CsvColumns::default();
+        let mut definitions = Vec::new();
+        let mut first = true;
+        for record in records.by_ref() {
+            let record = record?;
+            if std::mem::take(&mut first) {
+                if let Some(header) =
+                    CsvColumns::header(&record)
+                {
+                    columns = header;
+                    continue;
+                }
+            }
+            if record.iter().all(str::is_empty) {
+                continue;
+            }
+            definitions.push(
+                columns.definition(&record)?
+            );
+        }
+        Self::new(definitions)
+    }
+
+    /// load a file by its extension: `toml`,
+    /// `json`, or `csv` and `sdf` for CSV
+    pub fn load(
+        path: impl AsRef<Path>
+    ) -> std::result::Result<Self, TagError> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path)?;
+        match path
+            .extension()
+            .and_then(|x| x.to_str())
+            .map(|x| x.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("toml") => Self::from_toml(&s),
+            Some("json") => Self::from_json(&s),
+            Some("csv" | "sdf") => {
+                Self::from_csv(&s)
+            },
+            _ => Err(TagError::UnknownFormat(
+                path.display().to_string()
+            ))
+        }
+    }
+
+    pub fn get(
+        &self,
+        name: &str
+    ) -> Option<&Tag> {
+        self.tags.get(name)
+    }
+
+    /// the tags, by name
+    pub fn iter(
+        &self
+    ) -> impl Iterator<Item = &Tag> {
+        self.tags.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+/// The columns of a CSV tag table.
+struct CsvColumns {
+    name:        usize,
+    address:     usize,
+    data_type:   usize,
+    description: Option<usize>,
+    gain:        Option<usize>,
+    offset:      Option<usize>,
+    deadband:    Option<usize>
+}
+
+impl Default for CsvColumns {
+    /// the columns of a STEP 7 symbol table
+    fn default() -> Self {
+        Self {
+            name:        0,
+            address:     1,
+            data_type:   2,
+            description: Some(3),
+            gain:        None,
+            offset:      None,
+            deadband:    None
+        }
+    }
+}
+
+impl CsvColumns {
+    /// the columns named by a header row
+    fn header(
+        record: &csv::StringRecord
+    ) -> Option<Self> {
+        let find = |names: &[&str]| {
+            record.iter().position(|x| {
+                names.contains(
+                    &x.to_ascii_lowercase()
+                        .as_str()
+                )
+            })
+        };
+        Some(Self {
+            name:        find(&[
+                "name", "symbol"
+            ])?,
+            address:     find(&[
+                "address",
+                "logical address"
+            ])?,
+            data_type:   find(&[
+                "data type",
+                "datatype",
+                "type"
+            ])?,
+            description: find(&[
+                "comment",
+                "description"
+            ]),
+            gain:        find(&["gain"]),
+            offset:      find(&["offset"]),
+            deadband:    find(&["deadband"])
+        })
+    }
+
+    fn definition(
+        &self,
+        record: &csv::StringRecord
+    ) -> std::result::Result<
+        TagDefinition,
+        TagError
+    > {
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|x| record.get(x))
+                .unwrap_or_default()
+                .to_string()
+        };
+        let name = field(Some(self.name));
+        let number = |index: Option<usize>| {
+            let value = field(index);
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| {
+                    TagError::InvalidTag {
+                        name:   name.clone(),
+                        reason: format!(
+                            "invalid number {}",
+                            value
+                        )
+                    }
+                })
+        };
+        let gain = number(self.gain)?;
+        let offset = number(self.offset)?;
+        let deadband = number(self.deadband)?;
+        let scaling = (gain.is_some()
+            || offset.is_some())
+        .then(|| Scaling {
+            gain:   gain.unwrap_or(1.0),
+            offset: offset.unwrap_or(0.0)
+        });
+        Ok(TagDefinition {
+            address: field(Some(self.address)),
+            data_type: field(Some(
+                self.data_type
+            )),
+            scaling,
+            deadband: deadband
+                .unwrap_or_default(),
+            description: field(self.description),
+            name
+        })
+    }
+}
+
+/// an address of a tag file: the `%` of TIA
+/// Portal is dropped, and the padding of STEP 7
+/// symbol tables, as `MW     10`
+fn parse_address(
+    address: &str
+) -> std::result::Result<Area, String> {
+    let address =
+        address.trim().trim_start_matches('%');
+    if address.is_empty() {
+        return Err(
+            "no absolute address".to_string()
+        );
+    }
+    address.parse::<Area>().or_else(|err| {
+        address
+            .split_whitespace()
+            .collect::<String>()
+            .parse()
+            .map_err(|_| err.to_string())
+    })
+}
+
+/// the bytes of a tag end within the 64 KiB
+/// addressed by an area
+fn check_end(
+    area: &Area
+) -> std::result::Result<(), String> {
+    let end = area.byte_addr() as usize
+        + area.len() as usize;
+    if end > u16::MAX as usize {
+        return Err(format!(
+            "{:?} ends past byte {}",
+            area,
+            u16::MAX
+        ));
+    }
+    Ok(())
+}
+
+fn is_numeric(data_type: DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Byte
+            | DataType::Word
+            | DataType::DWord
+            | DataType::Int
+            | DataType::DInt
+            | DataType::Real
+            | DataType::LReal
+    )
+}
+
+/// The tags read by one item: a range of bytes
+/// of an area, and the bytes of each tag in it.
+#[derive(Debug, Clone, PartialEq)]
+struct TagRead<'a> {
+    area: Area,
+    tags: Vec<(&'a Tag, Range<usize>)>
+}
+
+/// Merge the tags of the same area, closer than
+/// the overhead of an item, into one read.
+/// Timers and counters are read by their own
+/// items.
+fn plan_tags<'a>(
+    tags: &[&'a Tag]
+) -> Vec<TagRead<'a>> {
+    let mut sorted = tags.to_vec();
+    sorted.sort_by_key(|x| {
+        (area_key(&x.area), x.area.byte_addr())
+    });
+    let mut reads: Vec<TagRead<'a>> = Vec::new();
+    for tag in sorted {
+        // in usize, the end of a tag at the top
+        // of an area being past u16
+        let start = tag.area.byte_addr() as usize;
+        let end = start + tag_len(tag) as usize;
+        if let Some(read) = reads.last_mut() {
+            let read_start =
+                read.area.byte_addr() as usize;
+            let read_end = read_start
+                + read.area.len() as usize;
+            let len =
+                read_end.max(end) - read_start;
+            if mergeable(&read.area, &tag.area)
+                && start
+                    <= read_end + ITEM_OVERHEAD
+                && len <= u16::MAX as usize
+            {
+                read.area =
+                    read.area.with_data_size(
+                        DataSizeType::Byte {
+                            addr: read_start
+                                as u16,
+                            len:  len as u16
+                        }
+                    );
+                read.tags.push((
+                    tag,
+                    start - read_start
+                        ..end - read_start
+                ));
+                continue;
+            }
+        }
+        let area = match (tag.area, *tag.area) {
+            (
+                Area::Timer(_) | Area::Counter(_),
+                _
+            )
+            | (_, DataSizeType::Byte { .. }) => {
+                tag.area
+            },
+            (
+                _,
+                DataSizeType::Bit {
+                    addr, ..
+                }
+            ) => tag.area.with_data_size(
+                DataSizeType::Byte {
+                    addr,
+                    len: 1
+                }
+            )
+        };
+        let len = match area {
+            Area::Timer(_) | Area::Counter(_) => {
+                super::plan::data_len(&area)
+            },
+            _ => end - start
+        };
+        reads.push(TagRead {
+            area,
+            tags: vec![(tag, 0..len)]
+        });
+    }
+    reads
+}
+
+/// the memory of an area and its db number, the
+/// same for the areas of the same memory
+fn area_key(area: &Area) -> (u8, u16) {
+    match area {
+        Area::ProcessInput(_) => (0, 0),
+        Area::ProcessOutput(_) => (1, 0),
+        Area::Merker(_) => (2, 0),
+        Area::DataBausteine(db, _) => (3, *db),
+        Area::V(_) => (4, 0),
+        Area::Timer(_) => (5, 0),
+        Area::Counter(_) => (6, 0)
+    }
+}
+
+fn mergeable(read: &Area, tag: &Area) -> bool {
+    !matches!(
+        read,
+        Area::Timer(_) | Area::Counter(_)
+    ) && area_key(read) == area_key(tag)
+}
+
+/// bytes of the area of a tag, the byte of a bit
+fn tag_len(tag: &Tag) -> u16 {
+    match *tag.area {
+        DataSizeType::Bit { .. } => 1,
+        DataSizeType::Byte { len, .. } => len
+    }
+}
+
+/// the data of `tag` in the bytes read by its
+/// item, a bit as a byte of 0 or 1
+fn tag_data(tag: &Tag, data: &[u8]) -> Vec<u8> {
+    match *tag.area {
+        DataSizeType::Bit {
+            bit_addr, ..
+        } => {
+            vec![data.first().map_or(0, |x| {
+                (x >> bit_addr as u8) & 1
+            })]
+        },
+        DataSizeType::Byte { .. } => data.to_vec()
+    }
+}
+
+impl S7Client {
+    /// read the tags `names` of `table`, the tags
+    /// close to each other read by one item. Each
+    /// tag fails on its own, an unknown name as
+    /// an invalid input.
+    pub async fn read_tags(
+        &self,
+        table: &TagTable,
+        names: &[&str]
+    ) -> Result<BTreeMap<String, ItemResult<Value>>>
+    {
+        let mut values = BTreeMap::new();
+        let mut tags =
+            Vec::with_capacity(names.len());
+        for name in names {
+            match table.get(name) {
+                Some(tag) => tags.push(tag),
+                None => {
+                    values.insert(
+                        name.to_string(),
+                        Err(ItemError::InvalidInput(
+                            format!("unknown tag {}", name)
+                        ))
+                    );
+                }
+            }
+        }
+        let reads = plan_tags(&tags);
+        let areas: Vec<Area> = reads
+            .iter()
+            .map(|x| x.area)
+            .collect();
+        let results =
+            self.read_areas(&areas).await?;
+        for (read, result) in
+            reads.into_iter().zip(results)
+        {
+            for (tag, range) in read.tags {
+                let value = match &result {
+                    Ok(data) => data
+                        .get(range)
+                        .ok_or_else(|| {
+                            ItemError::InvalidValue(
+                                format!(
+                                    "{} is not in the \
+                                     data read",
+                                    tag.name
+                                )
+                            )
+                        })
+                        .and_then(|x| {
+                            tag.value(&tag_data(tag, x))
+                        }),
+                    Err(err) => Err(err.clone())
+                };
+                values.insert(
+                    tag.name.clone(),
+                    value
+                );
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TagError, TagTable, plan_tags};
+    use crate::{
+        Area, DataSizeType, DataType, Value
+    };
+
+    #[test]
+    fn load_formats() {
+        let toml = r#"
+            [[tags]]
+            name = "Motor1.Speed"
+            address = "DB1.DBW0"
+            type = "Int"
+            scaling = { gain = 0.1 }
+            description = "speed in rpm"
+
+            [[tags]]
+            name = "Motor1.Running"
+            address = "%DB1.DBX2.0"
+            type = "Bool"
+        "#;
+        let table =
+            TagTable::from_toml(toml).unwrap();
+        assert_eq!(table.len(), 2);
+        let speed =
+            table.get("Motor1.Speed").unwrap();
+        assert_eq!(
+            speed.area,
+            "DB1.DBW0".parse::<Area>().unwrap()
+        );
+        assert_eq!(
+            speed.data_type,
+            DataType::Int
+        );
+        assert_eq!(
+            speed.value(&[0x00, 0x64]),
+            Ok(Value::LReal(10.0))
+        );
+
+        let json = r#"[
+            {"name": "Level", "address": "MD10", "type": "Real"},
+            {"name": "Text", "address": "DB2.DBB0", "type": "String[10]",
+             "comment": "operator text"}
+        ]"#;
+        let table =
+            TagTable::from_json(json).unwrap();
+        let text = table.get("Text").unwrap();
+        assert_eq!(
+            text.data_type,
+            DataType::String(10)
+        );
+        assert_eq!(
+            text.description,
+            "operator text"
+        );
+        assert_eq!(
+            text.area,
+            "P#DB2.DBX0.0 BYTE 12"
+                .parse::<Area>()
+                .unwrap()
+        );
+
+        // a STEP 7 symbol table, without header
+        let sdf = "\"Start\",\"I      \
+                   0.0\",\"BOOL\",\"start \
+                   button\"\r\n\"Setpoint\",\"\
+                   MW     10\",\"INT\",\"\"\r\n";
+        let table =
+            TagTable::from_csv(sdf).unwrap();
+        assert_eq!(
+            table.get("Setpoint").unwrap().area,
+            "MW10".parse::<Area>().unwrap()
+        );
+
+        // a TIA Portal export, with header
+        let csv = concat!(
+            "Name;Path;Data Type;",
+            "Logical Address;Comment;Gain\n",
+            "Pressure;Default tag table;",
+            "Int;%IW64;bar;0.01\n"
+        );
+        let table =
+            TagTable::from_csv(csv).unwrap();
+        let pressure =
+            table.get("Pressure").unwrap();
+        assert_eq!(pressure.description, "bar");
+        assert_eq!(
+            pressure.scaling.unwrap().gain,
+            0.01
+        );
+    }
+
+    #[test]
+    fn invalid_tables() {
+        assert!(matches!(
+            TagTable::from_json(
+                r#"[{"name": "A", "address": "MW0", "type": "Int"},
+                    {"name": "A", "address": "MW2", "type": "Int"}]"#
+            ),
+            Err(TagError::DuplicateTag(_))
+        ));
+        for (address, data_type) in [
+            ("MW0", "Bool"),
+            ("M0.0", "Int"),
+            ("XW0", "Int"),
+            ("", "Int"),
+            ("MW0", "Time"),
+            ("DB1.DBW65534", "Int"),
+            ("T65535", "Word")
+        ] {
+            let json = format!(
+                r#"[{{"name": "A", "address": "{}", "type": "{}"}}]"#,
+                address, data_type
+            );
+            assert!(matches!(
+                TagTable::from_json(&json),
+                Err(TagError::InvalidTag { .. })
+            ));
+        }
+        assert!(matches!(
+            TagTable::from_json(
+                r#"[{"name": "A", "address": "M0.0", "type": "Bool",
+                     "scaling": {"gain": 2.0}}]"#
+            ),
+            Err(TagError::InvalidTag { .. })
+        ));
+    }
+
+    #[test]
+    fn merge_close_tags() {
+        let table = TagTable::from_json(
+            r#"[
+                {"name": "a", "address": "DB1.DBW0", "type": "Int"},
+                {"name": "b", "address": "DB1.DBX4.1", "type": "Bool"},
+                {"name": "c", "address": "DB1.DBD20", "type": "Real"},
+                {"name": "d", "address": "DB1.DBD100", "type": "Real"},
+                {"name": "e", "address": "DB2.DBW0", "type": "Int"},
+                {"name": "f", "address": "MW0", "type": "Int"},
+                {"name": "g", "address": "T1", "type": "Word"},
+                {"name": "h", "address": "T2", "type": "Word"}
+            ]"#,
+        )
+        .unwrap();
+        let tags: Vec<_> = table.iter().collect();
+        let reads = plan_tags(&tags);
+        let areas: Vec<String> = reads
+            .iter()
+            .map(|x| x.area.to_string())
+            .collect();
+        assert_eq!(
+            areas,
+            [
+                "MW0",
+                "P#DB1.DBX0.0 BYTE 24",
+                "DB1.DBD100",
+                "DB2.DBW0",
+                "T1",
+                "T2"
+            ]
+        );
+        let ranges: Vec<_> = reads[1]
+            .tags
+            .iter()
+            .map(|(tag, range)| {
+                (tag.name.as_str(), range.clone())
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                ("a", 0..2),
+                ("b", 4..5),
+                ("c", 20..24)
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_tags_at_the_top() {
+        let table = TagTable::from_json(
+            r#"[
+                {"name": "a", "address": "DB1.DBW65530", "type": "Int"},
+                {"name": "b", "address": "DB1.DBB65534", "type": "Byte"}
+            ]"#,
+        )
+        .unwrap();
+        let mut tags: Vec<_> =
+            table.iter().cloned().collect();
+        tags.sort_by(|x, y| x.name.cmp(&y.name));
+        // built by hand, past the top of the area
+        tags[1].area = Area::DataBausteine(
+            1,
+            DataSizeType::Byte {
+                addr: 65534,
+                len:  2
+            }
+        );
+        let tags: Vec<_> = tags.iter().collect();
+        let reads = plan_tags(&tags);
+        assert_eq!(reads.len(), 1);
+        assert_eq!(
+            reads[0].area.to_string(),
+            "P#DB1.DBX65530.0 BYTE 6"
+        );
+        assert_eq!(reads[0].tags[1].1, 4..6);
+    }
+}
@@ -0,0 +1,117 @@
+use super::S7Client;
+use crate::{Error, Result};
+use s7_comm::{
+    DataUnit, Frame, FunctionGroup, Method,
+    ReturnCode, UserDataItem, UserDataParameter
+};
+
+/// fragments of a userdata response, far more
+/// than the largest system status list needs at
+/// the smallest pdu
+pub(super) const MAX_FRAGMENTS: usize = 1024;
+
+impl S7Client {
+    /// send a userdata request of `subfunction`
+    /// in `function_group`, and return the data
+    /// of the response. A fragmented response
+    /// is requested fragment by fragment and
+    /// merged, up to [`MAX_FRAGMENTS`].
+    pub(crate) async fn user_data(
+        &self,
+        function_group: FunctionGroup,
+        subfunction: u8,
+        data: UserDataItem
+    ) -> Result<Vec<u8>> {
+        let mut frame = Frame::user_data(0)
+            .request(function_group, subfunction)
+            .data(data)
+            .build();
+        let mut merged = Vec::new();
+        for _ in 0..MAX_FRAGMENTS {
+            let (parameter, item) =
+                user_data_response(
+                    self.request(frame).await?
+                )?;
+            merged.extend(item.data);
+            match parameter.data_unit {
+                Some(DataUnit {
+                    reference,
+                    last: false,
+                    ..
+                }) => {
+                    frame = follow_request(
+                        &parameter, reference
+                    );
+                },
+                _ => return Ok(merged)
+            }
+        }
+        Err(Error::InvalidPlcAnswer(format!(
+            "userdata response of more than {} \
+             fragments",
+            MAX_FRAGMENTS
+        )))
+    }
+}
+
+/// the request of the next fragment of a
+/// fragmented response
+fn follow_request(
+    response: &UserDataParameter,
+    reference: u8
+) -> Frame {
+    Frame::user_data(0)
+        .request(
+            response.function_group,
+            response.subfunction
+        )
+        .method(Method::Response)
+        .sequence_number(response.sequence_number)
+        .data_unit(DataUnit {
+            reference,
+            last: true,
+            error_code: 0
+        })
+        .data(UserDataItem::no_data())
+        .build()
+}
+
+/// the parameter and data of a response. A
+/// response without data, as the one of setting
+/// the clock, has an empty data.
+pub(super) fn user_data_response(
+    frame: Frame
+) -> Result<(UserDataParameter, UserDataItem)> {
+    let Frame::UserData {
+        parameter, data, ..
+    } = frame
+    else {
+        return Err(Error::InvalidPlcAnswer(
+            format!(
+                "should recv userdata, but not \
+                 {:?}",
+                frame
+            )
+        ));
+    };
+    if let Some(DataUnit { error_code, .. }) =
+        parameter.data_unit
+    {
+        if let Some(err) =
+            Error::from_code(error_code)
+        {
+            return Err(err);
+        }
+    }
+    let item = data
+        .unwrap_or_else(UserDataItem::no_data);
+    if item.return_code != ReturnCode::Success
+        && !(item.return_code == ReturnCode::Err
+            && item.data.is_empty())
+    {
+        return Err(Error::ReturnCode(
+            item.return_code
+        ));
+    }
+    Ok((parameter, item))
+}
@@ -0,0 +1,745 @@
+use crate::{Area, DataSizeType, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// the longest WSTRING, whose bytes still fit in
+/// the length of an area
+const WSTRING_MAX_LEN: u16 = 16382;
+
+/// S7 elementary data types
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub enum DataType {
+    Bool,
+    Byte,
+    Word,
+    DWord,
+    Int,
+    DInt,
+    Real,
+    LReal,
+    Char,
+    /// STRING[n]: max length byte, actual length
+    /// byte and n characters
+    String(u8),
+    /// WSTRING[n]: max length word, actual length
+    /// word and n UTF-16 characters
+    WString(u16)
+}
+
+impl DataType {
+    /// Bytes occupied by a value of this type in
+    /// the PLC. A WSTRING longer than 16382
+    /// characters, refused where it is used, is
+    /// counted as one of 16382.
+    pub fn byte_len(&self) -> u16 {
+        match self {
+            DataType::Bool
+            | DataType::Byte
+            | DataType::Char => 1,
+            DataType::Word | DataType::Int => 2,
+            DataType::DWord
+            | DataType::DInt
+            | DataType::Real => 4,
+            DataType::LReal => 8,
+            DataType::String(max_len) => {
+                *max_len as u16 + 2
+            },
+            DataType::WString(max_len) => {
+                (*max_len).min(WSTRING_MAX_LEN)
+                    * 2
+                    + 4
+            },
+        }
+    }
+
+    /// refuse a WSTRING too long for an area
+    fn check(&self) -> Result<()> {
+        match self {
+            DataType::WString(max_len)
+                if *max_len > WSTRING_MAX_LEN =>
+            {
+                Err(Error::InvalidInput(format!(
+                    "WSTRING[{}] exceeds {} \
+                     characters",
+                    max_len, WSTRING_MAX_LEN
+                )))
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// The area accessing a value of this type at
+    /// the start address of `area`.
+    pub fn area(
+        &self,
+        area: &Area
+    ) -> Result<Area> {
+        self.check()?;
+        match (self, **area) {
+            (
+                DataType::Bool,
+                DataSizeType::Bit { .. }
+            ) => Ok(*area),
+            (
+                DataType::Bool,
+                DataSizeType::Byte { .. }
+            ) => {
+                Err(Error::InvalidInput(format!(
+                    "BOOL needs a bit address: \
+                     {:?}",
+                    area
+                )))
+            },
+            (_, DataSizeType::Bit { .. }) => {
+                Err(Error::InvalidInput(format!(
+                    "{:?} needs a byte address: \
+                     {:?}",
+                    self, area
+                )))
+            },
+            (
+                _,
+                DataSizeType::Byte {
+                    addr, ..
+                }
+            ) => Ok(area.with_data_size(
+                DataSizeType::Byte {
+                    addr,
+                    len: self.byte_len()
+                }
+            ))
+        }
+    }
+}
+
+/// The STEP 7 spelling, case insensitive, e.g.
+/// `Int`, `REAL` or `String[20]`. `String` and
+/// `WString` without length are 254 characters.
+impl FromStr for DataType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_ascii_uppercase();
+        let (name, len) = match s.split_once('[')
+        {
+            Some((name, len)) => {
+                let len = len
+                    .strip_suffix(']')
+                    .and_then(|x| {
+                        x.trim()
+                            .parse::<u16>()
+                            .ok()
+                    })
+                    .ok_or_else(|| {
+                        Error::InvalidInput(
+                            format!(
+                                "invalid length \
+                                 of data type {}",
+                                s
+                            )
+                        )
+                    })?;
+                (name.trim(), Some(len))
+            },
+            None => (s.as_str(), None)
+        };
+        match (name, len) {
+            ("BOOL", None) => Ok(DataType::Bool),
+            ("BYTE", None) => Ok(DataType::Byte),
+            ("WORD", None) => Ok(DataType::Word),
+            ("DWORD", None) => {
+                Ok(DataType::DWord)
+            },
+            ("INT", None) => Ok(DataType::Int),
+            ("DINT", None) => Ok(DataType::DInt),
+            ("REAL", None) => Ok(DataType::Real),
+            ("LREAL", None) => {
+                Ok(DataType::LReal)
+            },
+            ("CHAR", None) => Ok(DataType::Char),
+            ("STRING", len)
+                if len.unwrap_or(254) <= 254 =>
+            {
+                Ok(DataType::String(
+                    len.unwrap_or(254) as u8
+                ))
+            },
+            ("WSTRING", len)
+                if len.unwrap_or(254)
+                    <= WSTRING_MAX_LEN =>
+            {
+                Ok(DataType::WString(
+                    len.unwrap_or(254)
+                ))
+            },
+            _ => {
+                Err(Error::InvalidInput(format!(
+                    "unsupported data type {}",
+                    s
+                )))
+            },
+        }
+    }
+}
+
+/// A value of an S7 elementary data type
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub enum Value {
+    Bool(bool),
+    Byte(u8),
+    Word(u16),
+    DWord(u32),
+    Int(i16),
+    DInt(i32),
+    Real(f32),
+    LReal(f64),
+    Char(char),
+    String(String),
+    WString(String)
+}
+
+impl Value {
+    /// the number of a numeric value
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Byte(x) => Some(*x as f64),
+            Value::Word(x) => Some(*x as f64),
+            Value::DWord(x) => Some(*x as f64),
+            Value::Int(x) => Some(*x as f64),
+            Value::DInt(x) => Some(*x as f64),
+            Value::Real(x) => Some(*x as f64),
+            Value::LReal(x) => Some(*x),
+            _ => None
+        }
+    }
+
+    /// Decode the big-endian PLC representation
+    /// of `data_type`.
+    pub fn decode(
+        data_type: DataType,
+        data: &[u8]
+    ) -> Result<Self> {
+        data_type.check()?;
+        let len = data_type.byte_len() as usize;
+        if data.len() < len {
+            return Err(Error::InvalidValue(
+                format!(
+                    "{:?} needs {} bytes, but \
+                     recv {}",
+                    data_type,
+                    len,
+                    data.len()
+                )
+            ));
+        }
+        Ok(match data_type {
+            DataType::Bool => {
+                Value::Bool(data[0] & 0x01 > 0)
+            },
+            DataType::Byte => {
+                Value::Byte(data[0])
+            },
+            DataType::Char => {
+                Value::Char(data[0] as char)
+            },
+            DataType::Word => {
+                Value::Word(u16::from_be_bytes([
+                    data[0], data[1]
+                ]))
+            },
+            DataType::Int => {
+                Value::Int(i16::from_be_bytes([
+                    data[0], data[1]
+                ]))
+            },
+            DataType::DWord => {
+                Value::DWord(u32::from_be_bytes(
+                    data[0..4]
+                        .try_into()
+                        .unwrap()
+                ))
+            },
+            DataType::DInt => {
+                Value::DInt(i32::from_be_bytes(
+                    data[0..4]
+                        .try_into()
+                        .unwrap()
+                ))
+            },
+            DataType::Real => {
+                Value::Real(f32::from_be_bytes(
+                    data[0..4]
+                        .try_into()
+                        .unwrap()
+                ))
+            },
+            DataType::LReal => {
+                Value::LReal(f64::from_be_bytes(
+                    data[0..8]
+                        .try_into()
+                        .unwrap()
+                ))
+            },
+            DataType::String(max_len) => {
+                let cur_len = data[1];
+                if cur_len > data[0]
+                    || cur_len > max_len
+                {
+                    return Err(
+                        Error::InvalidValue(
+                            format!(
+                                "STRING[{}] \
+                                 with max length \
+                                 {} has invalid \
+                                 length {}",
+                                max_len,
+                                data[0],
+                                cur_len
+                            )
+                        )
+                    );
+                }
+                Value::String(
+                    data[2..2 + cur_len as usize]
+                        .iter()
+                        .map(|x| *x as char)
+                        .collect()
+                )
+            },
+            DataType::WString(max_len) => {
+                let declared =
+                    u16::from_be_bytes([
+                        data[0], data[1]
+                    ]);
+                let cur_len =
+                    u16::from_be_bytes([
+                        data[2], data[3]
+                    ]);
+                if cur_len > declared
+                    || cur_len > max_len
+                {
+                    return Err(
+                        Error::InvalidValue(
+                            format!(
+                                "WSTRING[{}] \
+                                 with max length \
+                                 {} has invalid \
+                                 length {}",
+                                max_len,
+                                declared,
+                                cur_len
+                            )
+                        )
+                    );
+                }
+                let chars: Vec<u16> = data
+                    [4..4 + cur_len as usize * 2]
+                    .chunks_exact(2)
+                    .map(|x| {
+                        u16::from_be_bytes([
+                            x[0], x[1]
+                        ])
+                    })
+                    .collect();
+                Value::WString(
+                    String::from_utf16(&chars)
+                        .map_err(|x| {
+                            Error::InvalidValue(
+                                x.to_string()
+                            )
+                        })?
+                )
+            }
+        })
+    }
+
+    /// Encode to the big-endian PLC
+    /// representation of `data_type`, padded
+    /// to its full length.
+    pub fn encode(
+        &self,
+        data_type: DataType
+    ) -> Result<Vec<u8>> {
+        data_type.check()?;
+        let mut data = match (self, data_type) {
+            (
+                Value::Bool(val),
+                DataType::Bool
+            ) => vec![*val as u8],
+            (
+                Value::Byte(val),
+                DataType::Byte
+            ) => vec![*val],
+            (
+                Value::Word(val),
+                DataType::Word
+            ) => val.to_be_bytes().to_vec(),
+            (
+                Value::DWord(val),
+                DataType::DWord
+            ) => val.to_be_bytes().to_vec(),
+            (Value::Int(val), DataType::Int) => {
+                val.to_be_bytes().to_vec()
+            },
+            (
+                Value::DInt(val),
+                DataType::DInt
+            ) => val.to_be_bytes().to_vec(),
+            (
+                Value::Real(val),
+                DataType::Real
+            ) => val.to_be_bytes().to_vec(),
+            (
+                Value::LReal(val),
+                DataType::LReal
+            ) => val.to_be_bytes().to_vec(),
+            (
+                Value::Char(val),
+                DataType::Char
+            ) => {
+                vec![latin1(*val)?]
+            },
+            (
+                Value::String(val),
+                DataType::String(max_len)
+            ) => {
+                let chars = val
+                    .chars()
+                    .map(latin1)
+                    .collect::<Result<Vec<u8>>>(
+                    )?;
+                if chars.len() > max_len as usize
+                {
+                    return Err(Error::InvalidValue(format!(
+                        "{} chars exceed STRING[{}]",
+                        chars.len(),
+                        max_len
+                    )));
+                }
+                let mut data = vec![
+                    max_len,
+                    chars.len() as u8,
+                ];
+                data.extend(chars);
+                data
+            },
+            (
+                Value::WString(val),
+                DataType::WString(max_len)
+            ) => {
+                let chars: Vec<u16> =
+                    val.encode_utf16().collect();
+                if chars.len() > max_len as usize
+                {
+                    return Err(Error::InvalidValue(format!(
+                        "{} chars exceed WSTRING[{}]",
+                        chars.len(),
+                        max_len
+                    )));
+                }
+                let mut data = Vec::with_capacity(
+                    data_type.byte_len() as usize
+                );
+                data.extend(
+                    max_len.to_be_bytes()
+                );
+                data.extend(
+                    (chars.len() as u16)
+                        .to_be_bytes()
+                );
+                chars.iter().for_each(|x| {
+                    data.extend(x.to_be_bytes())
+                });
+                data
+            },
+            (val, data_type) => {
+                return Err(Error::InvalidValue(
+                    format!(
+                        "{:?} is not a value of \
+                         {:?}",
+                        val, data_type
+                    )
+                ));
+            }
+        };
+        data.resize(
+            data_type.byte_len() as usize,
+            0
+        );
+        Ok(data)
+    }
+}
+
+fn latin1(val: char) -> Result<u8> {
+    u8::try_from(val as u32).map_err(|_| {
+        Error::InvalidValue(format!(
+            "{:?} is not a CHAR",
+            val
+        ))
+    })
+}
+
+/// Rust types which map onto an S7 elementary
+/// data type.
+pub trait S7Value: Sized {
+    /// The data type used to access `area`.
+    fn data_type(area: &Area)
+    -> Result<DataType>;
+
+    fn from_value(value: Value) -> Result<Self>;
+
+    fn into_value(self) -> Value;
+}
+
+macro_rules! impl_s7_value {
+    ($ty:ty, $variant:ident) => {
+        impl S7Value for $ty {
+            fn data_type(
+                _area: &Area
+            ) -> Result<DataType> {
+                Ok(DataType::$variant)
+            }
+
+            fn from_value(
+                value: Value
+            ) -> Result<Self> {
+                match value {
+                    Value::$variant(val) => {
+                        Ok(val)
+                    },
+                    val => {
+                        Err(Error::InvalidValue(
+                            format!(
+                                "{:?} is not a \
+                                 {}",
+                                val,
+                                stringify!(
+                                    $variant
+                                )
+                            )
+                        ))
+                    },
+                }
+            }
+
+            fn into_value(self) -> Value {
+                Value::$variant(self)
+            }
+        }
+    };
+}
+
+impl_s7_value!(bool, Bool);
+impl_s7_value!(u8, Byte);
+impl_s7_value!(u16, Word);
+impl_s7_value!(u32, DWord);
+impl_s7_value!(i16, Int);
+impl_s7_value!(i32, DInt);
+impl_s7_value!(f32, Real);
+impl_s7_value!(f64, LReal);
+impl_s7_value!(char, Char);
+
+/// STRING, sized by the length of the area: max
+/// length + 2
+impl S7Value for String {
+    fn data_type(
+        area: &Area
+    ) -> Result<DataType> {
+        match **area {
+            DataSizeType::Byte {
+                len, ..
+            } if (3..=256).contains(&len) => Ok(
+                DataType::String((len - 2) as u8)
+            ),
+            _ => {
+                Err(Error::InvalidInput(format!(
+                    "STRING needs 3 to 256 \
+                     bytes: {:?}",
+                    area
+                )))
+            },
+        }
+    }
+
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::String(val) => Ok(val),
+            val => {
+                Err(Error::InvalidValue(format!(
+                    "{:?} is not a STRING",
+                    val
+                )))
+            },
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+/// WSTRING, sized by the length of the area: max
+/// length * 2
+/// + 4
+#[derive(
+    Debug,
+    Clone,
+    Eq,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub struct WString(pub String);
+
+impl S7Value for WString {
+    fn data_type(
+        area: &Area
+    ) -> Result<DataType> {
+        match **area {
+            DataSizeType::Byte {
+                len, ..
+            } if len >= 6 && len % 2 == 0 => Ok(
+                DataType::WString((len - 4) / 2)
+            ),
+            _ => {
+                Err(Error::InvalidInput(format!(
+                    "WSTRING needs an even \
+                     length of at least 6 \
+                     bytes: {:?}",
+                    area
+                )))
+            },
+        }
+    }
+
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::WString(val) => {
+                Ok(WString(val))
+            },
+            val => {
+                Err(Error::InvalidValue(format!(
+                    "{:?} is not a WSTRING",
+                    val
+                )))
+            },
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::WString(self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DataType, Value};
+
+    #[test]
+    fn check_numbers() {
+        let data = Value::Real(1.5)
+            .encode(DataType::Real)
+            .unwrap();
+        assert_eq!(data, [0x3f, 0xc0, 0, 0]);
+        let data = Value::Int(-2)
+            .encode(DataType::Int)
+            .unwrap();
+        assert_eq!(data, [0xff, 0xfe]);
+        assert_eq!(
+            Value::decode(
+                DataType::DInt,
+                &[0, 0, 0x01, 0x2c]
+            )
+            .unwrap(),
+            Value::DInt(300)
+        );
+        assert!(
+            Value::Int(1)
+                .encode(DataType::Word)
+                .is_err()
+        );
+        assert!(
+            Value::decode(
+                DataType::LReal,
+                &[0; 4]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn check_string() {
+        let ty = DataType::String(4);
+        let data =
+            Value::String("ab".to_string())
+                .encode(ty)
+                .unwrap();
+        assert_eq!(
+            data,
+            [4, 2, b'a', b'b', 0, 0]
+        );
+        assert_eq!(
+            Value::decode(ty, &data).unwrap(),
+            Value::String("ab".to_string())
+        );
+        assert!(
+            Value::String("abcde".to_string())
+                .encode(ty)
+                .is_err()
+        );
+        assert!(
+            Value::decode(
+                ty,
+                &[4, 5, 0, 0, 0, 0]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn check_wstring() {
+        let ty = DataType::WString(2);
+        let data =
+            Value::WString("ä".to_string())
+                .encode(ty)
+                .unwrap();
+        assert_eq!(
+            data,
+            [0, 2, 0, 1, 0, 0xe4, 0, 0]
+        );
+        assert_eq!(
+            Value::decode(ty, &data).unwrap(),
+            Value::WString("ä".to_string())
+        );
+
+        // longer than the bytes of an area
+        let ty = DataType::WString(40000);
+        assert_eq!(ty.byte_len(), 16382 * 2 + 4);
+        assert!(
+            Value::WString("a".to_string())
+                .encode(ty)
+                .is_err()
+        );
+        assert!(
+            Value::decode(ty, &[0; 8]).is_err()
+        );
+        assert!(
+            ty.area(&"DB1.DBB0".parse().unwrap())
+                .is_err()
+        );
+    }
+}
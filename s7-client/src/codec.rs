@@ -1,10 +1,18 @@
 use std::ops::{Deref, DerefMut};
 
+/// Encodes the s7 frames, split into the dt data
+/// tpdus of the tpdu size of the connection.
 #[derive(Default)]
-pub struct S7Encoder(tpkt::TpktEncoder<copt::CoptEncoder<s7_comm::S7CommEncoder>>);
+pub struct S7Encoder(copt::SegmentEncoder<s7_comm::S7CommEncoder>);
+
+impl S7Encoder {
+    pub fn new(tpdu_size: copt::TpduSize) -> Self {
+        Self(copt::SegmentEncoder::new(s7_comm::S7CommEncoder, tpdu_size))
+    }
+}
 
 impl Deref for S7Encoder {
-    type Target = tpkt::TpktEncoder<copt::CoptEncoder<s7_comm::S7CommEncoder>>;
+    type Target = copt::SegmentEncoder<s7_comm::S7CommEncoder>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -1,6 +1,729 @@
-use std::io;
+use crate::Area;
+use copt::{DisconnectReason, RejectCause};
+use s7_comm::{
+    BlockType, HearderAckData, ReturnCode
+};
+use std::{fmt, io, io::ErrorKind};
 use thiserror::Error;
 
+/// The error class and code of an ack data
+/// header, or the error code of a userdata
+/// response, in the same space.
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Hash,
+)]
+pub struct ErrorCode {
+    pub class: u8,
+    pub code:  u8
+}
+
+impl ErrorCode {
+    pub fn new(class: u8, code: u8) -> Self {
+        Self { class, code }
+    }
+
+    /// the class and the code as one value, as
+    /// `0xd209`
+    pub fn value(&self) -> u16 {
+        u16::from_be_bytes([
+            self.class, self.code
+        ])
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.value() == 0
+    }
+
+    /// the description of the error class
+    pub fn class_text(&self) -> &'static str {
+        match self.class {
+            0x00 => "no error",
+            0x81 => "application relationship",
+            0x82 => "object definition",
+            0x83 => "no resources available",
+            0x84 => "error on service processing",
+            0x85 => "error on supplies",
+            0x87 => "access error",
+            0xd0..=0xdf => "function error",
+            _ => "unknown error class"
+        }
+    }
+
+    /// the description of the error, the class
+    /// description for an unknown code
+    pub fn text(&self) -> &'static str {
+        match self.value() {
+            0x0000 => "no error",
+            0x0110 => "invalid block number",
+            0x0111 => "invalid request length",
+            0x0112 => "invalid parameter",
+            0x0113 => "invalid block type",
+            0x0114 => "block not found",
+            0x0115 => "block already exists",
+            0x0116 => "block is write protected",
+            0x0117 => {
+                "block or operating system \
+                 update too large"
+            },
+            0x0118 => "invalid block number",
+            0x0119 => {
+                "incorrect password entered"
+            },
+            0x011a => "pg resource error",
+            0x011b => "plc resource error",
+            0x011c => "protocol error",
+            0x011d => "too many blocks",
+            0x011e => {
+                "no longer a connection to the \
+                 database, or s7dos handle is \
+                 invalid"
+            },
+            0x011f => "result buffer too small",
+            0x0120 => "end of block list",
+            0x0140 => {
+                "insufficient memory available"
+            },
+            0x0141 => {
+                "job cannot be processed because \
+                 of a lack of resources"
+            },
+            0x8001 => {
+                "the requested service cannot be \
+                 performed while the block is in \
+                 the current status"
+            },
+            0x8003 => {
+                "s7 protocol error: error \
+                 occurred while transferring the \
+                 block"
+            },
+            0x8100 => {
+                "application, general error: \
+                 service unknown to remote module"
+            },
+            0x8104 => {
+                "this service is not implemented \
+                 on the module or a frame error \
+                 was reported"
+            },
+            0x8204 => {
+                "the type specification for the \
+                 object is inconsistent"
+            },
+            0x8205 => {
+                "a copied block already exists \
+                 and is not linked"
+            },
+            0x8301 => {
+                "insufficient memory space or \
+                 work memory on the module"
+            },
+            0x8302 => {
+                "too few resources available or \
+                 the processor resources are not \
+                 available"
+            },
+            0x8304 => {
+                "no further parallel upload \
+                 possible, there is a resource \
+                 bottleneck"
+            },
+            0x8305 => "function not available",
+            0x8306 => "insufficient work memory",
+            0x8307 => {
+                "not enough retentive work memory"
+            },
+            0x8401 => {
+                "s7 protocol error: invalid \
+                 service sequence"
+            },
+            0x8402 => {
+                "service cannot execute owing to \
+                 status of the addressed object"
+            },
+            0x8404 => {
+                "s7 protocol: the function \
+                 cannot be performed"
+            },
+            0x8405 => {
+                "remote block is in disable state"
+            },
+            0x8500 => {
+                "s7 protocol error: wrong frames"
+            },
+            0x8503 => {
+                "alarm from the module: service \
+                 canceled prematurely"
+            },
+            0x8701 => {
+                "error addressing the object on \
+                 the communications partner"
+            },
+            0x8702 => {
+                "the requested service is not \
+                 supported by the module"
+            },
+            0x8703 => "access to object refused",
+            0x8704 => {
+                "access error: object damaged"
+            },
+            0xd001 => {
+                "protocol error: illegal job \
+                 number"
+            },
+            0xd002 => {
+                "parameter error: illegal job \
+                 variant"
+            },
+            0xd003 => {
+                "parameter error: debugging \
+                 function not supported by module"
+            },
+            0xd004 => {
+                "parameter error: illegal job \
+                 status"
+            },
+            0xd005 => {
+                "parameter error: illegal job \
+                 termination"
+            },
+            0xd006 => {
+                "parameter error: illegal link \
+                 disconnection id"
+            },
+            0xd007 => {
+                "parameter error: illegal number \
+                 of buffer elements"
+            },
+            0xd008 => {
+                "parameter error: illegal scan \
+                 rate"
+            },
+            0xd009 => {
+                "parameter error: illegal number \
+                 of executions"
+            },
+            0xd00a => {
+                "parameter error: illegal \
+                 trigger event"
+            },
+            0xd00b => {
+                "parameter error: illegal \
+                 trigger condition"
+            },
+            0xd011 => {
+                "parameter error in path of the \
+                 call environment: block does \
+                 not exist"
+            },
+            0xd012 => {
+                "parameter error: wrong address \
+                 in block"
+            },
+            0xd014 => {
+                "parameter error: block being \
+                 deleted/overwritten"
+            },
+            0xd015 => {
+                "parameter error: illegal tag \
+                 address"
+            },
+            0xd016 => {
+                "parameter error: test jobs not \
+                 possible, because of errors in \
+                 user program"
+            },
+            0xd017 => {
+                "parameter error: illegal \
+                 trigger number"
+            },
+            0xd025 => {
+                "parameter error: invalid path"
+            },
+            0xd026 => {
+                "parameter error: illegal access \
+                 type"
+            },
+            0xd027 => {
+                "parameter error: this number of \
+                 data blocks is not permitted"
+            },
+            0xd031 => "internal protocol error",
+            0xd032 => {
+                "parameter error: wrong result \
+                 buffer length"
+            },
+            0xd033 => {
+                "protocol error: wrong job length"
+            },
+            0xd03f => {
+                "coding error: error in \
+                 parameter section"
+            },
+            0xd041 => {
+                "data error: illegal status list \
+                 id"
+            },
+            0xd042 => {
+                "data error: illegal tag address"
+            },
+            0xd043 => {
+                "data error: referenced job not \
+                 found, check job data"
+            },
+            0xd044 => {
+                "data error: illegal tag value, \
+                 check job data"
+            },
+            0xd045 => {
+                "data error: exiting the odis \
+                 control is not allowed in hold"
+            },
+            0xd046 => {
+                "data error: illegal measuring \
+                 stage during run-time \
+                 measurement"
+            },
+            0xd047 => {
+                "data error: illegal hierarchy \
+                 in 'read job list'"
+            },
+            0xd048 => {
+                "data error: illegal deletion id \
+                 in 'delete job'"
+            },
+            0xd049 => {
+                "invalid substitute id in \
+                 'replace job'"
+            },
+            0xd04a => {
+                "error executing 'program status'"
+            },
+            0xd05f => {
+                "coding error: error in data \
+                 section"
+            },
+            0xd061 => {
+                "resource error: no memory space \
+                 for job"
+            },
+            0xd062 => {
+                "resource error: job list full"
+            },
+            0xd063 => {
+                "resource error: trigger event \
+                 occupied"
+            },
+            0xd064 => {
+                "resource error: not enough \
+                 memory space for one result \
+                 buffer element"
+            },
+            0xd065 => {
+                "resource error: not enough \
+                 memory space for several result \
+                 buffer elements"
+            },
+            0xd066 => {
+                "resource error: the timer \
+                 available for run-time \
+                 measurement is occupied by \
+                 another job"
+            },
+            0xd067 => {
+                "resource error: too many \
+                 'modify tag' jobs active"
+            },
+            0xd081 => {
+                "function not permitted in \
+                 current mode"
+            },
+            0xd082 => {
+                "mode error: cannot exit hold \
+                 mode"
+            },
+            0xd0a1 => {
+                "function not permitted in \
+                 current protection level"
+            },
+            0xd0a2 => {
+                "function not possible at \
+                 present, because a function is \
+                 running that modifies memory"
+            },
+            0xd0a3 => {
+                "too many 'modify tag' jobs \
+                 active on the i/o"
+            },
+            0xd0a4 => {
+                "'forcing' has already been \
+                 established"
+            },
+            0xd0a5 => "referenced job not found",
+            0xd0a6 => {
+                "job cannot be disabled/enabled"
+            },
+            0xd0a7 => {
+                "job cannot be deleted, for \
+                 example because it is currently \
+                 being read"
+            },
+            0xd0a8 => {
+                "job cannot be replaced, for \
+                 example because it is currently \
+                 being read or deleted"
+            },
+            0xd0a9 => {
+                "job cannot be read, for example \
+                 because it is currently being \
+                 deleted"
+            },
+            0xd0aa => {
+                "time limit exceeded in \
+                 processing operation"
+            },
+            0xd0ab => {
+                "invalid job parameters in \
+                 process operation"
+            },
+            0xd0ac => {
+                "invalid job data in process \
+                 operation"
+            },
+            0xd0ad => {
+                "operating mode already set"
+            },
+            0xd0ae => {
+                "the job was set up over a \
+                 different connection and can \
+                 only be handled over this \
+                 connection"
+            },
+            0xd0c1 => {
+                "at least one error has been \
+                 detected while accessing the \
+                 tag(s)"
+            },
+            0xd0c2 => "change to stop/hold mode",
+            0xd0c3 => {
+                "at least one error was detected \
+                 while accessing the tag(s), \
+                 mode change to stop/hold"
+            },
+            0xd0c4 => {
+                "timeout during run-time \
+                 measurement"
+            },
+            0xd0c5 => {
+                "display of block stack \
+                 inconsistent, because blocks \
+                 were deleted/reloaded"
+            },
+            0xd0c6 => {
+                "job was automatically deleted \
+                 as the jobs it referenced have \
+                 been deleted"
+            },
+            0xd0c7 => {
+                "the job was automatically \
+                 deleted because stop mode was \
+                 exited"
+            },
+            0xd0c8 => {
+                "'block status' aborted because \
+                 of inconsistencies between test \
+                 job and running program"
+            },
+            0xd0c9 => {
+                "exit the status area by \
+                 resetting ob90"
+            },
+            0xd0ca => {
+                "exiting the status range by \
+                 resetting ob90 and access error \
+                 reading tags before exiting"
+            },
+            0xd0cb => {
+                "the output disable for the \
+                 peripheral outputs has been \
+                 activated again"
+            },
+            0xd0cc => {
+                "the amount of data for the \
+                 debugging functions is \
+                 restricted by the time limit"
+            },
+            0xd201 => {
+                "syntax error in block name"
+            },
+            0xd202 => {
+                "syntax error in function \
+                 parameters"
+            },
+            0xd203 => {
+                "syntax error in block type"
+            },
+            0xd204 => {
+                "no linked block in storage \
+                 medium"
+            },
+            0xd205 => "object already exists",
+            0xd206 => "object already exists",
+            0xd207 => "block exists in eprom",
+            0xd209 => "block does not exist",
+            0xd20e => "no block available",
+            0xd210 => "block number too big",
+            0xd240 => {
+                "coordination rules were violated"
+            },
+            0xd241 => {
+                "function not permitted in \
+                 current protection level"
+            },
+            0xd242 => {
+                "protection violation while \
+                 processing f blocks"
+            },
+            0xd250 => {
+                "update and module id or version \
+                 do not match"
+            },
+            0xd251 => {
+                "incorrect sequence of operating \
+                 system components"
+            },
+            0xd252 => "checksum error",
+            0xd253 => {
+                "no executable loader available, \
+                 update only possible using a \
+                 memory card"
+            },
+            0xd254 => {
+                "storage error in operating \
+                 system"
+            },
+            0xd280 => {
+                "error compiling block in s7-300 \
+                 cpu"
+            },
+            0xd2a1 => {
+                "another block function or a \
+                 trigger on a block is active"
+            },
+            0xd2a2 => {
+                "a trigger is active on a block, \
+                 complete the debugging function \
+                 first"
+            },
+            0xd2a3 => {
+                "the block is not active \
+                 (linked), the block is occupied \
+                 or the block is currently \
+                 marked for deletion"
+            },
+            0xd2a4 => {
+                "the block is already being \
+                 processed by another block \
+                 function"
+            },
+            0xd2a6 => {
+                "it is not possible to save and \
+                 change the user program \
+                 simultaneously"
+            },
+            0xd2a7 => {
+                "the block has the attribute \
+                 'unlinked' or is not processed"
+            },
+            0xd2a8 => {
+                "an active debugging function is \
+                 preventing parameters from \
+                 being assigned to the cpu"
+            },
+            0xd2a9 => {
+                "new parameters are being \
+                 assigned to the cpu"
+            },
+            0xd2aa => {
+                "new parameters are currently \
+                 being assigned to the modules"
+            },
+            0xd2ab => {
+                "the dynamic configuration \
+                 limits are currently being \
+                 changed"
+            },
+            0xd2ac => {
+                "a running active or deactivate \
+                 assignment (sfc 12) is \
+                 temporarily preventing r-kill \
+                 from being executed"
+            },
+            0xd2b0 => {
+                "an error occurred while \
+                 configuring in run (cir)"
+            },
+            0xd2c0 => {
+                "the maximum number of \
+                 technological objects has been \
+                 exceeded"
+            },
+            0xd2c1 => {
+                "the same technology data block \
+                 already exists on the module"
+            },
+            0xd2c2 => {
+                "downloading the user program or \
+                 downloading the hardware \
+                 configuration is not possible"
+            },
+            0xd401 => {
+                "information function unavailable"
+            },
+            0xd402 => {
+                "information function unavailable"
+            },
+            0xd403 => {
+                "service has already been logged \
+                 on/off (diagnostics/pmc)"
+            },
+            0xd404 => {
+                "maximum number of nodes \
+                 reached, no more logons \
+                 possible for diagnostics/pmc"
+            },
+            0xd405 => {
+                "service not supported or syntax \
+                 error in function parameters"
+            },
+            0xd406 => {
+                "required information currently \
+                 unavailable"
+            },
+            0xd407 => {
+                "diagnostics error occurred"
+            },
+            0xd408 => "update aborted",
+            0xd409 => "error on dp bus",
+            0xd601 => {
+                "syntax error in function \
+                 parameter"
+            },
+            0xd602 => {
+                "incorrect password entered"
+            },
+            0xd603 => {
+                "the connected resource is \
+                 already legitimized"
+            },
+            0xd604 => {
+                "the connected resource is \
+                 already enabled"
+            },
+            0xd605 => {
+                "legitimization not possible \
+                 because password does not exist"
+            },
+            0xd801 => {
+                "at least one tag address is \
+                 invalid"
+            },
+            0xd802 => {
+                "specified job does not exist"
+            },
+            0xd803 => "illegal job status",
+            0xd804 => {
+                "illegal cycle time (illegal \
+                 time base or multiple)"
+            },
+            0xd805 => {
+                "no more cyclic read jobs can be \
+                 set up"
+            },
+            0xd806 => {
+                "the referenced job is in a \
+                 state in which the requested \
+                 function cannot be performed"
+            },
+            0xd807 => {
+                "function aborted due to \
+                 overload, meaning executing the \
+                 read cycle takes longer than \
+                 the set scan cycle time"
+            },
+            0xdc01 => "date and/or time invalid",
+            0xe201 => "cpu is already the master",
+            0xe202 => {
+                "connect and update not possible \
+                 due to different user program \
+                 in flash module"
+            },
+            0xe203 => {
+                "connect and update not possible \
+                 due to different firmware"
+            },
+            0xe204 => {
+                "connect and update not possible \
+                 due to different memory \
+                 configuration"
+            },
+            0xe205 => {
+                "connect/update aborted due to \
+                 synchronization error"
+            },
+            0xe206 => {
+                "connect/update denied due to \
+                 coordination violation"
+            },
+            0xef01 => {
+                "s7 protocol error: error at \
+                 id2, only 00h permitted in job"
+            },
+            0xef02 => {
+                "s7 protocol error: error at \
+                 id2, set of resources does not \
+                 exist"
+            },
+            _ => self.class_text()
+        }
+    }
+}
+
+impl From<u16> for ErrorCode {
+    fn from(value: u16) -> Self {
+        let [class, code] = value.to_be_bytes();
+        Self { class, code }
+    }
+}
+
+impl From<&HearderAckData> for ErrorCode {
+    fn from(header: &HearderAckData) -> Self {
+        Self::new(
+            header.error_class(),
+            header.error_code()
+        )
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{:#06x} {}: {}",
+            self.value(),
+            self.class_text(),
+            self.text()
+        )
+    }
+}
+
+/// The errors of the client. The transport
+/// errors are the io, tpkt and cotp ones, the
+/// protocol errors those reported by the plc in
+/// a header, a userdata response or an item.
 #[derive(Debug, Error)]
 pub enum Error {
     #[error(transparent)]
@@ -9,8 +732,20 @@ pub enum Error {
     #[error(transparent)]
     TpktErr(#[from] tpkt::Error),
 
-    #[error("Error: {0}")]
-    Err(String),
+    /// the cotp connection refused or not
+    /// confirmed
+    #[error("Cotp: {0}")]
+    Cotp(String),
+
+    /// the connection refused, or released, by
+    /// a disconnect request of the plc
+    #[error("Disconnected: {0}")]
+    Disconnected(DisconnectReason),
+
+    /// a tpdu rejected by the plc with an error
+    /// tpdu
+    #[error("CotpRejected: {0}")]
+    CotpRejected(RejectCause),
 
     #[error("WriteTimeout")]
     WriteTimeout,
@@ -18,66 +753,334 @@ pub enum Error {
     #[error("ReadTimeout")]
     ReadTimeout,
 
-    #[error("Error: {0}")]
-    ConnectErr(String),
+    #[error("ConnectionClosed: {0}")]
+    ConnectionClosed(String),
+
+    /// a frame or a data not expected from the
+    /// plc
+    #[error("InvalidPlcAnswer: {0}")]
+    InvalidPlcAnswer(String),
+
+    /// an error class and code of an ack data
+    /// header, or the error code of a userdata
+    /// response
+    #[error("Protocol: {0}")]
+    Protocol(ErrorCode),
+
+    /// the return code of the item `index` of a
+    /// read or write, accessing `area`
+    #[error(
+        "Item {index} {area:?}: {return_code:?}"
+    )]
+    Item {
+        index:       usize,
+        area:        Area,
+        return_code: ReturnCode
+    },
+
+    /// the return code of the data of a userdata
+    /// response
+    #[error("ReturnCode: {0:?}")]
+    ReturnCode(ReturnCode),
 
     #[error("InvalidBitAddr: {0}")]
     InvalidBitAddr(u16),
+
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String),
+
+    #[error("InvalidValue: {0}")]
+    InvalidValue(String),
+
+    #[error("CannotStartPlc")]
+    CannotStartPlc,
+
+    #[error("AlreadyRun")]
+    AlreadyRun,
+
+    #[error("CannotStopPlc")]
+    CannotStopPlc,
+
+    #[error("AlreadyStop")]
+    AlreadyStop,
+
+    #[error("CannotCopyRamToRom")]
+    CannotCopyRamToRom,
+
+    #[error("CannotCompress")]
+    CannotCompress,
+
+    #[error("UploadSequenceFailed")]
+    UploadSequenceFailed,
+
+    #[error("InvalidBlockType: {0:?}")]
+    InvalidBlockType(BlockType),
+
+    #[error("InvalidBlockNumber: {0}")]
+    InvalidBlockNumber(u16),
+
+    #[error("InvalidBlockSize: {0}")]
+    InvalidBlockSize(usize),
+
+    #[error("NotConfirmed: {0:?} {1}")]
+    NotConfirmed(BlockType, u16),
+
+    #[error("DownloadSequenceFailed")]
+    DownloadSequenceFailed,
+
+    /// a download requested while another one
+    /// is in progress on the connection
+    #[error("DownloadInProgress")]
+    DownloadInProgress,
+
+    #[error("InsertRefused")]
+    InsertRefused,
+
+    #[error("DeleteRefused")]
+    DeleteRefused,
+
+    #[error("NeedPassword")]
+    NeedPassword,
+
+    #[error("InvalidPassword")]
+    InvalidPassword,
+
+    #[error("NoPasswordToSetOrClear")]
+    NoPasswordToSetOrClear
 }
 
 pub type Result<T> =
     std::result::Result<T, Error>;
 
-// impl<T: TryFromPrimitive>
-// From<TryFromPrimitiveError<T>> for Error {
-//     fn from(value: TryFromPrimitiveError<T>) ->
-// Self {         Self::Error(format!("{}",
-// value))     }
-// }
-//
-// impl ToCoptError for Error {
-//     fn to_err(self) -> copt::error::Error {
-//         copt::error::Error::Error(self.
-// to_string())     }
-// }
+/// The failure of one item of a multi-item read
+/// or write, the other items unaffected.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ItemError {
+    /// the item refused by the plc
+    #[error("ReturnCode: {0:?}")]
+    ReturnCode(ReturnCode),
+
+    /// the address, the data type or the data
+    /// length of the item is invalid, the item
+    /// not sent
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String),
+
+    /// the value written or the data read does
+    /// not match the data type of the item
+    #[error("InvalidValue: {0}")]
+    InvalidValue(String)
+}
+
+pub type ItemResult<T> =
+    std::result::Result<T, ItemError>;
+
+impl ItemError {
+    /// the error of the item `index`, accessing
+    /// `area`, as a single-item access fails
+    pub fn into_error(
+        self,
+        index: usize,
+        area: Area
+    ) -> Error {
+        match self {
+            ItemError::ReturnCode(
+                return_code
+            ) => Error::Item {
+                index,
+                area,
+                return_code
+            },
+            ItemError::InvalidInput(x) => {
+                Error::InvalidInput(x)
+            },
+            ItemError::InvalidValue(x) => {
+                Error::InvalidValue(x)
+            },
+        }
+    }
+}
+
+impl From<Error> for ItemError {
+    /// the input and value errors of an item,
+    /// other errors as invalid input
+    fn from(value: Error) -> Self {
+        match value {
+            Error::InvalidValue(x) => {
+                ItemError::InvalidValue(x)
+            },
+            Error::InvalidInput(x) => {
+                ItemError::InvalidInput(x)
+            },
+            x => ItemError::InvalidInput(
+                x.to_string()
+            )
+        }
+    }
+}
+
+impl Error {
+    /// the error of an error code, none for no
+    /// error. The password errors have their own
+    /// variants.
+    pub(crate) fn from_code(
+        code: impl Into<ErrorCode>
+    ) -> Option<Self> {
+        let code = code.into();
+        match code.value() {
+            0 => None,
+            CODE_7_NEED_PASSWORD => {
+                Some(Error::NeedPassword)
+            },
+            CODE_7_INVALID_PASSWORD => {
+                Some(Error::InvalidPassword)
+            },
+            CODE_7_NO_PASSWORD_TO_SET
+            | CODE_7_NO_PASSWORD_TO_CLEAR => {
+                Some(
+                    Error::NoPasswordToSetOrClear
+                )
+            },
+            _ => Some(Error::Protocol(code))
+        }
+    }
+
+    /// the error code of snap7 for this error, to
+    /// share logs and alarms with snap7 based
+    /// tools
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::IoErr(err) => match err.kind()
+            {
+                ErrorKind::TimedOut => {
+                    TCP_CONNECTION_TIMEOUT
+                },
+                ErrorKind::ConnectionRefused => {
+                    TCP_CONNECTION_FAILED
+                },
+                ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted => {
+                    TCP_CONNECTION_RESET
+                },
+                ErrorKind::NotConnected => {
+                    TCP_NOT_CONNECTED
+                },
+                ErrorKind::HostUnreachable => {
+                    TCP_UNREACHABLE_HOST
+                },
+                _ => TCP_DATA_RECEIVE
+            },
+            Error::TpktErr(_) => ISO_INVALID_PDU,
+            Error::Cotp(_) => ISO_CONNECT,
+            Error::Disconnected(_) => {
+                ISO_DISCONNECT
+            },
+            Error::CotpRejected(_) => {
+                ISO_INVALID_PDU
+            },
+            Error::WriteTimeout => {
+                TCP_SEND_TIMEOUT
+            },
+            Error::ReadTimeout => {
+                TCP_RECEIVE_TIMEOUT
+            },
+            Error::ConnectionClosed(_) => {
+                TCP_CONNECTION_RESET
+            },
+            Error::InvalidPlcAnswer(_) => {
+                CLI_INVALID_PLC_ANSWER
+            },
+            Error::Protocol(code) => {
+                cpu_error(code.value())
+            },
+            Error::Item {
+                return_code, ..
+            }
+            | Error::ReturnCode(return_code) => {
+                cpu_error(u8::from(
+                    return_code.clone()
+                )
+                    as u16)
+            },
+            Error::InvalidBitAddr(_)
+            | Error::InvalidInput(_) => {
+                CLI_INVALID_PARAMS
+            },
+            Error::InvalidValue(_) => {
+                CLI_INVALID_VALUE
+            },
+            Error::CannotStartPlc => {
+                CLI_CANNOT_START_PLC
+            },
+            Error::AlreadyRun => CLI_ALREADY_RUN,
+            Error::CannotStopPlc => {
+                CLI_CANNOT_STOP_PLC
+            },
+            Error::AlreadyStop => {
+                CLI_ALREADY_STOP
+            },
+            Error::CannotCopyRamToRom => {
+                CLI_CANNOT_COPY_RAM_TO_ROM
+            },
+            Error::CannotCompress => {
+                CLI_CANNOT_COMPRESS
+            },
+            Error::UploadSequenceFailed => {
+                CLI_UPLOAD_SEQUENCE_FAILED
+            },
+            Error::InvalidBlockType(_) => {
+                CLI_INVALID_BLOCK_TYPE
+            },
+            Error::InvalidBlockNumber(_) => {
+                CLI_INVALID_BLOCK_NUMBER
+            },
+            Error::InvalidBlockSize(_) => {
+                CLI_INVALID_BLOCK_SIZE
+            },
+            Error::DownloadInProgress => {
+                CLI_JOB_PENDING
+            },
+            Error::NotConfirmed(..)
+            | Error::DownloadSequenceFailed
+            | Error::InsertRefused
+            | Error::DeleteRefused => {
+                CLI_FUNCTION_REFUSED
+            },
+            Error::NeedPassword => {
+                CLI_NEED_PASSWORD
+            },
+            Error::InvalidPassword => {
+                CLI_INVALID_PASSWORD
+            },
+            Error::NoPasswordToSetOrClear => {
+                CLI_NO_PASSWORD_TO_SET_OR_CLEAR
+            },
+        }
+    }
+}
 
 // Copyright 2019 Petar Dambovaliev. All rights
 // reserved. This software may be modified and
 // distributed under the terms of the BSD license.
 // See the LICENSE file for details.
 
-use std::{
-    error, fmt,
-    io::{Error as IOError, ErrorKind},
-};
-
-const TCP_SOCKET_CREATION: i32 = 1;
 const TCP_CONNECTION_TIMEOUT: i32 = 2;
 const TCP_CONNECTION_FAILED: i32 = 3;
 const TCP_RECEIVE_TIMEOUT: i32 = 4;
 const TCP_DATA_RECEIVE: i32 = -5;
 const TCP_SEND_TIMEOUT: i32 = 0x00000006;
-const TCP_DATA_SEND: i32 = 0x00000007;
 const TCP_CONNECTION_RESET: i32 = 0x00000008;
 const TCP_NOT_CONNECTED: i32 = 0x00000009;
-const TCP_UNREACHALE_HOST: i32 = 0x00002751;
+const TCP_UNREACHABLE_HOST: i32 = 0x00002751;
 
 const ISO_CONNECT: i32 = 0x00010000;
-pub(crate) const ISO_INVALID_PDU: i32 =
-    0x00030000; // Bad format
-pub(crate) const ISO_INVALID_DATA_SIZE: i32 =
-    0x00040000;
+const ISO_DISCONNECT: i32 = 0x00020000;
+const ISO_INVALID_PDU: i32 = 0x00030000;
 
-pub(crate) const CLI_NEGOTIATING_PDU: i32 =
-    0x00100000;
 const CLI_INVALID_PARAMS: i32 = 0x00200000;
 const CLI_JOB_PENDING: i32 = 0x00300000;
-const CLI_TOO_MANY_ITEMS: i32 = 0x00400000;
-const CLI_INVALID_DWORD_LEN: i32 = 0x00500000;
-const CLI_PARTIAL_DATA_WRITTEN: i32 = 0x00600000;
 const CLI_SIZE_OVER_PDU: i32 = 0x00700000;
-pub(crate) const CLI_INVALID_PLC_ANSWER: i32 =
-    0x00800000;
+const CLI_INVALID_PLC_ANSWER: i32 = 0x00800000;
 const CLI_ADDRESS_OUT_OF_RANGE: i32 = 0x00900000;
 const CLI_INVALID_TRANSPORT_SIZE: i32 =
     0x00A00000;
@@ -85,22 +1088,16 @@ const CLI_WRITE_DATA_SIZE_MISMATCH: i32 =
     0x00B00000;
 const CLI_ITEM_NOT_AVAILABLE: i32 = 0x00C00000;
 const CLI_INVALID_VALUE: i32 = 0x00D00000;
-pub(crate) const CLI_CANNOT_START_PLC: i32 =
-    0x00E00000;
-pub(crate) const CLI_ALREADY_RUN: i32 =
-    0x00F00000;
-pub(crate) const CLI_CANNOT_STOP_PLC: i32 =
-    0x01000000;
+const CLI_CANNOT_START_PLC: i32 = 0x00E00000;
+const CLI_ALREADY_RUN: i32 = 0x00F00000;
+const CLI_CANNOT_STOP_PLC: i32 = 0x01000000;
 const CLI_CANNOT_COPY_RAM_TO_ROM: i32 =
     0x01100000;
 const CLI_CANNOT_COMPRESS: i32 = 0x01200000;
-pub(crate) const CLI_ALREADY_STOP: i32 =
-    0x01300000;
+const CLI_ALREADY_STOP: i32 = 0x01300000;
 const CLI_FUN_NOT_AVAILABLE: i32 = 0x01400000;
 const CLI_UPLOAD_SEQUENCE_FAILED: i32 =
     0x01500000;
-const CLI_INVALID_DATA_SIZE_RECVD: i32 =
-    0x01600000;
 const CLI_INVALID_BLOCK_TYPE: i32 = 0x01700000;
 const CLI_INVALID_BLOCK_NUMBER: i32 = 0x01800000;
 const CLI_INVALID_BLOCK_SIZE: i32 = 0x01900000;
@@ -108,168 +1105,24 @@ const CLI_NEED_PASSWORD: i32 = 0x01D00000;
 const CLI_INVALID_PASSWORD: i32 = 0x01E00000;
 const CLI_NO_PASSWORD_TO_SET_OR_CLEAR: i32 =
     0x01F00000;
-const CLI_JOB_TIMEOUT: i32 = 0x02000000;
-const CLI_PARTIAL_DATA_READ: i32 = 0x02100000;
-const CLI_BUFFER_TOO_SMALL: i32 = 0x02200000;
 const CLI_FUNCTION_REFUSED: i32 = 0x02300000;
-const CLI_DESTROYING: i32 = 0x02400000;
-const CLI_INVALID_PARAM_NUMBER: i32 = 0x02500000;
-const CLI_CANNOT_CHANGE_PARAM: i32 = 0x02600000;
-const CLI_FUNCTION_NOT_IMPLEMENTED: i32 =
-    0x02700000;
-
-const CODE_7_ADDRESS_OUT_OF_RANGE: i32 = 5;
-const CODE_7_INVALID_TRANSPORT_SIZE: i32 = 6;
-const CODE_7_WRITE_DATA_SIZE_MISMATCH: i32 = 7;
-const CODE_7_RES_ITEM_NOT_AVAILABLE: i32 = 10;
-const CODE_7_RES_ITEM_NOT_AVAILABLE1: i32 = 53769;
-const CODE_7_INVALID_VALUE: i32 = 56321;
-const CODE_7_NEED_PASSWORD: i32 = 53825;
-const CODE_7_INVALID_PASSWORD: i32 = 54786;
-const CODE_7_NO_PASSWORD_TO_CLEAR: i32 = 54788;
-const CODE_7_NO_PASSWORD_TO_SET: i32 = 54789;
-const CODE_7_FUN_NOT_AVAILABLE: i32 = 33028;
-const CODE_7_DATA_OVER_PDU: i32 = 34048;
-
-#[derive(Debug)]
-pub enum S7ConnectError {
-    ConnectFail(String),
-    Lock,
-    IOError(ErrorKind),
-    Response {
-        code: i32,
-    },
-    CPU {
-        code: i32,
-    },
-    InvalidInput {
-        input: String,
-    },
-    Send,
-    Iso,
-    PduLength(u16),
-    TryFrom(Vec<u8>, String),
-    InvalidCpuStatus(u8),
-    InvalidResponse {
-        reason: String,
-        bytes: Vec<u8>,
-    },
-}
 
-impl fmt::Display for S7ConnectError {
-    fn fmt(
-        &self,
-        f: &mut fmt::Formatter,
-    ) -> fmt::Result {
-        match self {
-            S7ConnectError::ConnectFail(s) => {
-                write!(
-                    f,
-                    "connection error: {}",
-                    s
-                )
-            },
-            S7ConnectError::Lock => {
-                write!(f, "Lock error: panicked")
-            },
-            S7ConnectError::IOError(kind) => {
-                write!(f, "IO error: {:?}", kind)
-            },
-            S7ConnectError::Response { code } => {
-                write!(
-                    f,
-                    "Error response: {}",
-                    error_text(*code)
-                )
-            },
-            S7ConnectError::CPU { code } => {
-                write!(
-                    f,
-                    "Error response CPU: {}",
-                    error_text(cpu_error(*code))
-                )
-            },
-            S7ConnectError::InvalidInput {
-                input,
-            } => write!(
-                f,
-                "Invalid input: {}",
-                input
-            ),
-            S7ConnectError::Send => {
-                write!(f, "Send connection error")
-            },
-            S7ConnectError::Iso => {
-                write!(f, "ISO connection error")
-            },
-            S7ConnectError::PduLength(pdu) => {
-                write!(
-                    f,
-                    "PDU length connection \
-                     error {}",
-                    pdu
-                )
-            },
-            S7ConnectError::TryFrom(
-                bytes,
-                reason,
-            ) => {
-                write!(
-                    f,
-                    "Could not read bytes {:?} \
-                     reason {}",
-                    bytes, reason
-                )
-            },
-            S7ConnectError::InvalidCpuStatus(
-                status,
-            ) => write!(
-                f,
-                "Invalid cpu status {}",
-                status
-            ),
-            S7ConnectError::InvalidResponse {
-                reason,
-                bytes,
-            } => {
-                write!(
-                    f,
-                    "Invalid response {:?} err \
-                     {}",
-                    bytes, reason
-                )
-            },
-            // S7ConnectError::InvalidBitAddr(
-            //     addr
-            // ) => {
-            //     write!(
-            //         f,
-            //         "Invalid bit addr {}",
-            //         addr
-            //     )
-            // }
-        }
-    }
-}
-
-impl From<IOError> for S7ConnectError {
-    fn from(e: IOError) -> Self {
-        S7ConnectError::IOError(e.kind())
-    }
-}
-// This is important for other errors to wrap this
-// one.
-impl error::Error for S7ConnectError {
-    fn source(
-        &self,
-    ) -> Option<&(dyn error::Error + 'static)>
-    {
-        None
-    }
-}
+const CODE_7_ADDRESS_OUT_OF_RANGE: u16 = 5;
+const CODE_7_INVALID_TRANSPORT_SIZE: u16 = 6;
+const CODE_7_WRITE_DATA_SIZE_MISMATCH: u16 = 7;
+const CODE_7_RES_ITEM_NOT_AVAILABLE: u16 = 10;
+const CODE_7_RES_ITEM_NOT_AVAILABLE1: u16 = 53769;
+const CODE_7_INVALID_VALUE: u16 = 56321;
+const CODE_7_NEED_PASSWORD: u16 = 53825;
+const CODE_7_INVALID_PASSWORD: u16 = 54786;
+const CODE_7_NO_PASSWORD_TO_CLEAR: u16 = 54788;
+const CODE_7_NO_PASSWORD_TO_SET: u16 = 54789;
+const CODE_7_FUN_NOT_AVAILABLE: u16 = 33028;
+const CODE_7_DATA_OVER_PDU: u16 = 34048;
 
-//CPUError specific CPU error after response
-fn cpu_error(err: i32) -> i32 {
+/// the snap7 error code of an error code or a
+/// return code of the cpu
+fn cpu_error(err: u16) -> i32 {
     match err {
         CODE_7_ADDRESS_OUT_OF_RANGE => {
             CLI_ADDRESS_OUT_OF_RANGE
@@ -297,164 +1150,6 @@ fn cpu_error(err: i32) -> i32 {
         | CODE_7_NO_PASSWORD_TO_CLEAR => {
             CLI_NO_PASSWORD_TO_SET_OR_CLEAR
         },
-        _ => CLI_FUNCTION_REFUSED,
-    }
-}
-
-//ErrorText return a string error text from error
-// code integer
-fn error_text(err: i32) -> &'static str {
-    match err {
-        0 => "OK",
-        TCP_SOCKET_CREATION => {
-            "SYS : Error creating the Socket"
-        },
-        TCP_CONNECTION_TIMEOUT => {
-            "TCP : Connection Timeout"
-        },
-        TCP_CONNECTION_FAILED => {
-            "TCP : Connection Error"
-        },
-        TCP_RECEIVE_TIMEOUT => {
-            "TCP : Data receive Timeout"
-        },
-        TCP_DATA_RECEIVE => {
-            "TCP : Error receiving Data"
-        },
-        TCP_SEND_TIMEOUT => {
-            "TCP : Data send Timeout"
-        },
-        TCP_DATA_SEND => {
-            "TCP : Error sending Data"
-        },
-        TCP_CONNECTION_RESET => {
-            "TCP : Connection reset by the Peer"
-        },
-        TCP_NOT_CONNECTED => {
-            "CLI : Client not connected"
-        },
-        TCP_UNREACHALE_HOST => {
-            "TCP : Unreachable host"
-        },
-
-        ISO_CONNECT => "ISO : Connection Error",
-        ISO_INVALID_PDU => {
-            "ISO : Invalid PDU received"
-        },
-        ISO_INVALID_DATA_SIZE => {
-            "ISO : Invalid Buffer passed to \
-             Send/Receive"
-        },
-
-        CLI_NEGOTIATING_PDU => {
-            "CLI : Error in PDU negotiation"
-        },
-        CLI_INVALID_PARAMS => {
-            "CLI : invalid param(s) supplied"
-        },
-        CLI_JOB_PENDING => "CLI : Job pending",
-        CLI_TOO_MANY_ITEMS => {
-            "CLI : too may items (>20) in multi \
-             read/write"
-        },
-        CLI_INVALID_DWORD_LEN => {
-            "CLI : invalid WordLength"
-        },
-        CLI_PARTIAL_DATA_WRITTEN => {
-            "CLI : Partial data written"
-        },
-        CLI_SIZE_OVER_PDU => {
-            "CPU : total data exceeds the PDU \
-             size"
-        },
-        CLI_INVALID_PLC_ANSWER => {
-            "CLI : invalid CPU answer"
-        },
-        CLI_ADDRESS_OUT_OF_RANGE => {
-            "CPU : Address out of range"
-        },
-        CLI_INVALID_TRANSPORT_SIZE => {
-            "CPU : Invalid Transport size"
-        },
-        CLI_WRITE_DATA_SIZE_MISMATCH => {
-            "CPU : Data size mismatch"
-        },
-        CLI_ITEM_NOT_AVAILABLE => {
-            "CPU : Item not available"
-        },
-        CLI_INVALID_VALUE => {
-            "CPU : Invalid value supplied"
-        },
-        CLI_CANNOT_START_PLC => {
-            "CPU : Cannot start PLC"
-        },
-        CLI_ALREADY_RUN => {
-            "CPU : PLC already RUN"
-        },
-        CLI_CANNOT_STOP_PLC => {
-            "CPU : Cannot stop PLC"
-        },
-        CLI_CANNOT_COPY_RAM_TO_ROM => {
-            "CPU : Cannot copy RAM to ROM"
-        },
-        CLI_CANNOT_COMPRESS => {
-            "CPU : Cannot compress"
-        },
-        CLI_ALREADY_STOP => {
-            "CPU : PLC already STOP"
-        },
-        CLI_FUN_NOT_AVAILABLE => {
-            "CPU : Function not available"
-        },
-        CLI_UPLOAD_SEQUENCE_FAILED => {
-            "CPU : Upload sequence failed"
-        },
-        CLI_INVALID_DATA_SIZE_RECVD => {
-            "CLI : Invalid data size received"
-        },
-        CLI_INVALID_BLOCK_TYPE => {
-            "CLI : Invalid block type"
-        },
-        CLI_INVALID_BLOCK_NUMBER => {
-            "CLI : Invalid block number"
-        },
-        CLI_INVALID_BLOCK_SIZE => {
-            "CLI : Invalid block size"
-        },
-        CLI_NEED_PASSWORD => {
-            "CPU : Function not authorized for \
-             current protection level"
-        },
-        CLI_INVALID_PASSWORD => {
-            "CPU : Invalid password"
-        },
-        CLI_NO_PASSWORD_TO_SET_OR_CLEAR => {
-            "CPU : No password to set or clear"
-        },
-        CLI_JOB_TIMEOUT => "CLI : Job Timeout",
-        CLI_FUNCTION_REFUSED => {
-            "CLI : function refused by CPU \
-             (Unknown error)"
-        },
-        CLI_PARTIAL_DATA_READ => {
-            "CLI : Partial data read"
-        },
-        CLI_BUFFER_TOO_SMALL => {
-            "CLI : The buffer supplied is too \
-             small to accomplish the operation"
-        },
-        CLI_DESTROYING => {
-            "CLI : Cannot perform (destroying)"
-        },
-        CLI_INVALID_PARAM_NUMBER => {
-            "CLI : Invalid Param Number"
-        },
-        CLI_CANNOT_CHANGE_PARAM => {
-            "CLI : Cannot change this param now"
-        },
-        CLI_FUNCTION_NOT_IMPLEMENTED => {
-            "CLI : Function not implemented"
-        },
-        _ => "CLI : Unknown error",
+        _ => CLI_FUNCTION_REFUSED
     }
 }
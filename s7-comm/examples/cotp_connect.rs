@@ -34,7 +34,7 @@ async fn main() -> Result<()> {
             .unwrap();
         let mut bytes = BytesMut::new();
         let mut decoder = TpktDecoder(
-            CoptDecoder(S7CommDecoder),
+            CoptDecoder::new(S7CommDecoder),
         );
         loop {
             let size =
@@ -68,7 +68,7 @@ async fn main() -> Result<()> {
             .unwrap();
         let mut bytes = BytesMut::new();
         let mut decoder = TpktDecoder(
-            CoptDecoder(S7CommDecoder),
+            CoptDecoder::new(S7CommDecoder),
         );
         loop {
             let size =
@@ -106,7 +106,7 @@ async fn main() -> Result<()> {
             .unwrap();
         let mut bytes = BytesMut::new();
         let mut decoder = TpktDecoder(
-            CoptDecoder(S7CommDecoder),
+            CoptDecoder::new(S7CommDecoder),
         );
         loop {
             let size =
@@ -147,7 +147,7 @@ async fn main() -> Result<()> {
             .unwrap();
         let mut bytes = BytesMut::new();
         let mut decoder = TpktDecoder(
-            CoptDecoder(S7CommDecoder),
+            CoptDecoder::new(S7CommDecoder),
         );
         loop {
             let size =
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+mod frame;
+
+// an ack data, of the variant chosen by the first byte
+fuzz_target!(|data: &[u8]| frame::decode(0x03, 12, data));
@@ -0,0 +1,22 @@
+#![no_main]
+
+use bytes::BytesMut;
+use copt::CoptDecoder;
+use libfuzzer_sys::fuzz_target;
+use s7_comm::S7CommDecoder;
+use tokio_util::codec::Decoder;
+
+// the data after the pdu type of a connect request or confirm, the first
+// byte choosing the one
+fuzz_target!(|data: &[u8]| {
+    let Some((kind, data)) = data.split_first() else {
+        return;
+    };
+    let Ok(length) = u8::try_from(data.len() + 1) else {
+        return;
+    };
+    let pdu_type = if kind & 1 > 0 { 0xd0 } else { 0xe0 };
+    let mut src = BytesMut::from([length, pdu_type].as_ref());
+    src.extend_from_slice(data);
+    let _ = CoptDecoder::new(S7CommDecoder).decode(&mut src);
+});
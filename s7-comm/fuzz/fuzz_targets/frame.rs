@@ -0,0 +1,35 @@
+//! the frame builder shared by the `job` and `ack_data` targets
+
+use bytes::{BufMut, BytesMut};
+use s7_comm::S7CommDecoder;
+use tokio_util::codec::Decoder;
+
+/// the functions of the variants of `Job` and `AckData`
+const FUNCTIONS: [u8; 11] = [
+    0xf0, 0x05, 0x04, 0x28, 0x29, 0x1d, 0x1e, 0x1f, 0x1a, 0x1b, 0x1c,
+];
+
+/// decode a frame of `rosctr`, whose header of `header_len` bytes ends with
+/// zeros after the data length. The first byte of `data` chooses the variant,
+/// the second one splits the rest into its parameter and data.
+pub fn decode(rosctr: u8, header_len: usize, data: &[u8]) {
+    let [variant, split, rest @ ..] = data else {
+        return;
+    };
+    if rest.len() >= u16::MAX as usize {
+        return;
+    }
+    let parameter_len = (*split as usize).min(rest.len()) + 1;
+    let data_len = rest.len() + 1 - parameter_len;
+    let mut src = BytesMut::new();
+    src.put_u8(0x32);
+    src.put_u8(rosctr);
+    src.put_u16(0);
+    src.put_u16(1);
+    src.put_u16(parameter_len as u16);
+    src.put_u16(data_len as u16);
+    src.put_bytes(0, header_len.saturating_sub(src.len()));
+    src.put_u8(FUNCTIONS[*variant as usize % FUNCTIONS.len()]);
+    src.extend_from_slice(rest);
+    let _ = S7CommDecoder.decode(&mut src);
+}
@@ -0,0 +1,15 @@
+#![no_main]
+
+use bytes::BytesMut;
+use copt::CoptDecoder;
+use libfuzzer_sys::fuzz_target;
+use s7_comm::S7CommDecoder;
+use tokio_util::codec::Decoder;
+use tpkt::TpktDecoder;
+
+// the packets received from the network, decoded as a connection does
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
+    let mut src = BytesMut::from(data);
+    while let Ok(Some(_)) = decoder.decode(&mut src) {}
+});
@@ -0,0 +1,433 @@
+use crate::{
+    error::*, packet::encode_data_items,
+    DataItemVal, DateAndTime, ReturnCode,
+};
+use bytes::{Buf, BufMut, BytesMut};
+use num_enum::{FromPrimitive, IntoPrimitive};
+
+/// subfunction of the cpu functions subscribing
+/// the messages of the plc
+pub const MESSAGE_SERVICE: u8 = 0x02;
+/// subfunction of the cpu functions
+/// acknowledging alarms
+pub const ALARM_ACK: u8 = 0x0b;
+/// subfunction of the cpu functions reading the
+/// active alarms
+pub const ALARM_QUERY: u8 = 0x13;
+
+/// the messages of mode transitions
+pub const EVENTS_MODE: u8 = 0x01;
+/// the messages of the diagnostic buffer
+pub const EVENTS_SYSTEM: u8 = 0x02;
+/// the messages of the user diagnostic
+pub const EVENTS_USER: u8 = 0x04;
+/// the alarms, of the type of the subscription
+pub const EVENTS_ALARM: u8 = 0x80;
+
+/// the length of the user name of a message
+/// service subscription, shorter ones padded
+/// with spaces
+pub const MESSAGE_USER_LEN: usize = 8;
+
+/// syntax id of the items of alarm messages and
+/// acknowledgements
+const SYNTAX_ALARM: u8 = 0x09;
+/// syntax id of the item of an alarm query
+const SYNTAX_ALARM_QUERY: u8 = 0x1a;
+/// query of the alarms of a type
+const QUERY_BY_TYPE: u8 = 0x01;
+/// function identifier of alarm messages and
+/// queries
+const ALARM_FUNCTION: u8 = 0x00;
+/// function identifier of acknowledgements
+const ACK_FUNCTION: u8 = 0x09;
+/// bytes of an alarm message item besides its
+/// values: syntax id, count of values, event id
+/// and states
+const ALARM_ITEM_LEN: u8 = 9;
+/// bytes of an acknowledgement item besides its
+/// head
+const ACK_ITEM_LEN: u8 = 8;
+
+/// An alarm notification pushed by the plc, by
+/// the subfunction of its push.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum AlarmKind {
+    /// ALARM_8, ALARM_8P and ALARM
+    Alarm8 = 0x05,
+    Notify = 0x06,
+    Scan = 0x09,
+    /// an alarm acknowledged by another station
+    AckIndication = 0x0c,
+    /// ALARM_S and ALARM_D
+    AlarmS = 0x11,
+    /// ALARM_SQ and ALARM_DQ
+    AlarmSq = 0x12,
+    Notify8 = 0x16,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// The alarms of a message service
+/// subscription, or of an alarm query.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum AlarmType {
+    Scan = 0x01,
+    Alarm8 = 0x02,
+    AlarmS = 0x04,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+impl AlarmType {
+    /// the byte of a subscription starting, or
+    /// ending, the alarms of this type
+    fn subscription(&self, initiate: bool) -> u8 {
+        let abort = match self {
+            AlarmType::Scan => 0x00,
+            AlarmType::Alarm8 => 0x04,
+            AlarmType::AlarmS => 0x08,
+            AlarmType::NotSupport(x) => *x & 0xfe,
+        };
+        abort | initiate as u8
+    }
+
+    fn from_subscription(
+        src: u8,
+    ) -> (Self, bool) {
+        let alarm_type = match src & 0xfe {
+            0x00 => AlarmType::Scan,
+            0x04 => AlarmType::Alarm8,
+            0x08 => AlarmType::AlarmS,
+            x => AlarmType::NotSupport(x),
+        };
+        (alarm_type, src & 0x01 > 0)
+    }
+}
+
+/// The data of a message service request: the
+/// events pushed to the subscriber, and the
+/// alarms started or ended.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MessageService {
+    /// [`EVENTS_MODE`], [`EVENTS_SYSTEM`],
+    /// [`EVENTS_USER`] and [`EVENTS_ALARM`]
+    /// combined, none ending the subscription
+    pub events: u8,
+    /// the name of the subscriber, at most 8
+    /// ascii chars
+    pub user: String,
+    /// the alarms, and whether they start or end
+    pub alarms: Option<(AlarmType, bool)>,
+}
+
+impl MessageService {
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        if !self.user.is_ascii()
+            || self.user.len() > MESSAGE_USER_LEN
+        {
+            return Err(Error::Error(format!(
+                "user should be at most {} \
+                 ascii chars",
+                MESSAGE_USER_LEN
+            )));
+        }
+        let mut dst = BytesMut::new();
+        dst.put_u8(self.events);
+        dst.put_u8(0x00);
+        let mut user = [b' '; MESSAGE_USER_LEN];
+        user[..self.user.len()].copy_from_slice(
+            self.user.as_bytes(),
+        );
+        dst.extend_from_slice(&user);
+        if let Some((alarm_type, initiate)) =
+            self.alarms
+        {
+            dst.put_u8(
+                alarm_type.subscription(initiate),
+            );
+            dst.put_u8(0x00);
+        }
+        Ok(dst.to_vec())
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_len(
+            data,
+            "message service",
+            2 + MESSAGE_USER_LEN,
+        )?;
+        let user = String::from_utf8_lossy(
+            &data[2..2 + MESSAGE_USER_LEN],
+        )
+        .trim_end_matches(' ')
+        .to_string();
+        Ok(Self {
+            events: data[0],
+            user,
+            alarms: data
+                .get(2 + MESSAGE_USER_LEN)
+                .map(|x| {
+                    AlarmType::from_subscription(
+                        *x,
+                    )
+                }),
+        })
+    }
+}
+
+/// A message of an alarm: the signals raised and
+/// acknowledged, and the values associated.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AlarmMessage {
+    pub event_id: u32,
+    /// a bit for each signal raised
+    pub state: u8,
+    /// a bit for each signal acknowledged since
+    /// it went
+    pub ack_state_going: u8,
+    /// a bit for each signal acknowledged since
+    /// it came
+    pub ack_state_coming: u8,
+    pub values: Vec<DataItemVal>,
+}
+
+/// The data of an alarm push, and of the
+/// response of an alarm query.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AlarmNotification {
+    pub timestamp: DateAndTime,
+    pub messages: Vec<AlarmMessage>,
+}
+
+impl AlarmNotification {
+    pub fn encode(self) -> Result<Vec<u8>> {
+        let mut dst = BytesMut::new();
+        dst.extend_from_slice(
+            &self.timestamp.encode()?,
+        );
+        dst.put_u8(ALARM_FUNCTION);
+        dst.put_u8(self.messages.len() as u8);
+        for message in self.messages {
+            let mut values = BytesMut::new();
+            let count =
+                message.values.len() as u8;
+            encode_data_items(
+                message.values,
+                &mut values,
+            );
+            dst.put_u8(0x12);
+            dst.put_u8(
+                ALARM_ITEM_LEN
+                    + values.len() as u8,
+            );
+            dst.put_u8(SYNTAX_ALARM);
+            dst.put_u8(count);
+            dst.put_u32(message.event_id);
+            dst.put_u8(message.state);
+            dst.put_u8(message.ack_state_going);
+            dst.put_u8(message.ack_state_coming);
+            dst.extend_from_slice(&values);
+        }
+        Ok(dst.to_vec())
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_len(
+            data,
+            "alarm notification",
+            10,
+        )?;
+        let timestamp =
+            DateAndTime::decode(&data[..8])?;
+        let count = data[9];
+        let mut src = BytesMut::from(&data[10..]);
+        let mut messages =
+            Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            check_len(&src, "alarm message", 2)?;
+            src.advance(1);
+            let len = src.get_u8();
+            if len < ALARM_ITEM_LEN {
+                return Err(Error::Error(format!(
+                    "invalid alarm message length: \
+                     {}",
+                    len
+                )));
+            }
+            check_len(
+                &src,
+                "alarm message",
+                len as usize,
+            )?;
+            let mut item =
+                src.split_to(len as usize);
+            item.advance(1);
+            let values = item.get_u8();
+            let event_id = item.get_u32();
+            let state = item.get_u8();
+            let ack_state_going = item.get_u8();
+            let ack_state_coming = item.get_u8();
+            let values = (0..values)
+                .map(|_| {
+                    DataItemVal::decode(&mut item)
+                })
+                .collect::<Result<_>>()?;
+            messages.push(AlarmMessage {
+                event_id,
+                state,
+                ack_state_going,
+                ack_state_coming,
+                values,
+            });
+        }
+        Ok(Self {
+            timestamp,
+            messages,
+        })
+    }
+}
+
+/// An acknowledgement of the signals of an
+/// alarm.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AlarmAck {
+    pub event_id: u32,
+    /// the signals acknowledged since they went
+    pub ack_state_going: u8,
+    /// the signals acknowledged since they came
+    pub ack_state_coming: u8,
+}
+
+impl AlarmAck {
+    pub fn encode_list(
+        acks: &[AlarmAck],
+    ) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        dst.put_u8(ACK_FUNCTION);
+        dst.put_u8(acks.len() as u8);
+        for ack in acks {
+            dst.put_u8(0x12);
+            dst.put_u8(ACK_ITEM_LEN);
+            dst.put_u8(SYNTAX_ALARM);
+            dst.put_u8(0x01);
+            dst.put_u32(ack.event_id);
+            dst.put_u8(ack.ack_state_going);
+            dst.put_u8(ack.ack_state_coming);
+        }
+        dst.to_vec()
+    }
+
+    pub fn decode_list(
+        data: &[u8],
+    ) -> Result<Vec<AlarmAck>> {
+        check_len(data, "alarm ack", 2)?;
+        let count = data[1] as usize;
+        let items = &data[2..];
+        check_len(
+            items,
+            "alarm ack",
+            count * 10,
+        )?;
+        Ok(items
+            .chunks_exact(10)
+            .take(count)
+            .map(|x| AlarmAck {
+                event_id: u32::from_be_bytes([
+                    x[4], x[5], x[6], x[7],
+                ]),
+                ack_state_going: x[8],
+                ack_state_coming: x[9],
+            })
+            .collect())
+    }
+
+    /// the response of an acknowledgement: a
+    /// return code for each alarm
+    pub fn encode_response(
+        return_codes: &[ReturnCode],
+    ) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        dst.put_u8(ACK_FUNCTION);
+        dst.put_u8(return_codes.len() as u8);
+        for return_code in return_codes {
+            dst.put_u8(
+                return_code.clone().into(),
+            );
+        }
+        dst.to_vec()
+    }
+
+    pub fn decode_response(
+        data: &[u8],
+    ) -> Result<Vec<ReturnCode>> {
+        check_len(data, "alarm ack response", 2)?;
+        let count = data[1] as usize;
+        check_len(
+            data,
+            "alarm ack response",
+            2 + count,
+        )?;
+        Ok(data[2..2 + count]
+            .iter()
+            .map(|x| ReturnCode::try_from(*x))
+            .collect::<std::result::Result<_, _>>(
+            )?)
+    }
+}
+
+/// the data of a query of the active alarms of
+/// `alarm_type`
+pub fn encode_alarm_query(
+    alarm_type: AlarmType,
+) -> [u8; 12] {
+    [
+        ALARM_FUNCTION,
+        0x01,
+        0x12,
+        0x08,
+        SYNTAX_ALARM_QUERY,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        QUERY_BY_TYPE,
+        0x00,
+        alarm_type.into(),
+    ]
+}
+
+/// the alarm type of the data of an alarm query
+pub fn decode_alarm_query(
+    data: &[u8],
+) -> Result<AlarmType> {
+    match data {
+        [.., QUERY_BY_TYPE, _, alarm_type]
+            if data.len() == 12 =>
+        {
+            Ok(AlarmType::from(*alarm_type))
+        },
+        _ => Err(Error::Error(format!(
+            "not support alarm query: {:?}",
+            data
+        ))),
+    }
+}
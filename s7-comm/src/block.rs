@@ -0,0 +1,974 @@
+use crate::{error::*, DateAndTime};
+use bytes::{Buf, BufMut, BytesMut};
+use num_enum::{FromPrimitive, IntoPrimitive};
+
+/// subfunction of the block functions counting
+/// the blocks of each type
+pub const LIST_BLOCKS: u8 = 0x01;
+/// subfunction of the block functions listing
+/// the blocks of a type
+pub const LIST_BLOCKS_OF_TYPE: u8 = 0x02;
+/// subfunction of the block functions reading
+/// the info of a block
+pub const GET_BLOCK_INFO: u8 = 0x03;
+
+/// the PI service deleting a block
+pub const DELETE_BLOCK: &str = "_DELE";
+/// the PI service inserting a downloaded block
+/// of the passive file system
+pub const INSERT_BLOCK: &str = "_INSE";
+
+/// the ascii `0` preceding the block type
+const BLOCK_TYPE_PREFIX: u8 = 0x30;
+/// the unknown bytes of a request download job
+const REQUEST_DOWNLOAD_HEAD: u16 = 0x0100;
+/// the block control bytes of the upload data
+const UPLOAD_DATA_CONTROL: [u8; 2] = [0x00, 0xfb];
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+)]
+#[repr(u8)]
+pub enum BlockType {
+    OB = 0x38,
+    DB = 0x41,
+    SDB = 0x42,
+    FC = 0x43,
+    SFC = 0x44,
+    FB = 0x45,
+    SFB = 0x46,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+impl BlockType {
+    /// the block type in the header of a block,
+    /// see [`BlockInfo::sub_block_type`]
+    pub fn sub_block_type(&self) -> Option<u8> {
+        match self {
+            Self::OB => Some(0x08),
+            Self::DB => Some(0x0a),
+            Self::SDB => Some(0x0b),
+            Self::FC => Some(0x0c),
+            Self::SFC => Some(0x0d),
+            Self::FB => Some(0x0e),
+            Self::SFB => Some(0x0f),
+            Self::NotSupport(_) => None,
+        }
+    }
+
+    pub fn from_sub_block_type(
+        sub_block_type: u8,
+    ) -> Self {
+        match sub_block_type {
+            0x08 => Self::OB,
+            0x0a => Self::DB,
+            0x0b => Self::SDB,
+            0x0c => Self::FC,
+            0x0d => Self::SFC,
+            0x0e => Self::FB,
+            0x0f => Self::SFB,
+            x => Self::NotSupport(x),
+        }
+    }
+}
+
+/// The file system of a block file.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum FileSystem {
+    /// `A`
+    Active = 0x41,
+    /// `B`
+    Both = 0x42,
+    /// `P`
+    Passive = 0x50,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// A block addressed by its file name, as
+/// `_0A00001A` for DB1 in the active file system.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockFile {
+    pub block_type: BlockType,
+    pub number: u16,
+    pub file_system: FileSystem,
+}
+
+impl BlockFile {
+    pub fn init(
+        block_type: BlockType,
+        number: u16,
+        file_system: FileSystem,
+    ) -> Self {
+        Self {
+            block_type,
+            number,
+            file_system,
+        }
+    }
+
+    /// the name without the `_` prefix, as the
+    /// data of a block info request
+    pub fn name(&self) -> [u8; 8] {
+        let mut name = [0; 8];
+        name[0] = BLOCK_TYPE_PREFIX;
+        name[1] = self.block_type.into();
+        name[2..7].copy_from_slice(
+            format!("{:05}", self.number)
+                .as_bytes(),
+        );
+        name[7] = self.file_system.into();
+        name
+    }
+
+    pub fn bytes_len(&self) -> u16 {
+        10
+    }
+
+    /// the parameter of the PI services on a
+    /// block, as delete and insert: the count of
+    /// blocks, always 1, and the name
+    pub fn pi_parameter(&self) -> Vec<u8> {
+        let mut parameter = vec![0x01, 0x00];
+        parameter.extend_from_slice(&self.name());
+        parameter
+    }
+
+    pub fn decode_pi_parameter(
+        parameter: &[u8],
+    ) -> Result<Self> {
+        match parameter {
+            [0x01, 0x00, name @ ..] => {
+                Self::decode_name(name)
+            },
+            _ => Err(Error::Error(format!(
+                "invalid block parameter: {:?}",
+                parameter
+            ))),
+        }
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        dst.put_u8(9);
+        dst.put_u8(b'_');
+        dst.extend_from_slice(&self.name());
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        check_len(src, "block file name", 10)?;
+        if src[0] != 9 {
+            return Err(Error::Error(format!(
+                "invalid block file name length: {}",
+                src[0]
+            )));
+        }
+        let name = src.split_to(10);
+        Self::decode_name(&name[2..])
+    }
+
+    pub fn decode_name(
+        name: &[u8],
+    ) -> Result<Self> {
+        let number = name
+            .get(2..7)
+            .and_then(|x| {
+                std::str::from_utf8(x).ok()
+            })
+            .and_then(|x| x.parse().ok());
+        match (name.first(), number) {
+            (
+                Some(&BLOCK_TYPE_PREFIX),
+                Some(number),
+            ) if name.len() >= 8 => Ok(Self {
+                block_type: BlockType::from(
+                    name[1],
+                ),
+                number,
+                file_system: FileSystem::from(
+                    name[7],
+                ),
+            }),
+            _ => Err(Error::Error(format!(
+                "invalid block name: {:?}",
+                name
+            ))),
+        }
+    }
+}
+
+/// The count of the blocks of a type, in the
+/// response of the list blocks function.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockCount {
+    pub block_type: BlockType,
+    pub count: u16,
+}
+
+impl BlockCount {
+    pub fn encode_list(
+        counts: &[BlockCount],
+    ) -> Vec<u8> {
+        let mut data = BytesMut::new();
+        for count in counts {
+            data.put_u8(BLOCK_TYPE_PREFIX);
+            data.put_u8(count.block_type.into());
+            data.put_u16(count.count);
+        }
+        data.to_vec()
+    }
+
+    pub fn decode_list(
+        data: &[u8],
+    ) -> Vec<BlockCount> {
+        data.chunks_exact(4)
+            .map(|x| BlockCount {
+                block_type: BlockType::from(x[1]),
+                count: u16::from_be_bytes([
+                    x[2], x[3],
+                ]),
+            })
+            .collect()
+    }
+}
+
+/// A block, in the response of the list blocks
+/// of type function.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockListEntry {
+    pub number: u16,
+    pub flags: u8,
+    /// see [`BlockInfo::language`]
+    pub language: u8,
+}
+
+impl BlockListEntry {
+    pub fn encode_list(
+        entries: &[BlockListEntry],
+    ) -> Vec<u8> {
+        let mut data = BytesMut::new();
+        for entry in entries {
+            data.put_u16(entry.number);
+            data.put_u8(entry.flags);
+            data.put_u8(entry.language);
+        }
+        data.to_vec()
+    }
+
+    pub fn decode_list(
+        data: &[u8],
+    ) -> Vec<BlockListEntry> {
+        data.chunks_exact(4)
+            .map(|x| BlockListEntry {
+                number: u16::from_be_bytes([
+                    x[0], x[1],
+                ]),
+                flags: x[2],
+                language: x[3],
+            })
+            .collect()
+    }
+}
+
+/// Timestamp of a block: days since 1984-01-01
+/// and milliseconds since midnight.
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Default,
+)]
+pub struct BlockTimestamp {
+    pub days: u16,
+    pub milliseconds: u32,
+}
+
+impl BlockTimestamp {
+    /// the date and time, failing before 1990
+    pub fn date_time(
+        &self,
+    ) -> Result<DateAndTime> {
+        // days from 1970-01-01 to 1984-01-01
+        let (year, month, day) = civil_from_days(
+            self.days as i64 + 5113,
+        );
+        let ms = self.milliseconds;
+        DateAndTime::new(
+            year as u16,
+            month,
+            day,
+            (ms / 3_600_000) as u8,
+            (ms / 60_000 % 60) as u8,
+            (ms / 1000 % 60) as u8,
+            (ms % 1000) as u16,
+        )
+    }
+
+    fn encode(self, dst: &mut BytesMut) {
+        dst.put_u32(self.milliseconds);
+        dst.put_u16(self.days);
+    }
+
+    fn decode(src: &mut BytesMut) -> Self {
+        let milliseconds = src.get_u32();
+        let days = src.get_u16();
+        Self { days, milliseconds }
+    }
+}
+
+/// The info of a block, the data of the get block
+/// info function.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlockInfo {
+    pub block_type: BlockType,
+    pub number: u16,
+    pub flags: u8,
+    /// 0x01 STL, 0x02 LAD, 0x03 FBD, 0x04 SCL,
+    /// 0x05 DB, 0x06 GRAPH, ...
+    pub language: u8,
+    /// the block type in MC7: 0x08 OB, 0x0a DB,
+    /// 0x0b SDB, 0x0c FC, 0x0d SFC, 0x0e FB,
+    /// 0x0f SFB
+    pub sub_block_type: u8,
+    /// size in the load memory
+    pub load_size: u32,
+    /// 0x00000003 know how protected
+    pub security: u32,
+    pub code_time: BlockTimestamp,
+    pub interface_time: BlockTimestamp,
+    pub sbb_len: u16,
+    pub add_len: u16,
+    pub local_data_len: u16,
+    /// size of the MC7 code
+    pub mc7_size: u16,
+    pub author: String,
+    pub family: String,
+    pub name: String,
+    /// major version in the high nibble, minor
+    /// in the low one
+    pub version: u8,
+    pub checksum: u16,
+}
+
+impl BlockInfo {
+    pub const BYTES_LEN: usize = 78;
+
+    pub fn encode(self) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        dst.put_u8(0x01);
+        dst.put_u8(self.block_type.into());
+        // constants in the captures
+        dst.extend_from_slice(&[
+            0x00, 0x4a, 0x00, 0x22, 0x70, 0x70,
+            0x01,
+        ]);
+        dst.put_u8(self.flags);
+        dst.put_u8(self.language);
+        dst.put_u8(self.sub_block_type);
+        dst.put_u16(self.number);
+        dst.put_u32(self.load_size);
+        dst.put_u32(self.security);
+        self.code_time.encode(&mut dst);
+        self.interface_time.encode(&mut dst);
+        dst.put_u16(self.sbb_len);
+        dst.put_u16(self.add_len);
+        dst.put_u16(self.local_data_len);
+        dst.put_u16(self.mc7_size);
+        put_text(&mut dst, &self.author);
+        put_text(&mut dst, &self.family);
+        put_text(&mut dst, &self.name);
+        dst.put_u8(self.version);
+        dst.put_u8(0);
+        dst.put_u16(self.checksum);
+        dst.put_bytes(0, 8);
+        dst.to_vec()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_len(
+            data,
+            "block info",
+            Self::BYTES_LEN,
+        )?;
+        let mut src = BytesMut::from(data);
+        src.advance(1);
+        let block_type =
+            BlockType::from(src.get_u8());
+        src.advance(7);
+        let flags = src.get_u8();
+        let language = src.get_u8();
+        let sub_block_type = src.get_u8();
+        let number = src.get_u16();
+        let load_size = src.get_u32();
+        let security = src.get_u32();
+        let code_time =
+            BlockTimestamp::decode(&mut src);
+        let interface_time =
+            BlockTimestamp::decode(&mut src);
+        let sbb_len = src.get_u16();
+        let add_len = src.get_u16();
+        let local_data_len = src.get_u16();
+        let mc7_size = src.get_u16();
+        let author = get_text(&mut src);
+        let family = get_text(&mut src);
+        let name = get_text(&mut src);
+        let version = src.get_u8();
+        src.advance(1);
+        let checksum = src.get_u16();
+        Ok(Self {
+            block_type,
+            number,
+            flags,
+            language,
+            sub_block_type,
+            load_size,
+            security,
+            code_time,
+            interface_time,
+            sbb_len,
+            add_len,
+            local_data_len,
+            mc7_size,
+            author,
+            family,
+            name,
+            version,
+            checksum,
+        })
+    }
+}
+
+/// Start upload job, 0x1d.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StartUploadJob {
+    session_id: u32,
+    file: BlockFile,
+}
+
+impl StartUploadJob {
+    pub fn init(file: BlockFile) -> Self {
+        Self {
+            session_id: 0,
+            file,
+        }
+    }
+
+    pub fn file(&self) -> BlockFile {
+        self.file
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        8 + self.file.bytes_len()
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        encode_session(0, self.session_id, dst);
+        self.file.encode(dst);
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        let (_, session_id) =
+            decode_session(src)?;
+        let file = BlockFile::decode(src)?;
+        Ok(Self { session_id, file })
+    }
+}
+
+/// Ack data of a start upload job.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StartUploadAckData {
+    session_id: u32,
+    block_len: u32,
+}
+
+impl StartUploadAckData {
+    pub fn init(
+        session_id: u32,
+        block_len: u32,
+    ) -> Self {
+        Self {
+            session_id,
+            block_len,
+        }
+    }
+
+    /// the session of the following upload jobs
+    pub fn session_id(&self) -> u32 {
+        self.session_id
+    }
+
+    /// the length of the block in the load
+    /// memory
+    pub fn block_len(&self) -> u32 {
+        self.block_len
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        16
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        encode_session(0, self.session_id, dst);
+        dst.put_u8(7);
+        dst.extend_from_slice(
+            format!("{:07}", self.block_len)
+                .as_bytes(),
+        );
+    }
+
+    /// `len`: the length of the parameter, the
+    /// function included. The ack of a refused
+    /// job has only the function.
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        len: u16,
+    ) -> Result<Self> {
+        if len < 2 {
+            return Ok(Self::init(0, 0));
+        }
+        let (_, session_id) =
+            decode_session(src)?;
+        check_len(src, "block length", 1)?;
+        let len = src.get_u8() as usize;
+        check_len(src, "block length", len)?;
+        let block_len = src
+            .get(..len)
+            .and_then(|x| {
+                std::str::from_utf8(x).ok()
+            })
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(|| {
+                Error::Error(
+                    "invalid block length"
+                        .to_string(),
+                )
+            })?;
+        src.advance(len);
+        Ok(Self {
+            session_id,
+            block_len,
+        })
+    }
+}
+
+/// Upload job, 0x1e, and end upload job, 0x1f,
+/// of an upload session.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UploadJob {
+    session_id: u32,
+}
+
+impl UploadJob {
+    pub fn init(session_id: u32) -> Self {
+        Self { session_id }
+    }
+
+    pub fn session_id(&self) -> u32 {
+        self.session_id
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        8
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        encode_session(0, self.session_id, dst);
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        let (_, session_id) =
+            decode_session(src)?;
+        Ok(Self { session_id })
+    }
+}
+
+/// Request download job, 0x1a: the plc then
+/// requests the parts of the block by download
+/// block jobs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RequestDownloadJob {
+    session_id: u32,
+    file: BlockFile,
+    load_len: u32,
+    mc7_len: u32,
+}
+
+impl RequestDownloadJob {
+    /// `load_len`: the length of the block,
+    /// `mc7_len`: the length of its MC7 code
+    pub fn init(
+        file: BlockFile,
+        load_len: u32,
+        mc7_len: u32,
+    ) -> Self {
+        Self {
+            session_id: 0,
+            file,
+            load_len,
+            mc7_len,
+        }
+    }
+
+    pub fn file(&self) -> BlockFile {
+        self.file
+    }
+
+    pub fn load_len(&self) -> u32 {
+        self.load_len
+    }
+
+    pub fn mc7_len(&self) -> u32 {
+        self.mc7_len
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        8 + self.file.bytes_len() + 14
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        dst.put_u8(0);
+        dst.put_u16(REQUEST_DOWNLOAD_HEAD);
+        dst.put_u32(self.session_id);
+        self.file.encode(dst);
+        dst.put_u8(13);
+        dst.put_u8(b'1');
+        dst.extend_from_slice(
+            format!(
+                "{:06}{:06}",
+                self.load_len, self.mc7_len
+            )
+            .as_bytes(),
+        );
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        let (_, session_id) =
+            decode_session(src)?;
+        let file = BlockFile::decode(src)?;
+        check_len(src, "block lengths", 14)?;
+        if src[0] != 13 {
+            return Err(Error::Error(format!(
+                "invalid block lengths length: {}",
+                src[0]
+            )));
+        }
+        let lens = src.split_to(14);
+        let len = |x: &[u8]| {
+            std::str::from_utf8(x)
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(|| {
+                    Error::Error(
+                        "invalid block length"
+                            .to_string(),
+                    )
+                })
+        };
+        Ok(Self {
+            session_id,
+            file,
+            load_len: len(&lens[2..8])?,
+            mc7_len: len(&lens[8..14])?,
+        })
+    }
+}
+
+/// Download block job, 0x1b, and download ended
+/// job, 0x1c, sent by the plc to the client
+/// downloading the block.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DownloadJob {
+    session_id: u32,
+    file: BlockFile,
+}
+
+impl DownloadJob {
+    pub fn init(
+        session_id: u32,
+        file: BlockFile,
+    ) -> Self {
+        Self { session_id, file }
+    }
+
+    pub fn session_id(&self) -> u32 {
+        self.session_id
+    }
+
+    pub fn file(&self) -> BlockFile {
+        self.file
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        8 + self.file.bytes_len()
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        encode_session(0, self.session_id, dst);
+        self.file.encode(dst);
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        let (_, session_id) =
+            decode_session(src)?;
+        let file = BlockFile::decode(src)?;
+        Ok(Self { session_id, file })
+    }
+}
+
+/// Ack data of an upload job, or of a download
+/// block job: a part of the block.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UploadAckData {
+    /// more parts follow
+    more: bool,
+    data: Vec<u8>,
+}
+
+impl UploadAckData {
+    pub fn init(more: bool, data: &[u8]) -> Self {
+        Self {
+            more,
+            data: data.to_vec(),
+        }
+    }
+
+    pub fn more(&self) -> bool {
+        self.more
+    }
+
+    pub fn data(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        2
+    }
+
+    pub fn bytes_len_data(&self) -> u16 {
+        self.data.len() as u16 + 4
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        dst.put_u8(self.more as u8);
+        dst.put_u16(self.data.len() as u16);
+        dst.extend_from_slice(
+            &UPLOAD_DATA_CONTROL,
+        );
+        dst.extend_from_slice(&self.data);
+    }
+
+    /// `len`: the length of the parameter, the
+    /// function included
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        len: u16,
+    ) -> Result<Self> {
+        if len < 2 {
+            return Ok(Self::init(false, &[]));
+        }
+        check_len(src, "upload ack data", 5)?;
+        let more = src.get_u8() & 0x01 > 0;
+        let len = src.get_u16() as usize;
+        src.advance(2);
+        check_len(src, "upload data", len)?;
+        Ok(Self {
+            more,
+            data: src.split_to(len).to_vec(),
+        })
+    }
+}
+
+/// The header of a block in the load memory,
+/// its first 36 bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockHeader {
+    pub flags: u8,
+    /// see [`BlockInfo::language`]
+    pub language: u8,
+    /// see [`BlockInfo::sub_block_type`]
+    pub sub_block_type: u8,
+    pub number: u16,
+    /// the length of the block
+    pub load_size: u32,
+    pub security: u32,
+    pub code_time: BlockTimestamp,
+    pub interface_time: BlockTimestamp,
+    pub sbb_len: u16,
+    pub add_len: u16,
+    pub local_data_len: u16,
+    pub mc7_size: u16,
+}
+
+impl BlockHeader {
+    pub const BYTES_LEN: usize = 36;
+
+    pub fn block_type(&self) -> BlockType {
+        BlockType::from_sub_block_type(
+            self.sub_block_type,
+        )
+    }
+
+    pub fn encode(self) -> [u8; 36] {
+        let mut dst = BytesMut::new();
+        dst.extend_from_slice(&[
+            0x70, 0x70, 0x01,
+        ]);
+        dst.put_u8(self.flags);
+        dst.put_u8(self.language);
+        dst.put_u8(self.sub_block_type);
+        dst.put_u16(self.number);
+        dst.put_u32(self.load_size);
+        dst.put_u32(self.security);
+        self.code_time.encode(&mut dst);
+        self.interface_time.encode(&mut dst);
+        dst.put_u16(self.sbb_len);
+        dst.put_u16(self.add_len);
+        dst.put_u16(self.local_data_len);
+        dst.put_u16(self.mc7_size);
+        let mut header = [0; 36];
+        header.copy_from_slice(&dst);
+        header
+    }
+
+    pub fn decode(block: &[u8]) -> Result<Self> {
+        check_len(
+            block,
+            "block header",
+            Self::BYTES_LEN,
+        )?;
+        let mut src = BytesMut::from(
+            &block[..Self::BYTES_LEN],
+        );
+        src.advance(3);
+        Ok(Self {
+            flags: src.get_u8(),
+            language: src.get_u8(),
+            sub_block_type: src.get_u8(),
+            number: src.get_u16(),
+            load_size: src.get_u32(),
+            security: src.get_u32(),
+            code_time: BlockTimestamp::decode(
+                &mut src,
+            ),
+            interface_time:
+                BlockTimestamp::decode(&mut src),
+            sbb_len: src.get_u16(),
+            add_len: src.get_u16(),
+            local_data_len: src.get_u16(),
+            mc7_size: src.get_u16(),
+        })
+    }
+}
+
+/// the MC7 code of a block uploaded from the
+/// load memory, following its header of 36
+/// bytes
+pub fn mc7_code(block: &[u8]) -> Option<&[u8]> {
+    let len = u16::from_be_bytes(
+        block.get(34..36)?.try_into().ok()?,
+    ) as usize;
+    block.get(36..36 + len)
+}
+
+/// function status, unknown bytes and session id
+fn encode_session(
+    status: u8,
+    session_id: u32,
+    dst: &mut BytesMut,
+) {
+    dst.put_u8(status);
+    dst.put_u16(0);
+    dst.put_u32(session_id);
+}
+
+fn decode_session(
+    src: &mut BytesMut,
+) -> Result<(u8, u32)> {
+    check_len(src, "upload session", 7)?;
+    let status = src.get_u8();
+    src.advance(2);
+    Ok((status, src.get_u32()))
+}
+
+/// a text of 8 bytes, padded with nul
+fn put_text(dst: &mut BytesMut, text: &str) {
+    let mut bytes = [0; 8];
+    let len = text.len().min(8);
+    bytes[..len]
+        .copy_from_slice(&text.as_bytes()[..len]);
+    dst.extend_from_slice(&bytes);
+}
+
+fn get_text(src: &mut BytesMut) -> String {
+    let bytes = src.split_to(8);
+    String::from_utf8_lossy(&bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+/// year, month and day of the days since
+/// 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524
+        - doe / 146_096)
+        / 365;
+    let doy =
+        doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day =
+        (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month =
+        if mp < 10 { mp + 3 } else { mp - 9 }
+            as u8;
+    let year =
+        yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
@@ -0,0 +1,51 @@
+use crate::packet::{Frame, Header, Job};
+use crate::{BlockFile, BlockType, DownloadJob, FileSystem, RequestDownloadJob};
+
+/// A job of the download sequence: request
+/// download by the client, download block and
+/// download ended by the plc.
+pub struct FrameJobDownloadBuilder {
+    pdu_ref: u16,
+    job: Job,
+}
+
+impl Default for FrameJobDownloadBuilder {
+    fn default() -> Self {
+        let file = BlockFile::init(BlockType::DB, 0, FileSystem::Passive);
+        Self {
+            pdu_ref: 0,
+            job: Job::DownloadEnded(DownloadJob::init(0, file)),
+        }
+    }
+}
+
+impl FrameJobDownloadBuilder {
+    pub fn pdu_ref(mut self, pdu_ref: u16) -> Self {
+        self.pdu_ref = pdu_ref;
+        self
+    }
+    /// `load_len`: the length of the block,
+    /// `mc7_len`: the length of its MC7 code
+    pub fn request_download(mut self, file: BlockFile, load_len: u32, mc7_len: u32) -> Self {
+        self.job = Job::RequestDownload(RequestDownloadJob::init(file, load_len, mc7_len));
+        self
+    }
+    pub fn download_block(mut self, session_id: u32, file: BlockFile) -> Self {
+        self.job = Job::DownloadBlock(DownloadJob::init(session_id, file));
+        self
+    }
+    pub fn download_ended(mut self, session_id: u32, file: BlockFile) -> Self {
+        self.job = Job::DownloadEnded(DownloadJob::init(session_id, file));
+        self
+    }
+    pub fn build(self) -> Frame {
+        let Self { pdu_ref, job } = self;
+        let parameter_len = match &job {
+            Job::RequestDownload(job) => job.bytes_len_parameter(),
+            Job::DownloadBlock(job) | Job::DownloadEnded(job) => job.bytes_len_parameter(),
+            _ => unreachable!(),
+        };
+        let header = Header::init(pdu_ref, parameter_len, 0);
+        Frame::Job { header, job }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::packet::{Frame, Header, Job};
+use crate::{
+    BlockFile, BlockType, FileSystem, PiServiceJob, PlcStopJob, DELETE_BLOCK, INSERT_BLOCK,
+};
+
+#[derive(Default)]
+pub struct FrameJobPiServiceBuilder {
+    pdu_ref: u16,
+    service: String,
+    parameter: Vec<u8>,
+}
+
+impl FrameJobPiServiceBuilder {
+    pub fn pdu_ref(mut self, pdu_ref: u16) -> Self {
+        self.pdu_ref = pdu_ref;
+        self
+    }
+    pub fn service(mut self, service: &str) -> Self {
+        self.service = service.to_string();
+        self
+    }
+    pub fn parameter(mut self, parameter: &[u8]) -> Self {
+        self.parameter = parameter.to_vec();
+        self
+    }
+    /// delete a block of both file systems
+    pub fn delete_block(self, block_type: BlockType, number: u16) -> Self {
+        let file = BlockFile::init(block_type, number, FileSystem::Both);
+        self.service(DELETE_BLOCK).parameter(&file.pi_parameter())
+    }
+    /// insert a block downloaded to the passive
+    /// file system
+    pub fn insert_block(self, block_type: BlockType, number: u16) -> Self {
+        let file = BlockFile::init(block_type, number, FileSystem::Passive);
+        self.service(INSERT_BLOCK).parameter(&file.pi_parameter())
+    }
+    pub fn build(self) -> Frame {
+        let Self {
+            pdu_ref,
+            service,
+            parameter,
+        } = self;
+        let job = PiServiceJob::init(&service, &parameter);
+        let header = Header::init(pdu_ref, job.bytes_len_parameter(), 0);
+        let job = Job::PiService(job);
+        Frame::Job { header, job }
+    }
+}
+
+#[derive(Default)]
+pub struct FrameJobPlcStopBuilder {
+    pdu_ref: u16,
+    service: String,
+}
+
+impl FrameJobPlcStopBuilder {
+    pub fn pdu_ref(mut self, pdu_ref: u16) -> Self {
+        self.pdu_ref = pdu_ref;
+        self
+    }
+    pub fn service(mut self, service: &str) -> Self {
+        self.service = service.to_string();
+        self
+    }
+    pub fn build(self) -> Frame {
+        let Self { pdu_ref, service } = self;
+        let job = PlcStopJob::init(&service);
+        let header = Header::init(pdu_ref, job.bytes_len_parameter(), 0);
+        let job = Job::PlcStop(job);
+        Frame::Job { header, job }
+    }
+}
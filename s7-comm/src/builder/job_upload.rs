@@ -0,0 +1,47 @@
+use crate::packet::{Frame, Header, Job};
+use crate::{BlockFile, StartUploadJob, UploadJob};
+
+/// A job of the upload sequence: start upload,
+/// upload and end upload.
+pub struct FrameJobUploadBuilder {
+    pdu_ref: u16,
+    job: Job,
+}
+
+impl Default for FrameJobUploadBuilder {
+    fn default() -> Self {
+        Self {
+            pdu_ref: 0,
+            job: Job::EndUpload(UploadJob::init(0)),
+        }
+    }
+}
+
+impl FrameJobUploadBuilder {
+    pub fn pdu_ref(mut self, pdu_ref: u16) -> Self {
+        self.pdu_ref = pdu_ref;
+        self
+    }
+    pub fn start_upload(mut self, file: BlockFile) -> Self {
+        self.job = Job::StartUpload(StartUploadJob::init(file));
+        self
+    }
+    pub fn upload(mut self, session_id: u32) -> Self {
+        self.job = Job::Upload(UploadJob::init(session_id));
+        self
+    }
+    pub fn end_upload(mut self, session_id: u32) -> Self {
+        self.job = Job::EndUpload(UploadJob::init(session_id));
+        self
+    }
+    pub fn build(self) -> Frame {
+        let Self { pdu_ref, job } = self;
+        let parameter_len = match &job {
+            Job::StartUpload(job) => job.bytes_len_parameter(),
+            Job::Upload(job) | Job::EndUpload(job) => job.bytes_len_parameter(),
+            _ => unreachable!(),
+        };
+        let header = Header::init(pdu_ref, parameter_len, 0);
+        Frame::Job { header, job }
+    }
+}
@@ -1,7 +1,15 @@
+mod job_download;
+mod job_plc_control;
 mod job_read_var;
 mod job_setup;
+mod job_upload;
 mod job_write_var;
+mod user_data;
 
+pub use crate::builder::job_plc_control::{FrameJobPiServiceBuilder, FrameJobPlcStopBuilder};
 pub use crate::builder::job_read_var::FrameJobReadVarBuilder;
+pub use crate::builder::job_download::FrameJobDownloadBuilder;
 pub use crate::builder::job_setup::FrameJobSetupBuilder;
+pub use crate::builder::job_upload::FrameJobUploadBuilder;
 pub use crate::builder::job_write_var::FrameJobWriteVarBuilder;
+pub use crate::builder::user_data::FrameUserDataBuilder;
@@ -0,0 +1,304 @@
+use crate::{
+    encode_alarm_query, encode_password,
+    error::*,
+    packet::{Frame, Header},
+    encode_unsubscribe, AlarmAck, AlarmType,
+    BlockFile, BlockType,
+    CyclicInterval, CyclicRequest, DataUnit,
+    DateAndTime, FileSystem, FunctionGroup,
+    ItemRequest, Method, ReturnCode, UserDataItem,
+    UserDataParameter, UserDataType,
+    CLEAR_PASSWORD, CYCLIC_TRANSFER,
+    CYCLIC_UNSUBSCRIBE, GET_BLOCK_INFO,
+    LIST_BLOCKS, LIST_BLOCKS_OF_TYPE,
+    MessageService, ALARM_ACK, ALARM_QUERY,
+    MESSAGE_SERVICE, READ_CLOCK, SET_CLOCK,
+    SET_PASSWORD,
+};
+
+pub struct FrameUserDataBuilder {
+    pdu_ref: u16,
+    parameter: UserDataParameter,
+    data: Option<UserDataItem>,
+}
+
+impl Default for FrameUserDataBuilder {
+    fn default() -> Self {
+        Self {
+            pdu_ref: 0,
+            parameter: UserDataParameter {
+                method: Method::Request,
+                ty: UserDataType::Request,
+                function_group: FunctionGroup::Cpu,
+                subfunction: 0,
+                sequence_number: 0,
+                data_unit: None,
+            },
+            data: None,
+        }
+    }
+}
+
+impl FrameUserDataBuilder {
+    pub fn pdu_ref(
+        mut self,
+        pdu_ref: u16,
+    ) -> Self {
+        self.pdu_ref = pdu_ref;
+        self
+    }
+
+    /// a request of `subfunction` in
+    /// `function_group`
+    pub fn request(
+        mut self,
+        function_group: FunctionGroup,
+        subfunction: u8,
+    ) -> Self {
+        self.parameter.method = Method::Request;
+        self.parameter.ty = UserDataType::Request;
+        self.parameter.function_group =
+            function_group;
+        self.parameter.subfunction = subfunction;
+        self
+    }
+
+    /// a response of `subfunction` in
+    /// `function_group`
+    pub fn response(
+        mut self,
+        function_group: FunctionGroup,
+        subfunction: u8,
+    ) -> Self {
+        self.parameter.method = Method::Response;
+        self.parameter.ty = UserDataType::Response;
+        self.parameter.function_group =
+            function_group;
+        self.parameter.subfunction = subfunction;
+        self
+    }
+
+    /// a push of `subfunction` in
+    /// `function_group`, sent by the plc without
+    /// request
+    pub fn push(
+        mut self,
+        function_group: FunctionGroup,
+        subfunction: u8,
+    ) -> Self {
+        self.parameter.method = Method::Response;
+        self.parameter.ty = UserDataType::Push;
+        self.parameter.function_group =
+            function_group;
+        self.parameter.subfunction = subfunction;
+        self
+    }
+
+    /// the method, a response for the requests
+    /// following a fragmented response
+    pub fn method(
+        mut self,
+        method: Method,
+    ) -> Self {
+        self.parameter.method = method;
+        self
+    }
+
+    pub fn sequence_number(
+        mut self,
+        sequence_number: u8,
+    ) -> Self {
+        self.parameter.sequence_number =
+            sequence_number;
+        self
+    }
+
+    pub fn data_unit(
+        mut self,
+        data_unit: DataUnit,
+    ) -> Self {
+        self.parameter.data_unit = Some(data_unit);
+        self
+    }
+
+    pub fn data(
+        mut self,
+        data: UserDataItem,
+    ) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// a request reading the clock of the plc
+    pub fn read_clock(self) -> Self {
+        self.request(FunctionGroup::Time, READ_CLOCK)
+            .data(UserDataItem::no_data())
+    }
+
+    /// a request setting the clock of the plc to
+    /// `time`
+    pub fn set_clock(
+        self,
+        time: &DateAndTime,
+    ) -> Result<Self> {
+        Ok(self
+            .request(FunctionGroup::Time, SET_CLOCK)
+            .data(UserDataItem::init(
+                ReturnCode::Success,
+                &time.encode_clock()?,
+            )))
+    }
+
+    /// a request counting the blocks of each type
+    pub fn list_blocks(self) -> Self {
+        self.request(FunctionGroup::Block, LIST_BLOCKS)
+            .data(UserDataItem::no_data())
+    }
+
+    /// a request listing the blocks of
+    /// `block_type`
+    pub fn list_blocks_of_type(
+        self,
+        block_type: BlockType,
+    ) -> Self {
+        self.request(
+            FunctionGroup::Block,
+            LIST_BLOCKS_OF_TYPE,
+        )
+        .data(UserDataItem::init(
+            ReturnCode::Success,
+            &[0x30, block_type.into()],
+        ))
+    }
+
+    /// a request reading the info of a block in
+    /// the active file system
+    pub fn block_info(
+        self,
+        block_type: BlockType,
+        number: u16,
+    ) -> Self {
+        let file = BlockFile::init(
+            block_type,
+            number,
+            FileSystem::Active,
+        );
+        self.request(
+            FunctionGroup::Block,
+            GET_BLOCK_INFO,
+        )
+        .data(UserDataItem::init(
+            ReturnCode::Success,
+            &file.name(),
+        ))
+    }
+
+    /// a request setting the session password,
+    /// at most 8 ascii chars
+    pub fn set_password(
+        self,
+        password: &str,
+    ) -> Result<Self> {
+        Ok(self
+            .request(FunctionGroup::Security, SET_PASSWORD)
+            .data(UserDataItem::init(
+                ReturnCode::Success,
+                &encode_password(password)?,
+            )))
+    }
+
+    /// a request clearing the session password
+    pub fn clear_password(self) -> Self {
+        self.request(FunctionGroup::Security, CLEAR_PASSWORD)
+            .data(UserDataItem::no_data())
+    }
+
+    /// a request registering a cyclic read of
+    /// `items`, pushed by the plc at `interval`
+    pub fn cyclic_read(
+        self,
+        interval: CyclicInterval,
+        items: Vec<ItemRequest>,
+    ) -> Self {
+        self.request(
+            FunctionGroup::CyclicData,
+            CYCLIC_TRANSFER,
+        )
+        .data(UserDataItem::init(
+            ReturnCode::Success,
+            &CyclicRequest::init(interval, items)
+                .encode(),
+        ))
+    }
+
+    /// a request cancelling the cyclic read
+    /// `job_id`
+    pub fn cyclic_unsubscribe(
+        self,
+        job_id: u8,
+    ) -> Self {
+        self.request(
+            FunctionGroup::CyclicData,
+            CYCLIC_UNSUBSCRIBE,
+        )
+        .data(UserDataItem::init(
+            ReturnCode::Success,
+            &encode_unsubscribe(job_id),
+        ))
+    }
+
+    /// a request subscribing the messages of the
+    /// plc, or ending the subscription
+    pub fn message_service(
+        self,
+        service: &MessageService,
+    ) -> Result<Self> {
+        Ok(self
+            .request(FunctionGroup::Cpu, MESSAGE_SERVICE)
+            .data(UserDataItem::init(
+                ReturnCode::Success,
+                &service.encode()?,
+            )))
+    }
+
+    /// a request acknowledging the signals of
+    /// alarms
+    pub fn alarm_ack(self, acks: &[AlarmAck]) -> Self {
+        self.request(FunctionGroup::Cpu, ALARM_ACK)
+            .data(UserDataItem::init(
+                ReturnCode::Success,
+                &AlarmAck::encode_list(acks),
+            ))
+    }
+
+    /// a request reading the active alarms of
+    /// `alarm_type`
+    pub fn alarm_query(
+        self,
+        alarm_type: AlarmType,
+    ) -> Self {
+        self.request(FunctionGroup::Cpu, ALARM_QUERY)
+            .data(UserDataItem::init(
+                ReturnCode::Success,
+                &encode_alarm_query(alarm_type),
+            ))
+    }
+
+    pub fn build(self) -> Frame {
+        let Self {
+            pdu_ref,
+            parameter,
+            data,
+        } = self;
+        let header = Header::init(
+            pdu_ref,
+            parameter.bytes_len(),
+            data.as_ref().map_or(0, |x| x.bytes_len()),
+        );
+        Frame::UserData {
+            header,
+            parameter,
+            data,
+        }
+    }
+}
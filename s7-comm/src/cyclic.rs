@@ -0,0 +1,205 @@
+use crate::{
+    error::*, packet::encode_data_items,
+    DataItemVal, ItemRequest,
+};
+use bytes::{Buf, BufMut, BytesMut};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::time::Duration;
+
+/// subfunction of the cyclic data functions
+/// registering a cyclic read, whose data is
+/// then pushed by the plc at its interval
+pub const CYCLIC_TRANSFER: u8 = 0x01;
+/// subfunction of the cyclic data functions
+/// cancelling a cyclic read
+pub const CYCLIC_UNSUBSCRIBE: u8 = 0x04;
+
+/// the function of an unsubscribe request
+const UNSUBSCRIBE_FUNCTION: u8 = 0x80;
+
+/// Unit of the interval of a cyclic read.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum TimeBase {
+    Millis100 = 0x00,
+    Second = 0x01,
+    Seconds10 = 0x02,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+impl TimeBase {
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            TimeBase::Millis100 => {
+                Some(Duration::from_millis(100))
+            },
+            TimeBase::Second => {
+                Some(Duration::from_secs(1))
+            },
+            TimeBase::Seconds10 => {
+                Some(Duration::from_secs(10))
+            },
+            TimeBase::NotSupport(_) => None,
+        }
+    }
+}
+
+/// The interval of a cyclic read: `factor` times
+/// the time base.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CyclicInterval {
+    pub time_base: TimeBase,
+    pub factor: u8,
+}
+
+impl CyclicInterval {
+    /// the interval closest to `interval`, in
+    /// the finest time base it fits, at least
+    /// 100 ms and at most 2550 s
+    pub fn from_duration(
+        interval: Duration,
+    ) -> Self {
+        let millis = interval.as_millis();
+        [
+            TimeBase::Millis100,
+            TimeBase::Second,
+            TimeBase::Seconds10,
+        ]
+        .into_iter()
+        .find_map(|time_base| {
+            let base =
+                time_base.duration()?.as_millis();
+            let factor =
+                (millis + base / 2) / base;
+            (factor <= u8::MAX as u128).then_some(
+                Self {
+                    time_base,
+                    factor: factor.max(1) as u8,
+                },
+            )
+        })
+        .unwrap_or(Self {
+            time_base: TimeBase::Seconds10,
+            factor: u8::MAX,
+        })
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        Some(
+            self.time_base.duration()?
+                * self.factor as u32,
+        )
+    }
+}
+
+/// The data of a cyclic transfer request: the
+/// interval and the items read at it.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CyclicRequest {
+    pub interval: CyclicInterval,
+    pub items: Vec<ItemRequest>,
+}
+
+impl CyclicRequest {
+    pub fn init(
+        interval: CyclicInterval,
+        items: Vec<ItemRequest>,
+    ) -> Self {
+        Self { interval, items }
+    }
+
+    pub fn encode(self) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        dst.put_u16(self.items.len() as u16);
+        dst.put_u8(
+            self.interval.time_base.into(),
+        );
+        dst.put_u8(self.interval.factor);
+        for item in self.items {
+            item.encode(&mut dst);
+        }
+        dst.to_vec()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_len(data, "cyclic request", 4)?;
+        let mut src = BytesMut::from(data);
+        let count = src.get_u16();
+        let interval = CyclicInterval {
+            time_base: TimeBase::from(
+                src.get_u8(),
+            ),
+            factor: src.get_u8(),
+        };
+        let items = (0..count)
+            .map(|_| {
+                ItemRequest::decode(&mut src)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { interval, items })
+    }
+}
+
+/// The data of the response of a cyclic transfer
+/// request, and of the pushes of the plc: an
+/// item for each item requested.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CyclicData {
+    pub items: Vec<DataItemVal>,
+}
+
+impl CyclicData {
+    pub fn init(items: Vec<DataItemVal>) -> Self {
+        Self { items }
+    }
+
+    pub fn encode(self) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        dst.put_u16(self.items.len() as u16);
+        encode_data_items(self.items, &mut dst);
+        dst.to_vec()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        check_len(data, "cyclic data", 2)?;
+        let mut src = BytesMut::from(data);
+        let count = src.get_u16();
+        let items = (0..count)
+            .map(|_| {
+                DataItemVal::decode(&mut src)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { items })
+    }
+}
+
+/// the data of an unsubscribe request of the
+/// cyclic read `job_id`, the sequence number of
+/// its response and pushes
+pub fn encode_unsubscribe(job_id: u8) -> [u8; 2] {
+    [UNSUBSCRIBE_FUNCTION, job_id]
+}
+
+/// the job id of the data of an unsubscribe
+/// request
+pub fn decode_unsubscribe(
+    data: &[u8],
+) -> Result<u8> {
+    match data {
+        [_, job_id] => Ok(*job_id),
+        _ => Err(Error::Error(format!(
+            "unsubscribe request should be 2 \
+             bytes, but not {}",
+            data.len()
+        ))),
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::*;
+
+/// subfunction of the time functions reading the
+/// clock
+pub const READ_CLOCK: u8 = 0x01;
+/// subfunction of the time functions setting the
+/// clock
+pub const SET_CLOCK: u8 = 0x02;
+
+/// S7 DATE_AND_TIME, without timezone: 8 bytes
+/// of BCD from year 1990 to 2089, with
+/// milliseconds and the weekday.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DateAndTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+    /// 1 sunday to 7 saturday
+    pub weekday: u8,
+}
+
+impl Default for DateAndTime {
+    /// 1990-01-01 00:00:00.000, a monday
+    fn default() -> Self {
+        Self {
+            year: 1990,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+            weekday: 2,
+        }
+    }
+}
+
+impl DateAndTime {
+    /// a date and time, with the weekday of the
+    /// date
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        millisecond: u16,
+    ) -> Result<Self> {
+        let mut date_time = Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            millisecond,
+            weekday: 1,
+        };
+        date_time.check()?;
+        date_time.weekday =
+            weekday(year, month, day);
+        Ok(date_time)
+    }
+
+    pub fn encode(&self) -> Result<[u8; 8]> {
+        self.check()?;
+        let year = (self.year % 100) as u8;
+        let millisecond = self.millisecond;
+        Ok([
+            bcd(year),
+            bcd(self.month),
+            bcd(self.day),
+            bcd(self.hour),
+            bcd(self.minute),
+            bcd(self.second),
+            bcd((millisecond / 10) as u8),
+            (((millisecond % 10) as u8) << 4)
+                | self.weekday,
+        ])
+    }
+
+    pub fn decode(src: &[u8]) -> Result<Self> {
+        check_len(src, "DATE_AND_TIME", 8)?;
+        let year = from_bcd(src[0])? as u16;
+        let date_time = Self {
+            year: if year >= 90 {
+                1900 + year
+            } else {
+                2000 + year
+            },
+            month: from_bcd(src[1])?,
+            day: from_bcd(src[2])?,
+            hour: from_bcd(src[3])?,
+            minute: from_bcd(src[4])?,
+            second: from_bcd(src[5])?,
+            millisecond: from_bcd(src[6])? as u16
+                * 10
+                + from_bcd(src[7] >> 4)? as u16,
+            weekday: src[7] & 0x0f,
+        };
+        date_time.check()?;
+        Ok(date_time)
+    }
+
+    /// the data of the clock functions: a
+    /// reserved byte and the century, followed by
+    /// the DATE_AND_TIME
+    pub fn encode_clock(
+        &self,
+    ) -> Result<[u8; 10]> {
+        let mut data = [0; 10];
+        data[1] = bcd((self.year / 100) as u8);
+        data[2..]
+            .copy_from_slice(&self.encode()?);
+        Ok(data)
+    }
+
+    pub fn decode_clock(
+        src: &[u8],
+    ) -> Result<Self> {
+        check_len(src, "clock", 10)?;
+        Self::decode(&src[2..])
+    }
+
+    fn check(&self) -> Result<()> {
+        if !(1990..=2089).contains(&self.year)
+            || !(1..=12).contains(&self.month)
+            || self.day == 0
+            || self.day
+                > days_of_month(
+                    self.year, self.month,
+                )
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 59
+            || self.millisecond > 999
+            || !(1..=7).contains(&self.weekday)
+        {
+            return Err(Error::Error(format!(
+                "invalid DATE_AND_TIME: {:?}",
+                self
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+fn from_bcd(value: u8) -> Result<u8> {
+    let (high, low) = (value >> 4, value & 0x0f);
+    if high > 9 || low > 9 {
+        return Err(Error::Error(format!(
+            "invalid bcd: {:#04x}",
+            value
+        )));
+    }
+    Ok(high * 10 + low)
+}
+
+fn days_of_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4)
+            && (!year.is_multiple_of(100)
+                || year.is_multiple_of(400)) =>
+        {
+            29
+        },
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// 1 sunday to 7 saturday, by Zeller's
+/// congruence
+fn weekday(year: u16, month: u8, day: u8) -> u8 {
+    let (year, month) = if month < 3 {
+        (year as u32 - 1, month as u32 + 12)
+    } else {
+        (year as u32, month as u32)
+    };
+    let (century, year) =
+        (year / 100, year % 100);
+    // 0 saturday to 6 friday
+    let h = (day as u32
+        + 13 * (month + 1) / 5
+        + year
+        + year / 4
+        + century / 4
+        + 5 * century)
+        % 7;
+    ((h + 6) % 7 + 1) as u8
+}
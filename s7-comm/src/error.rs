@@ -12,6 +12,14 @@ pub enum Error {
     // AnyhowErr(#[from] anyhow::Error),
     #[error("Error: {0}")]
     Error(String),
+
+    /// the data of `what` shorter than needed
+    #[error("data of {what} not enough: {len} < {need}")]
+    NotEnough {
+        what: &'static str,
+        need: usize,
+        len: usize,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -27,3 +35,20 @@ impl ToCoptError for Error {
         copt::error::Error::Error(self.to_string())
     }
 }
+
+/// check that `src` holds the `need` bytes of
+/// `what`, before reading them
+pub(crate) fn check_len(
+    src: &[u8],
+    what: &'static str,
+    need: usize,
+) -> Result<()> {
+    if src.len() < need {
+        return Err(Error::NotEnough {
+            what,
+            need,
+            len: src.len(),
+        });
+    }
+    Ok(())
+}
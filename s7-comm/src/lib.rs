@@ -1,12 +1,26 @@
+mod alarm;
+mod block;
 pub mod builder;
+mod cyclic;
+mod date_time;
 mod error;
 mod packet;
+mod plc_control;
+mod security;
+mod user_data;
 
 // use crate::packet::{AckData, Frame, Header, HearderAckData, Job};
+pub use alarm::*;
+pub use block::*;
+pub use cyclic::*;
 use bytes::{BufMut, BytesMut};
+pub use date_time::*;
 pub use error::*;
 use log::debug;
 pub use packet::*;
+pub use plc_control::*;
+pub use security::*;
+pub use user_data::*;
 use tokio_util::codec::{Decoder, Encoder};
 
 #[derive(Default)]
@@ -47,6 +61,38 @@ impl Encoder<Frame> for S7CommEncoder {
                         dst.put_u8(0x04);
                         data.encode(dst);
                     }
+                    Job::PiService(data) => {
+                        dst.put_u8(0x28);
+                        data.encode(dst);
+                    }
+                    Job::PlcStop(data) => {
+                        dst.put_u8(0x29);
+                        data.encode(dst);
+                    }
+                    Job::StartUpload(data) => {
+                        dst.put_u8(0x1d);
+                        data.encode(dst);
+                    }
+                    Job::Upload(data) => {
+                        dst.put_u8(0x1e);
+                        data.encode(dst);
+                    }
+                    Job::EndUpload(data) => {
+                        dst.put_u8(0x1f);
+                        data.encode(dst);
+                    }
+                    Job::RequestDownload(data) => {
+                        dst.put_u8(0x1a);
+                        data.encode(dst);
+                    }
+                    Job::DownloadBlock(data) => {
+                        dst.put_u8(0x1b);
+                        data.encode(dst);
+                    }
+                    Job::DownloadEnded(data) => {
+                        dst.put_u8(0x1c);
+                        data.encode(dst);
+                    }
                 }
             }
             Frame::AckData { header, ack_data } => {
@@ -80,6 +126,58 @@ impl Encoder<Frame> for S7CommEncoder {
                         dst.put_u8(0x04);
                         data.encode(dst);
                     }
+                    AckData::PiService(data) => {
+                        dst.put_u8(0x28);
+                        data.encode(dst);
+                    }
+                    AckData::PlcStop(data) => {
+                        dst.put_u8(0x29);
+                        data.encode(dst);
+                    }
+                    AckData::StartUpload(data) => {
+                        dst.put_u8(0x1d);
+                        data.encode(dst);
+                    }
+                    AckData::Upload(data) => {
+                        dst.put_u8(0x1e);
+                        data.encode(dst);
+                    }
+                    AckData::EndUpload => {
+                        dst.put_u8(0x1f);
+                    }
+                    AckData::RequestDownload => {
+                        dst.put_u8(0x1a);
+                    }
+                    AckData::DownloadBlock(data) => {
+                        dst.put_u8(0x1b);
+                        data.encode(dst);
+                    }
+                    AckData::DownloadEnded => {
+                        dst.put_u8(0x1c);
+                    }
+                }
+            }
+            Frame::UserData {
+                header,
+                parameter,
+                data,
+            } => {
+                let Header {
+                    protocol_id,
+                    reserved,
+                    pdu_ref,
+                    parameter_len,
+                    data_len,
+                } = header;
+                dst.put_u8(protocol_id);
+                dst.put_u8(0x07);
+                dst.extend_from_slice(reserved.to_be_bytes().as_slice());
+                dst.extend_from_slice(pdu_ref.to_be_bytes().as_slice());
+                dst.extend_from_slice(parameter_len.to_be_bytes().as_slice());
+                dst.extend_from_slice(data_len.to_be_bytes().as_slice());
+                parameter.encode(dst);
+                if let Some(data) = data {
+                    data.encode(dst);
                 }
             }
         }
@@ -98,42 +196,56 @@ impl Decoder for S7CommDecoder {
         if src.len() < 10 {
             return Ok(None);
         }
-        let Some(rosctr) = src.get(1) else {
-            unreachable!()
-        };
-        let (Some(parameter_0), Some(parameter_1)) = (src.get(6), src.get(7)) else {
-            unreachable!()
-        };
-        let (Some(data_0), Some(data_1)) = (src.get(8), src.get(9)) else {
-            unreachable!()
+        if src[0] != 0x32 {
+            return Err(Error::Error(format!("not support protocol id: {}", src[0])));
+        }
+        let rosctr = src[1];
+        let parameter_length = u16::from_be_bytes([src[6], src[7]]);
+        let data_length = u16::from_be_bytes([src[8], src[9]]);
+        let header_length = match rosctr {
+            1 | 7 => 10,
+            3 => 12,
+            _ => return Err(Error::Error(format!("not support rosctr: {}", rosctr))),
         };
-
-        let parameter_length = u16::from_be_bytes([*parameter_0, *parameter_1]);
-        let data_length = u16::from_be_bytes([*data_0, *data_1]);
-        match *rosctr {
+        let length = header_length + parameter_length as usize + data_length as usize;
+        if src.len() < length {
+            debug!(
+                "parameter_length: {}, data_length: {}",
+                parameter_length, data_length
+            );
+            return Ok(None);
+        }
+        // the sub decoders only see the bytes announced by the header
+        let mut src = src.split_to(length);
+        match rosctr {
             1 => {
                 // job
-                if src.len() < (10 + parameter_length + data_length) as usize {
-                    return Ok(None);
-                }
-                let header = Header::decode(src);
-                let job = Job::decode(src)?;
+                let header = Header::decode(&mut src);
+                let job = Job::decode(&mut src)?;
                 Ok(Some(Frame::Job { header, job }))
             }
             3 => {
                 // ack data
-                if src.len() < (12 + parameter_length + data_length) as usize {
-                    debug!(
-                        "parameter_length: {}, data_length: {}",
-                        parameter_length, data_length
-                    );
-                    return Ok(None);
-                }
-                let header = HearderAckData::decode(src);
-                let ack_data = AckData::decode(src)?;
+                let header = HearderAckData::decode(&mut src);
+                let ack_data = AckData::decode(&mut src, parameter_length)?;
                 Ok(Some(Frame::AckData { header, ack_data }))
             }
-            _ => Err(Error::Error(format!("not support rosctr: {}", rosctr))),
+            7 => {
+                // userdata
+                let header = Header::decode(&mut src);
+                let parameter = UserDataParameter::decode(&mut src, parameter_length)?;
+                let data = if data_length > 0 {
+                    Some(UserDataItem::decode(&mut src, data_length)?)
+                } else {
+                    None
+                };
+                Ok(Some(Frame::UserData {
+                    header,
+                    parameter,
+                    data,
+                }))
+            }
+            _ => unreachable!(),
         }
     }
 }
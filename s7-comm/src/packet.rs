@@ -1,5 +1,12 @@
-use crate::{builder::*, error::*};
-use bytes::{Buf, BufMut, BytesMut};
+use crate::{
+    builder::*, error::*, DownloadJob,
+    PiServiceJob, PlcControlAckData, PlcStopJob,
+    RequestDownloadJob, StartUploadAckData,
+    StartUploadJob,
+    UploadAckData, UploadJob, UserDataItem,
+    UserDataParameter,
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use num_enum::{
     FromPrimitive, IntoPrimitive,
     TryFromPrimitive,
@@ -15,6 +22,12 @@ pub enum Frame {
         header: HearderAckData,
         ack_data: AckData,
     },
+    /// 0x07
+    UserData {
+        header: Header,
+        parameter: UserDataParameter,
+        data: Option<UserDataItem>,
+    },
 }
 
 impl Frame {
@@ -38,6 +51,71 @@ impl Frame {
         FrameJobReadVarBuilder::default()
             .pdu_ref(pdu_ref)
     }
+
+    pub fn job_pi_service(
+        pdu_ref: u16,
+    ) -> FrameJobPiServiceBuilder {
+        FrameJobPiServiceBuilder::default()
+            .pdu_ref(pdu_ref)
+    }
+
+    pub fn job_plc_stop(
+        pdu_ref: u16,
+    ) -> FrameJobPlcStopBuilder {
+        FrameJobPlcStopBuilder::default()
+            .pdu_ref(pdu_ref)
+    }
+
+    pub fn job_upload(
+        pdu_ref: u16,
+    ) -> FrameJobUploadBuilder {
+        FrameJobUploadBuilder::default()
+            .pdu_ref(pdu_ref)
+    }
+
+    pub fn job_download(
+        pdu_ref: u16,
+    ) -> FrameJobDownloadBuilder {
+        FrameJobDownloadBuilder::default()
+            .pdu_ref(pdu_ref)
+    }
+
+    pub fn user_data(
+        pdu_ref: u16,
+    ) -> FrameUserDataBuilder {
+        FrameUserDataBuilder::default()
+            .pdu_ref(pdu_ref)
+    }
+
+    /// reference of the pdu, to correlate an ack
+    /// data with its job
+    pub fn pdu_ref(&self) -> u16 {
+        match self {
+            Frame::Job { header, .. } => {
+                header.pdu_ref
+            },
+            Frame::AckData { header, .. } => {
+                header.pdu_ref
+            },
+            Frame::UserData { header, .. } => {
+                header.pdu_ref
+            },
+        }
+    }
+
+    pub fn set_pdu_ref(&mut self, pdu_ref: u16) {
+        match self {
+            Frame::Job { header, .. } => {
+                header.pdu_ref = pdu_ref
+            },
+            Frame::AckData { header, .. } => {
+                header.pdu_ref = pdu_ref
+            },
+            Frame::UserData { header, .. } => {
+                header.pdu_ref = pdu_ref
+            },
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -148,6 +226,18 @@ impl HearderAckData {
             error_code,
         }
     }
+
+    pub fn pdu_ref(&self) -> u16 {
+        self.pdu_ref
+    }
+
+    pub fn error_class(&self) -> u8 {
+        self.error_class
+    }
+
+    pub fn error_code(&self) -> u8 {
+        self.error_code
+    }
 }
 
 // #[derive(IntoPrimitive, FromPrimitive)]
@@ -168,15 +258,37 @@ pub enum Job {
     WriteVar(WriteVarJob),
     /// 0x04
     ReadVar(ReadVarJob),
+    /// 0x28
+    PiService(PiServiceJob),
+    /// 0x29
+    PlcStop(PlcStopJob),
+    /// 0x1d
+    StartUpload(StartUploadJob),
+    /// 0x1e
+    Upload(UploadJob),
+    /// 0x1f
+    EndUpload(UploadJob),
+    /// 0x1a
+    RequestDownload(RequestDownloadJob),
+    /// 0x1b, sent by the plc
+    DownloadBlock(DownloadJob),
+    /// 0x1c, sent by the plc
+    DownloadEnded(DownloadJob),
 }
 
 impl Job {
     pub(crate) fn decode(
         src: &mut BytesMut,
     ) -> Result<Self> {
+        check_len(src, "job function", 1)?;
         let function = src.get_u8();
         match function {
             0x04 => {
+                check_len(
+                    src,
+                    "read var job",
+                    1,
+                )?;
                 let count = src.get_u8();
                 let mut parameters_item =
                     Vec::with_capacity(
@@ -188,11 +300,16 @@ impl Job {
                     );
                 }
                 Ok(Self::ReadVar(ReadVarJob {
-                    count: 0,
+                    count,
                     parameters_item,
                 }))
             },
             0x05 => {
+                check_len(
+                    src,
+                    "write var job",
+                    1,
+                )?;
                 let count = src.get_u8();
                 let mut parameters_item =
                     Vec::with_capacity(
@@ -213,7 +330,7 @@ impl Job {
                     );
                 }
                 Ok(Self::WriteVar(WriteVarJob {
-                    count: 0,
+                    count,
                     parameters_item,
                     data_item,
                 }))
@@ -225,6 +342,30 @@ impl Job {
                     )?;
                 Ok(Self::SetupCommunication(data))
             },
+            0x28 => Ok(Self::PiService(
+                PiServiceJob::decode(src)?,
+            )),
+            0x29 => Ok(Self::PlcStop(
+                PlcStopJob::decode(src)?,
+            )),
+            0x1d => Ok(Self::StartUpload(
+                StartUploadJob::decode(src)?,
+            )),
+            0x1e => Ok(Self::Upload(
+                UploadJob::decode(src)?,
+            )),
+            0x1f => Ok(Self::EndUpload(
+                UploadJob::decode(src)?,
+            )),
+            0x1a => Ok(Self::RequestDownload(
+                RequestDownloadJob::decode(src)?,
+            )),
+            0x1b => Ok(Self::DownloadBlock(
+                DownloadJob::decode(src)?,
+            )),
+            0x1c => Ok(Self::DownloadEnded(
+                DownloadJob::decode(src)?,
+            )),
             _ => Err(Error::Error(format!(
                 "not support function: {}",
                 function
@@ -241,15 +382,38 @@ pub enum AckData {
     WriteVar(WriteVarAckData),
     /// 0x04
     ReadVar(ReadVarAckData),
+    /// 0x28
+    PiService(PlcControlAckData),
+    /// 0x29
+    PlcStop(PlcControlAckData),
+    /// 0x1d
+    StartUpload(StartUploadAckData),
+    /// 0x1e
+    Upload(UploadAckData),
+    /// 0x1f
+    EndUpload,
+    /// 0x1a
+    RequestDownload,
+    /// 0x1b, sent by the client
+    DownloadBlock(UploadAckData),
+    /// 0x1c, sent by the client
+    DownloadEnded,
 }
 
 impl AckData {
     pub(crate) fn decode(
         src: &mut BytesMut,
+        parameter_len: u16,
     ) -> Result<Self> {
+        check_len(src, "ack data function", 1)?;
         let function = src.get_u8();
         match function {
             0x04 => {
+                check_len(
+                    src,
+                    "read var ack data",
+                    1,
+                )?;
                 let count = src.get_u8();
                 let mut data_item =
                     Vec::with_capacity(
@@ -268,6 +432,11 @@ impl AckData {
                 ))
             },
             0x05 => {
+                check_len(
+                    src,
+                    "write var ack data",
+                    1,
+                )?;
                 let count = src.get_u8();
                 // let mut parameters_item =
                 // Vec::with_capacity(count as
@@ -297,6 +466,39 @@ impl AckData {
                     )?;
                 Ok(Self::SetupCommunication(data))
             },
+            0x28 => Ok(Self::PiService(
+                PlcControlAckData::decode(
+                    src,
+                    parameter_len,
+                )?,
+            )),
+            0x29 => Ok(Self::PlcStop(
+                PlcControlAckData::decode(
+                    src,
+                    parameter_len,
+                )?,
+            )),
+            0x1d => Ok(Self::StartUpload(
+                StartUploadAckData::decode(
+                    src,
+                    parameter_len,
+                )?,
+            )),
+            0x1e => Ok(Self::Upload(
+                UploadAckData::decode(
+                    src,
+                    parameter_len,
+                )?,
+            )),
+            0x1f => Ok(Self::EndUpload),
+            0x1a => Ok(Self::RequestDownload),
+            0x1b => Ok(Self::DownloadBlock(
+                UploadAckData::decode(
+                    src,
+                    parameter_len,
+                )?,
+            )),
+            0x1c => Ok(Self::DownloadEnded),
             _ => Err(Error::Error(format!(
                 "not support function: {}",
                 function
@@ -315,9 +517,7 @@ pub struct WriteVarJob {
 
 impl WriteVarJob {
     pub fn bytes_len_data(&self) -> u16 {
-        self.data_item
-            .iter()
-            .fold(0, |len, x| len + x.bytes_len())
+        bytes_len_data_items(&self.data_item)
     }
 
     pub fn bytes_len_parameter(&self) -> u16 {
@@ -335,6 +535,25 @@ impl WriteVarJob {
         self.data_item.push(x.1);
     }
 
+    /// the requested items with their data, none
+    /// if their counts differ
+    pub fn items(
+        self,
+    ) -> Option<Vec<(ItemRequest, DataItemVal)>>
+    {
+        if self.parameters_item.len()
+            != self.data_item.len()
+        {
+            return None;
+        }
+        Some(
+            self.parameters_item
+                .into_iter()
+                .zip(self.data_item)
+                .collect(),
+        )
+    }
+
     pub(crate) fn encode(
         self,
         dst: &mut BytesMut,
@@ -343,9 +562,7 @@ impl WriteVarJob {
         self.parameters_item
             .into_iter()
             .for_each(|x| x.encode(dst));
-        self.data_item
-            .into_iter()
-            .for_each(|x| x.encode(dst));
+        encode_data_items(self.data_item, dst);
     }
 }
 
@@ -403,6 +620,10 @@ impl ReadVarJob {
         self.parameters_item.push(x);
     }
 
+    pub fn parameters_item(self) -> Vec<ItemRequest> {
+        self.parameters_item
+    }
+
     pub(crate) fn encode(
         self,
         dst: &mut BytesMut,
@@ -424,6 +645,10 @@ impl ReadVarAckData {
         self.data_item
     }
 
+    pub fn bytes_len_data(&self) -> u16 {
+        bytes_len_data_items(&self.data_item)
+    }
+
     pub fn add_response(
         mut self,
         value: DataItemVal,
@@ -438,9 +663,35 @@ impl ReadVarAckData {
         dst: &mut BytesMut,
     ) {
         dst.put_u8(self.count);
-        self.data_item
-            .into_iter()
-            .for_each(|x| x.encode(dst));
+        encode_data_items(self.data_item, dst);
+    }
+}
+
+/// data items of odd length are followed by a
+/// fill byte, except the last one
+fn bytes_len_data_items(items: &[DataItemVal]) -> u16 {
+    let fill_byte_len = items
+        .iter()
+        .rev()
+        .skip(1)
+        .filter(|x| x.data.len() % 2 > 0)
+        .count() as u16;
+    items
+        .iter()
+        .fold(fill_byte_len, |len, x| len + x.bytes_len())
+}
+
+pub(crate) fn encode_data_items(
+    items: Vec<DataItemVal>,
+    dst: &mut BytesMut,
+) {
+    let last = items.len().saturating_sub(1);
+    for (index, item) in items.into_iter().enumerate() {
+        let fill_byte = item.data.len() % 2 > 0 && index < last;
+        item.encode(dst);
+        if fill_byte {
+            dst.put_u8(0);
+        }
     }
 }
 
@@ -495,13 +746,11 @@ impl SetupCommunication {
     fn decode(
         src: &mut BytesMut,
     ) -> Result<Self> {
-        if src.len() < Self::len() {
-            return Err(Error::Error(
-                "data of SetupCommunication not \
-                 enough"
-                    .to_string(),
-            ));
-        }
+        check_len(
+            src,
+            "setup communication",
+            Self::len(),
+        )?;
         let reserved = src.get_u8();
         let max_amq_calling = src.get_u16();
         let max_amq_called = src.get_u16();
@@ -517,6 +766,14 @@ impl SetupCommunication {
     pub fn pdu_length(&self) -> u16 {
         self.pdu_length
     }
+
+    pub fn max_amq_calling(&self) -> u16 {
+        self.max_amq_calling
+    }
+
+    pub fn max_amq_called(&self) -> u16 {
+        self.max_amq_called
+    }
 }
 
 const PARAM_ITEM_VAR_SPEC: u8 = 0x12;
@@ -675,7 +932,28 @@ impl ItemRequest {
         12
     }
 
-    fn encode(self, dst: &mut BytesMut) {
+    pub fn transport_size_type(&self) -> TransportSize {
+        self.transport_size_type
+    }
+
+    /// count of elements of the transport size
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    pub fn db_number(&self) -> DbNumber {
+        self.db_number
+    }
+
+    pub fn area(&self) -> Area {
+        self.area
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
         dst.put_u8(self.variable_specification);
         dst.put_u8(self.follow_length);
         dst.put_u8(self.syntax_id.into());
@@ -692,15 +970,10 @@ impl ItemRequest {
         );
     }
 
-    fn decode(
+    pub(crate) fn decode(
         src: &mut BytesMut,
     ) -> Result<Self> {
-        if src.len() < 12 {
-            return Err(Error::Error(
-                "item request byte's length is not enough"
-                    .to_string(),
-            ));
-        }
+        check_len(src, "item request", 12)?;
         let variable_specification = src.get_u8();
         let follow_length = src.get_u8();
         let syntax_id =
@@ -746,12 +1019,7 @@ impl DataItemWriteResponse {
     fn decode(
         src: &mut BytesMut,
     ) -> Result<Self> {
-        if src.len() == 0 {
-            return Err(Error::Error(
-                "byte's length is zero"
-                    .to_string(),
-            ));
-        }
+        check_len(src, "write response", 1)?;
         Ok(Self {
             return_code: ReturnCode::try_from(
                 src.get_u8(),
@@ -765,7 +1033,7 @@ pub struct DataItemVal {
     pub transport_size_type: DataTransportSize,
     // 位查询,返回长度为0x0001; 非位查询,长度须左移3位
     pub length: u16,
-    pub data: Vec<u8>,
+    pub data: Bytes,
 }
 
 impl DataItemVal {
@@ -778,7 +1046,7 @@ impl DataItemVal {
             transport_size_type:
                 DataTransportSize::NoBit,
             length: (data.len() as u16) << 3,
-            data: data.to_vec(),
+            data: Bytes::copy_from_slice(data),
         }
     }
 
@@ -791,11 +1059,11 @@ impl DataItemVal {
             transport_size_type:
                 DataTransportSize::Bit,
             length: 1,
-            data: if data {
-                vec![1]
+            data: Bytes::from_static(if data {
+                &[1]
             } else {
-                vec![0]
-            },
+                &[0]
+            }),
         }
     }
 
@@ -812,19 +1080,14 @@ impl DataItemVal {
             self.length.to_be_bytes().as_slice(),
         );
         dst.extend_from_slice(
-            self.data.as_slice(),
+            &self.data,
         );
     }
 
-    fn decode(
+    pub(crate) fn decode(
         src: &mut BytesMut,
     ) -> Result<Self> {
-        if src.len() < 4 {
-            return Err(Error::Error(
-                format!("data item val byte's length is not enough: {}"
-                    , src.len()),
-            ));
-        }
+        check_len(src, "data item", 4)?;
         let return_code =
             ReturnCode::try_from(src.get_u8())?;
         let transport_size_type =
@@ -839,16 +1102,9 @@ impl DataItemVal {
         }
 
         let fill_byte_len = bytes_len % 2;
-        if src.len() < bytes_len {
-            return Err(Error::Error(
-                format!("data item val byte's length is not enough: {} < {}", src.len(), bytes_len),
-            ));
-        }
-        let mut data =
-            Vec::with_capacity(bytes_len);
-        for _ in 0..bytes_len {
-            data.push(src.get_u8())
-        }
+        check_len(src, "data item", bytes_len)?;
+        let data =
+            src.split_to(bytes_len).freeze();
         if fill_byte_len > 0 && src.len() >= 1 {
             src.get_u8();
         }
@@ -926,8 +1182,10 @@ pub enum TransportType {
 )]
 #[repr(u8)]
 pub enum DataTransportSize {
+    Null = 0x00,
     Bit = 0x03,
     NoBit = 0x04,
+    OctetString = 0x09,
     #[num_enum(catch_all)]
     NotSupport(u8),
 }
@@ -945,12 +1203,16 @@ pub enum DataTransportSize {
 pub enum TransportSize {
     Bit = 0x01,
     NoBit = 0x02,
+    Counter = 0x1c,
+    Timer = 0x1d,
     #[num_enum(catch_all)]
     NotSupport(u8),
 }
 
 #[derive(
     Debug,
+    Copy,
+    Clone,
     IntoPrimitive,
     FromPrimitive,
     Eq,
@@ -983,6 +1245,8 @@ pub enum Syntax {
 }
 #[derive(
     Debug,
+    Copy,
+    Clone,
     IntoPrimitive,
     FromPrimitive,
     Eq,
@@ -1011,6 +1275,14 @@ impl Address {
         ]
     }
 
+    pub fn byte_addr(&self) -> u16 {
+        self.byte_addr
+    }
+
+    pub fn bit_addr(&self) -> u8 {
+        self.bit_addr
+    }
+
     pub fn from_bytes(
         index_0: u8,
         index_1: u8,
@@ -0,0 +1,209 @@
+use crate::error::*;
+use bytes::{Buf, BufMut, BytesMut};
+
+/// the unknown bytes of a PI service job
+const PI_SERVICE_HEAD: [u8; 7] =
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfd];
+/// the unknown bytes of a plc stop job
+const PLC_STOP_HEAD: [u8; 5] =
+    [0x00, 0x00, 0x00, 0x00, 0x00];
+
+/// the PI service starting and stopping the
+/// program
+pub const P_PROGRAM: &str = "P_PROGRAM";
+/// the PI service copying ram to rom
+pub const MODU: &str = "_MODU";
+/// the PI service compressing the memory
+pub const GARB: &str = "_GARB";
+/// state of a refused start, the plc already
+/// in run
+pub const ALREADY_RUN: u8 = 0x02;
+/// state of a refused stop, the plc already in
+/// stop
+pub const ALREADY_STOP: u8 = 0x07;
+/// error code of a block not in the plc
+pub const BLOCK_NOT_FOUND: u16 = 0xd209;
+
+/// PI (program invocation) service job, 0x28:
+/// start the plc, copy ram to rom or compress
+/// the memory.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PiServiceJob {
+    parameter: Vec<u8>,
+    service: Vec<u8>,
+}
+
+impl PiServiceJob {
+    pub fn init(
+        service: &str,
+        parameter: &[u8],
+    ) -> Self {
+        Self {
+            parameter: parameter.to_vec(),
+            service: service.as_bytes().to_vec(),
+        }
+    }
+
+    /// the name of the service, as `P_PROGRAM`
+    pub fn service(&self) -> &[u8] {
+        &self.service
+    }
+
+    /// the parameter block, as `C ` for a cold
+    /// start
+    pub fn parameter(&self) -> &[u8] {
+        &self.parameter
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        (PI_SERVICE_HEAD.len()
+            + 4
+            + self.parameter.len()
+            + self.service.len()) as u16
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        dst.extend_from_slice(&PI_SERVICE_HEAD);
+        dst.put_u16(self.parameter.len() as u16);
+        dst.extend_from_slice(&self.parameter);
+        dst.put_u8(self.service.len() as u8);
+        dst.extend_from_slice(&self.service);
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        check_len(
+            src,
+            "pi service job",
+            PI_SERVICE_HEAD.len() + 2,
+        )?;
+        src.advance(PI_SERVICE_HEAD.len());
+        let len = src.get_u16() as usize;
+        check_len(
+            src,
+            "pi service job",
+            len + 1,
+        )?;
+        let parameter =
+            src.split_to(len).to_vec();
+        let service = decode_service(src)?;
+        Ok(Self { parameter, service })
+    }
+}
+
+/// Plc stop job, 0x29.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlcStopJob {
+    service: Vec<u8>,
+}
+
+impl PlcStopJob {
+    pub fn init(service: &str) -> Self {
+        Self {
+            service: service.as_bytes().to_vec(),
+        }
+    }
+
+    /// the name of the service, as `P_PROGRAM`
+    pub fn service(&self) -> &[u8] {
+        &self.service
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        (PLC_STOP_HEAD.len()
+            + 2
+            + self.service.len()) as u16
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        dst.extend_from_slice(&PLC_STOP_HEAD);
+        dst.put_u8(self.service.len() as u8);
+        dst.extend_from_slice(&self.service);
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+    ) -> Result<Self> {
+        check_len(
+            src,
+            "plc stop job",
+            PLC_STOP_HEAD.len(),
+        )?;
+        src.advance(PLC_STOP_HEAD.len());
+        let service = decode_service(src)?;
+        Ok(Self { service })
+    }
+}
+
+/// Ack data of a PI service or plc stop job.
+#[derive(
+    Debug, Clone, Eq, PartialEq, Default,
+)]
+pub struct PlcControlAckData {
+    /// the state of a refused job: 0x02 already
+    /// in run, 0x07 already in stop
+    state: Option<u8>,
+}
+
+impl PlcControlAckData {
+    pub fn init(state: Option<u8>) -> Self {
+        Self { state }
+    }
+
+    pub fn state(&self) -> Option<u8> {
+        self.state
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        if self.state.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        if let Some(state) = self.state {
+            dst.put_u8(state);
+        }
+    }
+
+    /// `len`: the length of the parameter, the
+    /// function included
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        len: u16,
+    ) -> Result<Self> {
+        let state = if len >= 2
+            && src.has_remaining()
+        {
+            let state = src.get_u8();
+            src.advance(
+                (len as usize - 2).min(src.len()),
+            );
+            Some(state)
+        } else {
+            None
+        };
+        Ok(Self { state })
+    }
+}
+
+fn decode_service(
+    src: &mut BytesMut,
+) -> Result<Vec<u8>> {
+    check_len(src, "service name", 1)?;
+    let len = src.get_u8() as usize;
+    check_len(src, "service name", len)?;
+    Ok(src.split_to(len).to_vec())
+}
@@ -0,0 +1,65 @@
+use crate::error::*;
+
+/// subfunction of the security functions setting
+/// the session password
+pub const SET_PASSWORD: u8 = 0x01;
+/// subfunction of the security functions clearing
+/// the session password
+pub const CLEAR_PASSWORD: u8 = 0x02;
+
+/// the length of a password, shorter ones padded
+/// with spaces
+pub const PASSWORD_LEN: usize = 8;
+
+/// The password as sent by set password: padded
+/// with spaces to 8 bytes, each byte xored with
+/// 0x55 and, from the third, with the encoded
+/// byte two before.
+pub fn encode_password(
+    password: &str,
+) -> Result<[u8; PASSWORD_LEN]> {
+    if !password.is_ascii()
+        || password.len() > PASSWORD_LEN
+    {
+        return Err(Error::Error(format!(
+            "password should be at most {} \
+             ascii chars",
+            PASSWORD_LEN
+        )));
+    }
+    let mut dst = [b' '; PASSWORD_LEN];
+    dst[..password.len()]
+        .copy_from_slice(password.as_bytes());
+    for index in 0..PASSWORD_LEN {
+        dst[index] ^= 0x55;
+        if index >= 2 {
+            dst[index] ^= dst[index - 2];
+        }
+    }
+    Ok(dst)
+}
+
+/// the password of an encoded one, without the
+/// padding
+pub fn decode_password(
+    src: &[u8],
+) -> Result<String> {
+    if src.len() != PASSWORD_LEN {
+        return Err(Error::Error(format!(
+            "password should be {} bytes, but \
+             not {}",
+            PASSWORD_LEN,
+            src.len()
+        )));
+    }
+    let mut password = [0u8; PASSWORD_LEN];
+    for index in 0..PASSWORD_LEN {
+        password[index] = src[index] ^ 0x55;
+        if index >= 2 {
+            password[index] ^= src[index - 2];
+        }
+    }
+    Ok(String::from_utf8_lossy(&password)
+        .trim_end_matches(' ')
+        .to_string())
+}
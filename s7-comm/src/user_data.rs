@@ -0,0 +1,274 @@
+use crate::{
+    error::*, DataTransportSize, ReturnCode,
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use num_enum::{FromPrimitive, IntoPrimitive};
+
+/// 0x000112
+const PARAMETER_HEAD: [u8; 3] = [0x00, 0x01, 0x12];
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum Method {
+    Request = 0x11,
+    Response = 0x12,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// high nibble of the type and function group
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum UserDataType {
+    Push = 0x0,
+    Request = 0x4,
+    Response = 0x8,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// low nibble of the type and function group
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    IntoPrimitive,
+    FromPrimitive,
+    Eq,
+    PartialEq,
+)]
+#[repr(u8)]
+pub enum FunctionGroup {
+    ModeTransition = 0x1,
+    CyclicData = 0x2,
+    Block = 0x3,
+    Cpu = 0x4,
+    Security = 0x5,
+    ProgrammableBlock = 0x6,
+    Time = 0x7,
+    NcProgramming = 0xf,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// Fragment of a userdata, in the parameter of
+/// responses and of requests following a
+/// fragmented response.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DataUnit {
+    pub reference: u8,
+    /// the last fragment
+    pub last: bool,
+    pub error_code: u16,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UserDataParameter {
+    pub method: Method,
+    pub ty: UserDataType,
+    pub function_group: FunctionGroup,
+    pub subfunction: u8,
+    pub sequence_number: u8,
+    pub data_unit: Option<DataUnit>,
+}
+
+impl UserDataParameter {
+    pub fn bytes_len(&self) -> u16 {
+        if self.data_unit.is_some() {
+            12
+        } else {
+            8
+        }
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        dst.extend_from_slice(&PARAMETER_HEAD);
+        dst.put_u8(self.bytes_len() as u8 - 4);
+        dst.put_u8(self.method.into());
+        dst.put_u8(
+            u8::from(self.ty) << 4
+                | u8::from(self.function_group)
+                    & 0x0f,
+        );
+        dst.put_u8(self.subfunction);
+        dst.put_u8(self.sequence_number);
+        if let Some(data_unit) = self.data_unit {
+            dst.put_u8(data_unit.reference);
+            dst.put_u8(if data_unit.last {
+                0x00
+            } else {
+                0x01
+            });
+            dst.put_u16(data_unit.error_code);
+        }
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        len: u16,
+    ) -> Result<Self> {
+        if len < 8 {
+            return Err(Error::Error(format!(
+                "invalid userdata parameter \
+                 length: {}",
+                len
+            )));
+        }
+        check_len(
+            src,
+            "userdata parameter",
+            len as usize,
+        )?;
+        let mut src = src.split_to(len as usize);
+        if src[0..3] != PARAMETER_HEAD {
+            return Err(Error::Error(format!(
+                "not support userdata parameter \
+                 head: {:?}",
+                &src[0..3]
+            )));
+        }
+        src.advance(3);
+        let follow_len = src.get_u8();
+        let method = Method::from(src.get_u8());
+        let type_and_group = src.get_u8();
+        let subfunction = src.get_u8();
+        let sequence_number = src.get_u8();
+        let data_unit = if follow_len >= 8
+            && src.len() >= 4
+        {
+            Some(DataUnit {
+                reference: src.get_u8(),
+                last: src.get_u8() == 0x00,
+                error_code: src.get_u16(),
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            method,
+            ty: UserDataType::from(
+                type_and_group >> 4,
+            ),
+            function_group: FunctionGroup::from(
+                type_and_group & 0x0f,
+            ),
+            subfunction,
+            sequence_number,
+            data_unit,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UserDataItem {
+    pub return_code: ReturnCode,
+    pub transport_size_type: DataTransportSize,
+    pub data: Bytes,
+}
+
+impl UserDataItem {
+    pub fn init(
+        return_code: ReturnCode,
+        data: &[u8],
+    ) -> Self {
+        Self {
+            return_code,
+            transport_size_type:
+                DataTransportSize::OctetString,
+            data: Bytes::copy_from_slice(data),
+        }
+    }
+
+    /// the data of a request or response without
+    /// data
+    pub fn no_data() -> Self {
+        Self {
+            return_code: ReturnCode::Err,
+            transport_size_type:
+                DataTransportSize::Null,
+            data: Bytes::new(),
+        }
+    }
+
+    pub fn bytes_len(&self) -> u16 {
+        self.data.len() as u16 + 4
+    }
+
+    pub(crate) fn encode(
+        self,
+        dst: &mut BytesMut,
+    ) {
+        let length = self.length();
+        dst.put_u8(self.return_code.into());
+        dst.put_u8(
+            self.transport_size_type.into(),
+        );
+        dst.put_u16(length);
+        dst.extend_from_slice(&self.data);
+    }
+
+    /// the length field, in bits for the bit
+    /// oriented transport sizes
+    fn length(&self) -> u16 {
+        let len = self.data.len() as u16;
+        match self.transport_size_type {
+            DataTransportSize::NoBit => len << 3,
+            _ => len,
+        }
+    }
+
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        len: u16,
+    ) -> Result<Self> {
+        if len < 4 {
+            return Err(Error::Error(format!(
+                "invalid userdata data length: {}",
+                len
+            )));
+        }
+        check_len(
+            src,
+            "userdata data",
+            len as usize,
+        )?;
+        let mut src = src.split_to(len as usize);
+        let return_code =
+            ReturnCode::try_from(src.get_u8())?;
+        let transport_size_type =
+            DataTransportSize::from(src.get_u8());
+        let mut bytes_len = src.get_u16() as usize;
+        if transport_size_type
+            == DataTransportSize::NoBit
+        {
+            bytes_len >>= 3;
+        }
+        // tolerate a length field exceeding the
+        // data
+        let bytes_len = bytes_len.min(src.len());
+        Ok(Self {
+            return_code,
+            transport_size_type,
+            data: src.split_to(bytes_len).freeze(),
+        })
+    }
+}
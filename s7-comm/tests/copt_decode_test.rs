@@ -2,8 +2,12 @@ mod test_data;
 
 use crate::test_data::test_copt_data::{
     init_copt_connect_confirm_frame, init_copt_connect_confirm_frame_bytes,
-    init_copt_connect_request_frame, init_copt_connect_request_frame_bytes,
-    init_copt_dt_data_frame, init_copt_dt_data_frame_bytes,
+    init_copt_connect_confirm_with_options_frame,
+    init_copt_connect_confirm_with_options_frame_bytes, init_copt_connect_request_frame,
+    init_copt_connect_request_frame_bytes, init_copt_disconnect_confirm_frame,
+    init_copt_disconnect_confirm_frame_bytes, init_copt_disconnect_request_frame,
+    init_copt_disconnect_request_frame_bytes, init_copt_dt_data_frame,
+    init_copt_dt_data_frame_bytes, init_copt_error_frame, init_copt_error_frame_bytes,
 };
 use bytes::BytesMut;
 use copt::CoptDecoder;
@@ -12,7 +16,7 @@ use tokio_util::codec::Decoder;
 
 #[test]
 fn test_dt_data_decode() {
-    let mut decoder = CoptDecoder(S7CommDecoder);
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
     let mut src = BytesMut::new();
     src.extend_from_slice(init_copt_dt_data_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -27,7 +31,7 @@ fn test_dt_data_decode() {
 
 #[test]
 fn test_connect_request_decode() {
-    let mut decoder = CoptDecoder(S7CommDecoder);
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
     let mut src = BytesMut::new();
     src.extend_from_slice(init_copt_connect_request_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -42,7 +46,7 @@ fn test_connect_request_decode() {
 
 #[test]
 fn test_connect_confirm_decode() {
-    let mut decoder = CoptDecoder(S7CommDecoder);
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
     let mut src = BytesMut::new();
     src.extend_from_slice(init_copt_connect_confirm_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -54,3 +58,85 @@ fn test_connect_confirm_decode() {
         unreachable!()
     }
 }
+
+#[test]
+fn test_disconnect_request_decode() {
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
+    let mut src = BytesMut::new();
+    src.extend_from_slice(init_copt_disconnect_request_frame_bytes());
+    let rs = decoder.decode(&mut src);
+    assert!(rs.is_ok());
+    if let Ok(Some(frame)) = rs {
+        let dst_frame = init_copt_disconnect_request_frame();
+        assert_eq!(dst_frame, frame);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_disconnect_confirm_decode() {
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
+    let mut src = BytesMut::new();
+    src.extend_from_slice(init_copt_disconnect_confirm_frame_bytes());
+    let rs = decoder.decode(&mut src);
+    assert!(rs.is_ok());
+    if let Ok(Some(frame)) = rs {
+        let dst_frame = init_copt_disconnect_confirm_frame();
+        assert_eq!(dst_frame, frame);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_error_decode() {
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
+    let mut src = BytesMut::new();
+    src.extend_from_slice(init_copt_error_frame_bytes());
+    let rs = decoder.decode(&mut src);
+    assert!(rs.is_ok());
+    if let Ok(Some(frame)) = rs {
+        let dst_frame = init_copt_error_frame();
+        assert_eq!(dst_frame, frame);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_connect_confirm_with_options_decode() {
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
+    let mut src = BytesMut::new();
+    src.extend_from_slice(init_copt_connect_confirm_with_options_frame_bytes());
+    let rs = decoder.decode(&mut src);
+    assert!(rs.is_ok());
+    if let Ok(Some(frame)) = rs {
+        let dst_frame = init_copt_connect_confirm_with_options_frame();
+        assert_eq!(dst_frame, frame);
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_malformed_decode() {
+    let mut decoder = CoptDecoder::new(S7CommDecoder);
+
+    // a length indicator without the pdu type
+    let mut src = BytesMut::from([0x00, 0xf0, 0x80].as_ref());
+    assert!(decoder.decode(&mut src).is_err());
+
+    // a dt data without the tpdu number
+    let mut src = BytesMut::from([0x01, 0xf0, 0x80].as_ref());
+    assert!(decoder.decode(&mut src).is_err());
+
+    // a parameter of 255 bytes in a connect request of 10 bytes
+    let mut src = BytesMut::from(
+        [
+            0x0a, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc1, 0xff, 0x01, 0x00,
+        ]
+        .as_ref(),
+    );
+    assert!(decoder.decode(&mut src).is_err());
+}
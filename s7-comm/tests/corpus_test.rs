@@ -0,0 +1,22 @@
+use bytes::BytesMut;
+use copt::CoptDecoder;
+use s7_comm::S7CommDecoder;
+use std::{fs, path::Path};
+use tokio_util::codec::Decoder;
+use tpkt::TpktDecoder;
+
+/// the inputs of the corpus, the crashes found by the fuzz targets in
+/// `fuzz`, are refused by the decoders without a panic
+#[test]
+fn test_corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut count = 0;
+    for entry in fs::read_dir(corpus).unwrap() {
+        let data = fs::read(entry.unwrap().path()).unwrap();
+        let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
+        let mut src = BytesMut::from(data.as_slice());
+        while let Ok(Some(_)) = decoder.decode(&mut src) {}
+        count += 1;
+    }
+    assert!(count > 0);
+}
@@ -0,0 +1,90 @@
+use bytes::BytesMut;
+use s7_comm::{DateAndTime, Error, Frame, S7CommEncoder};
+use tokio_util::codec::Encoder;
+
+#[test]
+fn year_wraparound() {
+    for (year, bcd) in [
+        (1990, 0x90),
+        (1999, 0x99),
+        (2000, 0x00),
+        (2024, 0x24),
+        (2089, 0x89),
+    ] {
+        let time = DateAndTime::new(year, 1, 1, 0, 0, 0, 0).unwrap();
+        let bytes = time.encode().unwrap();
+        assert_eq!(bytes[0], bcd);
+        assert_eq!(DateAndTime::decode(&bytes).unwrap(), time);
+    }
+    assert!(DateAndTime::new(1989, 12, 31, 23, 59, 59, 999).is_err());
+    assert!(DateAndTime::new(2090, 1, 1, 0, 0, 0, 0).is_err());
+}
+
+#[test]
+fn milliseconds_and_weekday() {
+    // 2023-12-31 23:59:59.999, a sunday
+    let time = DateAndTime::new(2023, 12, 31, 23, 59, 59, 999).unwrap();
+    assert_eq!(time.weekday, 1);
+    let bytes = time.encode().unwrap();
+    assert_eq!(bytes, [0x23, 0x12, 0x31, 0x23, 0x59, 0x59, 0x99, 0x91]);
+    assert_eq!(DateAndTime::decode(&bytes).unwrap(), time);
+
+    // 1990-01-01 00:00:00.007, a monday
+    let bytes = [0x90, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x72];
+    let time = DateAndTime::decode(&bytes).unwrap();
+    assert_eq!(time, DateAndTime::new(1990, 1, 1, 0, 0, 0, 7).unwrap());
+    assert_eq!(time.millisecond, 7);
+    assert_eq!(time.weekday, 2);
+
+    // 2089-12-31, a saturday
+    let time = DateAndTime::new(2089, 12, 31, 12, 0, 0, 120).unwrap();
+    assert_eq!(time.weekday, 7);
+    assert_eq!(time.encode().unwrap()[6..], [0x12, 0x07]);
+}
+
+#[test]
+fn invalid() {
+    // not bcd
+    assert!(DateAndTime::decode(&[0x9a, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02]).is_err());
+    // 2023-02-29
+    assert!(DateAndTime::decode(&[0x23, 0x02, 0x29, 0x00, 0x00, 0x00, 0x00, 0x04]).is_err());
+    // weekday 0
+    assert!(DateAndTime::decode(&[0x24, 0x02, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00]).is_err());
+    assert!(matches!(
+        DateAndTime::decode(&[0x24, 0x02, 0x29, 0x00, 0x00, 0x00, 0x00]),
+        Err(Error::NotEnough {
+            what: "DATE_AND_TIME",
+            need: 8,
+            len: 7
+        })
+    ));
+    // 2024-02-29, a thursday
+    assert!(DateAndTime::decode(&[0x24, 0x02, 0x29, 0x00, 0x00, 0x00, 0x00, 0x05]).is_ok());
+}
+
+#[test]
+fn encode_set_clock() {
+    let bytes: [u8; 32] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0x00, 0x0e, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x47, 0x02, 0x00, 0xff, 0x09, 0x00, 0x0a, 0x00, 0x20, 0x24, 0x05, 0x17, 0x08, 0x30, 0x00,
+        0x50, 0x06,
+    ];
+    let time = DateAndTime::new(2024, 5, 17, 8, 30, 0, 500).unwrap();
+    let frame = Frame::user_data(1).set_clock(&time).unwrap().build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+    assert_eq!(DateAndTime::decode_clock(&bytes[22..]).unwrap(), time);
+}
+
+#[test]
+fn encode_read_clock() {
+    let bytes: [u8; 22] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x02, 0x00, 0x08, 0x00, 0x04, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x47, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let frame = Frame::user_data(2).read_clock().build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+}
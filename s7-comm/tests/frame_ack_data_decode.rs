@@ -1,7 +1,7 @@
 use bytes::BytesMut;
 use s7_comm::{
-    AckData, DataItemVal, DataItemWriteResponse, Frame, HearderAckData, ReadVarAckData, ReturnCode,
-    S7CommDecoder, SetupCommunication, WriteVarAckData,
+    AckData, DataItemVal, DataItemWriteResponse, Error, Frame, HearderAckData, ReadVarAckData,
+    ReturnCode, S7CommDecoder, SetupCommunication, WriteVarAckData,
 };
 use tokio_util::codec::Decoder;
 
@@ -87,3 +87,56 @@ fn read_var_decode() {
         }
     }
 }
+
+#[test]
+fn malformed_decode() {
+    let mut decoder = S7CommDecoder;
+
+    // an ack data without the function
+    let bytes = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(Error::NotEnough {
+            what: "ack data function",
+            need: 1,
+            len: 0
+        })
+    ));
+
+    // a read var job without the count of the items
+    let bytes = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x04,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(Error::NotEnough {
+            what: "read var job",
+            need: 1,
+            len: 0
+        })
+    ));
+
+    // the data length ends within the item, which is not read from the
+    // bytes after the frame
+    let bytes: [u8; 22] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x04, 0x01, 0xff,
+        0x04, 0x00, 0x20, 0x00, 0x00, 0x00, 0x79,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    assert!(decoder.decode(&mut src).is_err());
+    assert_eq!(src.as_ref(), &bytes[16..]);
+
+    // the largest lengths wait for more data
+    let bytes = [0x32, 0x01, 0x00, 0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff];
+    let mut src = BytesMut::from(bytes.as_ref());
+    assert!(matches!(decoder.decode(&mut src), Ok(None)));
+
+    // not s7comm
+    let bytes = [0x72, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+    let mut src = BytesMut::from(bytes.as_ref());
+    assert!(decoder.decode(&mut src).is_err());
+}
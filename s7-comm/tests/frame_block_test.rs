@@ -0,0 +1,206 @@
+use bytes::BytesMut;
+use s7_comm::{
+    AckData, BlockFile, BlockHeader, BlockInfo, BlockTimestamp, BlockType, Error, FileSystem,
+    Frame, Job, S7CommDecoder, S7CommEncoder, mc7_code,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn encode_start_upload() {
+    // start upload of DB1
+    let bytes = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x12, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x09, 0x5f, 0x30, 0x41, 0x30, 0x30, 0x30, 0x30, 0x31, 0x41,
+    ];
+    let file = BlockFile::init(BlockType::DB, 1, FileSystem::Active);
+    let frame = Frame::job_upload(1).start_upload(file).build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let Some(Frame::Job {
+        job: Job::StartUpload(job),
+        ..
+    }) = S7CommDecoder.decode(&mut dst).unwrap()
+    else {
+        panic!("should decode a start upload job")
+    };
+    assert_eq!(job.file(), file);
+}
+
+#[test]
+fn decode_upload_ack() {
+    // the last part of the block, 4 bytes
+    let bytes = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x08, 0x00, 0x00, 0x1e, 0x00, 0x00,
+        0x04, 0x00, 0xfb, 0x70, 0x70, 0x01, 0x02,
+    ];
+    let mut src = BytesMut::from(bytes.as_slice());
+    let Some(Frame::AckData {
+        ack_data: AckData::Upload(data),
+        ..
+    }) = S7CommDecoder.decode(&mut src).unwrap()
+    else {
+        panic!("should decode an upload ack data")
+    };
+    assert!(!data.more());
+    assert_eq!(data.data(), [0x70, 0x70, 0x01, 0x02]);
+    assert!(src.is_empty());
+}
+
+#[test]
+fn block_info() {
+    let info = BlockInfo {
+        block_type: BlockType::FB,
+        number: 10,
+        flags: 0x01,
+        language: 0x01,
+        sub_block_type: 0x0e,
+        load_size: 232,
+        security: 0,
+        // 2008-01-01 01:02:03.004
+        code_time: BlockTimestamp {
+            days: 8766,
+            milliseconds: 3_723_004,
+        },
+        interface_time: BlockTimestamp::default(),
+        sbb_len: 20,
+        add_len: 0,
+        local_data_len: 2,
+        mc7_size: 40,
+        author: "ME".to_string(),
+        family: "TOOLS".to_string(),
+        name: "MOTOR".to_string(),
+        version: 0x12,
+        checksum: 0xabcd,
+    };
+    let data = info.clone().encode();
+    assert_eq!(data.len(), BlockInfo::BYTES_LEN);
+    assert_eq!(BlockInfo::decode(&data).unwrap(), info);
+
+    let time = info.code_time.date_time().unwrap();
+    assert_eq!(
+        (time.year, time.month, time.day, time.weekday),
+        (2008, 1, 1, 3)
+    );
+    assert_eq!(
+        (time.hour, time.minute, time.second, time.millisecond),
+        (1, 2, 3, 4)
+    );
+}
+
+#[test]
+fn block_file_and_code() {
+    let file = BlockFile::decode_name(b"0800001P").unwrap();
+    assert_eq!(file.block_type, BlockType::OB);
+    assert_eq!(file.number, 1);
+    assert_eq!(file.file_system, FileSystem::Passive);
+    assert!(BlockFile::decode_name(b"0A0000").is_err());
+
+    let mut block = vec![0; 36];
+    block[35] = 2;
+    block.extend_from_slice(&[0x70, 0x0b, 0x65, 0x00]);
+    assert_eq!(mc7_code(&block), Some([0x70, 0x0b].as_slice()));
+    assert_eq!(mc7_code(&block[..35]), None);
+    assert!(matches!(
+        BlockHeader::decode(&block[..35]),
+        Err(Error::NotEnough {
+            what: "block header",
+            need: 36,
+            len: 35
+        })
+    ));
+    assert_eq!(mc7_code(&block[..37]), None);
+}
+
+#[test]
+fn encode_request_download() {
+    // request download of DB1, 100 bytes with 40 bytes of MC7 code
+    let bytes = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x20, 0x00, 0x00, 0x1a, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x09, 0x5f, 0x30, 0x41, 0x30, 0x30, 0x30, 0x30, 0x31, 0x50, 0x0d, 0x31,
+        0x30, 0x30, 0x30, 0x31, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x34, 0x30,
+    ];
+    let file = BlockFile::init(BlockType::DB, 1, FileSystem::Passive);
+    let frame = Frame::job_download(1)
+        .request_download(file, 100, 40)
+        .build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let Some(Frame::Job {
+        job: Job::RequestDownload(job),
+        ..
+    }) = S7CommDecoder.decode(&mut dst).unwrap()
+    else {
+        panic!("should decode a request download job")
+    };
+    assert_eq!(job.file(), file);
+    assert_eq!((job.load_len(), job.mc7_len()), (100, 40));
+}
+
+#[test]
+fn decode_download_block() {
+    // the plc requesting a part of DB1
+    let bytes = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x12, 0x00, 0x00, 0x1b, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x09, 0x5f, 0x30, 0x41, 0x30, 0x30, 0x30, 0x30, 0x31, 0x50,
+    ];
+    let mut src = BytesMut::from(bytes.as_slice());
+    let Some(Frame::Job {
+        header,
+        job: Job::DownloadBlock(job),
+    }) = S7CommDecoder.decode(&mut src).unwrap()
+    else {
+        panic!("should decode a download block job")
+    };
+    assert_eq!(header.pdu_ref, 5);
+    assert_eq!(
+        job.file(),
+        BlockFile::init(BlockType::DB, 1, FileSystem::Passive)
+    );
+    assert!(src.is_empty());
+}
+
+#[test]
+fn encode_delete_block() {
+    let frame = Frame::job_pi_service(1)
+        .delete_block(BlockType::FC, 12)
+        .build();
+    let Frame::Job {
+        job: Job::PiService(job),
+        ..
+    } = frame
+    else {
+        panic!("should build a pi service job")
+    };
+    assert_eq!(job.service(), b"_DELE");
+    assert_eq!(job.parameter(), b"\x01\x000C00012B");
+    let file = BlockFile::decode_pi_parameter(job.parameter()).unwrap();
+    assert_eq!(file, BlockFile::init(BlockType::FC, 12, FileSystem::Both));
+}
+
+#[test]
+fn block_header() {
+    let header = BlockHeader {
+        flags: 0x01,
+        language: 0x05,
+        sub_block_type: 0x0a,
+        number: 3,
+        load_size: 100,
+        security: 0,
+        code_time: BlockTimestamp::default(),
+        interface_time: BlockTimestamp::default(),
+        sbb_len: 0,
+        add_len: 0,
+        local_data_len: 0,
+        mc7_size: 40,
+    };
+    let bytes = header.encode();
+    assert_eq!(&bytes[..2], b"pp");
+    assert_eq!(BlockHeader::decode(&bytes).unwrap(), header);
+    assert_eq!(header.block_type(), BlockType::DB);
+    assert_eq!(BlockType::DB.sub_block_type(), Some(0x0a));
+    assert!(BlockHeader::decode(&bytes[..35]).is_err());
+}
@@ -0,0 +1,68 @@
+use bytes::BytesMut;
+use s7_comm::{AckData, Error, Frame, Job, S7CommDecoder, S7CommEncoder};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn encode_hot_start() {
+    let bytes = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x14, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0xfd, 0x00, 0x00, 0x09, 0x50, 0x5f, 0x50, 0x52, 0x4f, 0x47, 0x52, 0x41, 0x4d,
+    ];
+    let frame = Frame::job_pi_service(1).service("P_PROGRAM").build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let Some(Frame::Job {
+        job: Job::PiService(job),
+        ..
+    }) = S7CommDecoder.decode(&mut dst).unwrap()
+    else {
+        panic!("should decode a pi service job")
+    };
+    assert_eq!(job.service(), b"P_PROGRAM");
+    assert!(job.parameter().is_empty());
+
+    // the service name cut short
+    let mut src = BytesMut::from(&bytes[..29]);
+    src[7] = 0x13;
+    assert!(matches!(
+        S7CommDecoder.decode(&mut src),
+        Err(Error::NotEnough {
+            what: "service name",
+            need: 9,
+            len: 8
+        })
+    ));
+}
+
+#[test]
+fn encode_stop() {
+    let bytes = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x10, 0x00, 0x00, 0x29, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x09, 0x50, 0x5f, 0x50, 0x52, 0x4f, 0x47, 0x52, 0x41, 0x4d,
+    ];
+    let frame = Frame::job_plc_stop(2).service("P_PROGRAM").build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+}
+
+#[test]
+fn decode_refused_stop() {
+    // already in stop
+    let bytes = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x84, 0x04, 0x29, 0x07,
+    ];
+    let mut src = BytesMut::from(bytes.as_slice());
+    let Some(Frame::AckData {
+        header,
+        ack_data: AckData::PlcStop(data),
+    }) = S7CommDecoder.decode(&mut src).unwrap()
+    else {
+        panic!("should decode a plc stop ack data")
+    };
+    assert_eq!(header.error_class(), 0x84);
+    assert_eq!(data.state(), Some(0x07));
+    assert!(src.is_empty());
+}
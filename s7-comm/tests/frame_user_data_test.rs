@@ -0,0 +1,255 @@
+use bytes::BytesMut;
+use s7_comm::{
+    AlarmAck, AlarmMessage, AlarmNotification, AlarmType, Area, CyclicData, CyclicInterval,
+    CyclicRequest, DataItemVal, DataUnit, DateAndTime, DbNumber, EVENTS_ALARM, EVENTS_SYSTEM,
+    Frame, FunctionGroup, Header, ItemRequest, MessageService, Method, ReturnCode, S7CommDecoder,
+    S7CommEncoder, TimeBase, TransportSize, UserDataItem, UserDataType, decode_alarm_query,
+    decode_password, decode_unsubscribe, encode_password,
+};
+use std::time::Duration;
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn encode_request() {
+    // read szl 0x0011, index 0
+    let bytes: [u8; 26] = [
+        0x32, 0x07, 0x00, 0x00, 0x05, 0x00, 0x00, 0x08, 0x00, 0x08, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x44, 0x01, 0x00, 0xff, 0x09, 0x00, 0x04, 0x00, 0x11, 0x00, 0x00,
+    ];
+    let frame = Frame::user_data(0x0500)
+        .request(FunctionGroup::Cpu, 0x01)
+        .data(UserDataItem::init(
+            ReturnCode::Success,
+            &[0x00, 0x11, 0x00, 0x00],
+        ))
+        .build();
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref())
+}
+
+#[test]
+fn decode_response() {
+    let bytes: [u8; 34] = [
+        0x32, 0x07, 0x00, 0x00, 0x05, 0x00, 0x00, 0x0c, 0x00, 0x0c, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x84, 0x01, 0x01, 0x03, 0x01, 0x00, 0x00, 0xff, 0x09, 0x00, 0x08, 0x00, 0x11, 0x00, 0x00,
+        0x00, 0x1c, 0x00, 0x01,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let Ok(Some(frame)) = S7CommDecoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    let Frame::UserData {
+        header,
+        parameter,
+        data,
+    } = &frame
+    else {
+        unreachable!()
+    };
+    assert_eq!(header, &Header::init(0x0500, 12, 12));
+    assert_eq!(parameter.method, Method::Response);
+    assert_eq!(parameter.ty, UserDataType::Response);
+    assert_eq!(parameter.function_group, FunctionGroup::Cpu);
+    assert_eq!(parameter.subfunction, 0x01);
+    assert_eq!(parameter.sequence_number, 0x01);
+    assert_eq!(
+        parameter.data_unit,
+        Some(DataUnit {
+            reference: 0x03,
+            last: false,
+            error_code: 0,
+        })
+    );
+    let data = data.as_ref().unwrap();
+    assert_eq!(data.return_code, ReturnCode::Success);
+    assert_eq!(
+        data.data,
+        [0x00, 0x11, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x01][..]
+    );
+
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref())
+}
+
+#[test]
+fn encode_set_password() {
+    let password = [0x64, 0x67, 0x02, 0x06, 0x77, 0x73, 0x02, 0x06];
+    assert_eq!(encode_password("1234").unwrap(), password);
+    assert_eq!(decode_password(&password).unwrap(), "1234");
+    assert!(encode_password("123456789").is_err());
+    assert!(encode_password("pässwort").is_err());
+
+    let bytes: [u8; 30] = [
+        0x32, 0x07, 0x00, 0x00, 0x01, 0x00, 0x00, 0x08, 0x00, 0x0c, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x45, 0x01, 0x00, 0xff, 0x09, 0x00, 0x08, 0x64, 0x67, 0x02, 0x06, 0x77, 0x73, 0x02, 0x06,
+    ];
+    let frame = Frame::user_data(0x0100)
+        .set_password("1234")
+        .unwrap()
+        .build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref())
+}
+
+#[test]
+fn encode_cyclic_read() {
+    // DB1.DBB0 BYTE 4 every second
+    let bytes: [u8; 38] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0x00, 0x14, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x42, 0x01, 0x00, 0xff, 0x09, 0x00, 0x10, 0x00, 0x01, 0x00, 0x0a, 0x12, 0x0a, 0x10, 0x02,
+        0x00, 0x04, 0x00, 0x01, 0x84, 0x00, 0x00, 0x00,
+    ];
+    let item = || {
+        ItemRequest::new(
+            TransportSize::NoBit,
+            DbNumber::DbNumber(1),
+            Area::DataBlocks,
+            0,
+            0,
+            4,
+        )
+    };
+    let interval = CyclicInterval::from_duration(Duration::from_secs(1));
+    assert_eq!(
+        interval,
+        CyclicInterval {
+            time_base: TimeBase::Millis100,
+            factor: 10,
+        }
+    );
+    let frame = Frame::user_data(0x0001)
+        .cyclic_read(interval, vec![item()])
+        .build();
+    let mut dst = BytesMut::new();
+    assert!(S7CommEncoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+    assert_eq!(
+        CyclicRequest::decode(&bytes[22..]).unwrap(),
+        CyclicRequest::init(interval, vec![item()])
+    );
+
+    for (millis, time_base, factor) in [
+        (50, TimeBase::Millis100, 1),
+        (500, TimeBase::Millis100, 5),
+        (30_000, TimeBase::Second, 30),
+        (3_600_000, TimeBase::Seconds10, 255),
+    ] {
+        let interval = CyclicInterval::from_duration(Duration::from_millis(millis));
+        assert_eq!(interval, CyclicInterval { time_base, factor });
+    }
+
+    let frame = Frame::user_data(0x0002).cyclic_unsubscribe(0x07).build();
+    let Frame::UserData { data, .. } = frame else {
+        unreachable!()
+    };
+    assert_eq!(decode_unsubscribe(&data.unwrap().data).unwrap(), 0x07);
+}
+
+#[test]
+fn decode_cyclic_data() {
+    let data = CyclicData::init(vec![
+        DataItemVal::init_with_bytes(ReturnCode::Success, &[0x01, 0x02, 0x03]),
+        DataItemVal::init_with_bit(ReturnCode::Success, true),
+        DataItemVal::init_with_bytes(ReturnCode::Success, &[0x04]),
+    ]);
+    let bytes = data.clone().encode();
+    assert_eq!(
+        bytes,
+        [
+            0x00, 0x03, 0xff, 0x04, 0x00, 0x18, 0x01, 0x02, 0x03, 0x00, 0xff, 0x03, 0x00, 0x01,
+            0x01, 0x00, 0xff, 0x04, 0x00, 0x08, 0x04,
+        ]
+    );
+    assert_eq!(CyclicData::decode(&bytes).unwrap(), data);
+    assert!(CyclicData::decode(&bytes[..8]).is_err());
+}
+
+#[test]
+fn encode_alarms() {
+    let service = MessageService {
+        events: EVENTS_SYSTEM | EVENTS_ALARM,
+        user: "hmi".to_string(),
+        alarms: Some((AlarmType::AlarmS, true)),
+    };
+    let bytes = service.encode().unwrap();
+    assert_eq!(
+        bytes,
+        [
+            0x82, 0x00, b'h', b'm', b'i', b' ', b' ', b' ', b' ', b' ', 0x09, 0x00
+        ]
+    );
+    assert_eq!(MessageService::decode(&bytes).unwrap(), service);
+    let service = MessageService {
+        user: "operator1".to_string(),
+        ..service
+    };
+    assert!(service.encode().is_err());
+
+    let notification = AlarmNotification {
+        timestamp: DateAndTime::new(2026, 10, 18, 12, 30, 45, 123).unwrap(),
+        messages: vec![AlarmMessage {
+            event_id: 0x6000_0001,
+            state: 0x01,
+            ack_state_going: 0x00,
+            ack_state_coming: 0x00,
+            values: vec![DataItemVal::init_with_bytes(
+                ReturnCode::Success,
+                &[0x00, 0x2a],
+            )],
+        }],
+    };
+    let bytes = notification.clone().encode().unwrap();
+    assert_eq!(
+        bytes[8..],
+        [
+            0x00, 0x01, 0x12, 0x0f, 0x09, 0x01, 0x60, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0xff,
+            0x04, 0x00, 0x10, 0x00, 0x2a,
+        ]
+    );
+    assert_eq!(AlarmNotification::decode(&bytes).unwrap(), notification);
+    assert!(AlarmNotification::decode(&bytes[..14]).is_err());
+
+    let acks = [AlarmAck {
+        event_id: 0x6000_0001,
+        ack_state_going: 0x00,
+        ack_state_coming: 0x01,
+    }];
+    let frame = Frame::user_data(0x0003).alarm_ack(&acks).build();
+    let Frame::UserData {
+        parameter, data, ..
+    } = frame
+    else {
+        unreachable!()
+    };
+    assert_eq!(parameter.function_group, FunctionGroup::Cpu);
+    let data = data.unwrap().data;
+    assert_eq!(
+        data,
+        [
+            0x09, 0x01, 0x12, 0x08, 0x09, 0x01, 0x60, 0x00, 0x00, 0x01, 0x00, 0x01
+        ][..]
+    );
+    assert_eq!(AlarmAck::decode_list(&data).unwrap(), acks);
+    let response = AlarmAck::encode_response(&[ReturnCode::Success]);
+    assert_eq!(response, [0x09, 0x01, 0xff]);
+    assert_eq!(
+        AlarmAck::decode_response(&response).unwrap(),
+        [ReturnCode::Success]
+    );
+
+    let frame = Frame::user_data(0x0004)
+        .alarm_query(AlarmType::Alarm8)
+        .build();
+    let Frame::UserData { data, .. } = frame else {
+        unreachable!()
+    };
+    assert_eq!(
+        decode_alarm_query(&data.unwrap().data).unwrap(),
+        AlarmType::Alarm8
+    );
+}
@@ -0,0 +1,323 @@
+use bytes::BytesMut;
+use copt::{
+    CoptDecoder, CoptEncoder, CoptFrame, DisconnectReason, DisconnectRequest, Parameter, PduType,
+    RejectCause, TpduSize,
+};
+use proptest::prelude::*;
+use s7_comm::{
+    AckData, Area, DataItemVal, DataItemWriteResponse, DataTransportSize, DbNumber, Frame,
+    HearderAckData, ItemRequest, ReadVarAckData, ReturnCode, S7CommDecoder, S7CommEncoder,
+    SetupCommunication, TransportSize, WriteVarAckData,
+};
+use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktDecoder, TpktEncoder, TpktFrame};
+
+/// transport size, db number, area, byte address, bit address and length
+type ItemParams = (u8, u16, u8, u16, u8, u16);
+
+/// the frames are not `Clone`, so each one is built twice from the same
+/// parameters: to be encoded and to be compared with the decoded one
+#[derive(Debug, Clone)]
+enum FrameParams {
+    ReadVarJob(u16, Vec<ItemParams>),
+    WriteVarJob(u16, Vec<(ItemParams, DataItemVal)>),
+    SetupJob(u16, u16, u16, u16),
+    ReadVarAck(u16, u8, u8, Vec<DataItemVal>),
+    WriteVarAck(u16, Vec<ReturnCode>),
+    SetupAck(u16, u16, u16, u16),
+}
+
+#[derive(Debug, Clone)]
+enum CoptParams {
+    DtData(u8, FrameParams),
+    ConnectRequest(ConnectParams),
+    ConnectConfirm(ConnectParams),
+    DisconnectRequest([u8; 2], [u8; 2], u8, Vec<u8>),
+    DisconnectConfirm([u8; 2], [u8; 2]),
+    Error([u8; 2], u8, Vec<u8>),
+}
+
+/// destination and source references, class, extended formats, no explicit
+/// flow control and parameters
+type ConnectParams = ([u8; 2], [u8; 2], u8, bool, bool, Vec<Parameter>);
+
+fn item_request((size, db_number, area, byte_addr, bit_addr, length): &ItemParams) -> ItemRequest {
+    ItemRequest::new(
+        TransportSize::from(*size),
+        DbNumber::from(*db_number),
+        Area::from(*area),
+        *byte_addr,
+        *bit_addr,
+        *length,
+    )
+}
+
+impl FrameParams {
+    fn build(&self) -> Frame {
+        match self {
+            FrameParams::ReadVarJob(pdu_ref, items) => items
+                .iter()
+                .fold(Frame::job_read_var(*pdu_ref), |builder, item| {
+                    builder.add_item(item_request(item))
+                })
+                .build(),
+            FrameParams::WriteVarJob(pdu_ref, items) => items
+                .iter()
+                .fold(Frame::job_write_var(*pdu_ref), |builder, (item, data)| {
+                    builder.add_item((item_request(item), data.clone()))
+                })
+                .build(),
+            FrameParams::SetupJob(pdu_ref, calling, called, pdu_length) => {
+                Frame::job_setup(*pdu_ref)
+                    .max_amq_calling(*calling)
+                    .max_amq_called(*called)
+                    .pdu_length(*pdu_length)
+                    .build()
+            },
+            FrameParams::ReadVarAck(pdu_ref, error_class, error_code, items) => {
+                let ack_data = items
+                    .iter()
+                    .fold(ReadVarAckData::default(), |ack_data, item| {
+                        ack_data.add_response(item.clone())
+                    });
+                Frame::AckData {
+                    header: HearderAckData::init(
+                        *pdu_ref,
+                        2,
+                        ack_data.bytes_len_data(),
+                        *error_class,
+                        *error_code,
+                    ),
+                    ack_data: AckData::ReadVar(ack_data),
+                }
+            },
+            FrameParams::WriteVarAck(pdu_ref, return_codes) => {
+                let ack_data = return_codes.iter().fold(
+                    WriteVarAckData::default(),
+                    |ack_data, return_code| {
+                        ack_data.add_response(DataItemWriteResponse::init(return_code.clone()))
+                    },
+                );
+                Frame::AckData {
+                    header: HearderAckData::init(*pdu_ref, 2, return_codes.len() as u16, 0, 0),
+                    ack_data: AckData::WriteVar(ack_data),
+                }
+            },
+            FrameParams::SetupAck(pdu_ref, calling, called, pdu_length) => Frame::AckData {
+                header: HearderAckData::init(*pdu_ref, 8, 0, 0, 0),
+                ack_data: AckData::SetupCommunication(SetupCommunication::init(
+                    *calling,
+                    *called,
+                    *pdu_length,
+                )),
+            },
+        }
+    }
+}
+
+fn connect(params: &ConnectParams) -> copt::builder::ConnectBuilder<Frame> {
+    let (destination_ref, source_ref, class, extended_formats, no_flow_control, parameters) =
+        params;
+    parameters.iter().fold(
+        CoptFrame::builder_of_connect()
+            .destination_ref(*destination_ref)
+            .source_ref(*source_ref)
+            .class_and_others(*class, *extended_formats, *no_flow_control),
+        |builder, parameter| builder.push_parameter(parameter.clone()),
+    )
+}
+
+impl CoptParams {
+    fn build(&self) -> CoptFrame<Frame> {
+        match self {
+            // the last tpdu of its data unit, a former one is kept by the
+            // decoder until the last one
+            CoptParams::DtData(number, frame) => {
+                CoptFrame::builder_of_dt_data(frame.build()).build(*number, true)
+            },
+            CoptParams::ConnectRequest(params) => connect(params).build_to_request(),
+            CoptParams::ConnectConfirm(params) => connect(params).build_to_confirm(),
+            CoptParams::DisconnectRequest(destination_ref, source_ref, reason, info) => CoptFrame {
+                pdu_type: PduType::DisconnectRequest(DisconnectRequest {
+                    destination_ref: *destination_ref,
+                    source_ref: *source_ref,
+                    reason: DisconnectReason::from(*reason),
+                    additional_info: info.clone(),
+                }),
+            },
+            CoptParams::DisconnectConfirm(destination_ref, source_ref) => {
+                CoptFrame::disconnect_confirm(*destination_ref, *source_ref)
+            },
+            CoptParams::Error(destination_ref, cause, invalid_tpdu) => CoptFrame::error(
+                *destination_ref,
+                RejectCause::from(*cause),
+                invalid_tpdu.clone(),
+            ),
+        }
+    }
+}
+
+fn return_code() -> impl Strategy<Value = ReturnCode> {
+    prop::sample::select(vec![0u8, 1, 3, 5, 6, 7, 0x0a, 0xff])
+        .prop_map(|x| ReturnCode::try_from(x).unwrap())
+}
+
+fn item_params() -> impl Strategy<Value = ItemParams> {
+    (
+        any::<u8>(),
+        any::<u16>(),
+        any::<u8>(),
+        any::<u16>(),
+        0u8..8,
+        any::<u16>(),
+    )
+}
+
+/// the length of the data is in bits for the transport size of bytes, and in
+/// bytes otherwise
+fn data_item_val() -> impl Strategy<Value = DataItemVal> {
+    (
+        return_code(),
+        any::<u8>(),
+        prop::collection::vec(any::<u8>(), 0..64),
+    )
+        .prop_map(|(return_code, size, data)| {
+            let transport_size_type = DataTransportSize::from(size);
+            let length = match transport_size_type {
+                DataTransportSize::NoBit => (data.len() as u16) << 3,
+                _ => data.len() as u16,
+            };
+            DataItemVal {
+                return_code,
+                transport_size_type,
+                length,
+                data: data.into(),
+            }
+        })
+}
+
+fn frame_params() -> impl Strategy<Value = FrameParams> {
+    prop_oneof![
+        (any::<u16>(), prop::collection::vec(item_params(), 0..16))
+            .prop_map(|(pdu_ref, items)| FrameParams::ReadVarJob(pdu_ref, items)),
+        (
+            any::<u16>(),
+            prop::collection::vec((item_params(), data_item_val()), 0..8)
+        )
+            .prop_map(|(pdu_ref, items)| FrameParams::WriteVarJob(pdu_ref, items)),
+        any::<(u16, u16, u16, u16)>().prop_map(|(pdu_ref, calling, called, pdu_length)| {
+            FrameParams::SetupJob(pdu_ref, calling, called, pdu_length)
+        }),
+        (
+            any::<u16>(),
+            any::<u8>(),
+            any::<u8>(),
+            prop::collection::vec(data_item_val(), 0..8)
+        )
+            .prop_map(|(pdu_ref, error_class, error_code, items)| {
+                FrameParams::ReadVarAck(pdu_ref, error_class, error_code, items)
+            }),
+        (any::<u16>(), prop::collection::vec(return_code(), 0..16))
+            .prop_map(|(pdu_ref, return_codes)| FrameParams::WriteVarAck(pdu_ref, return_codes)),
+        any::<(u16, u16, u16, u16)>().prop_map(|(pdu_ref, calling, called, pdu_length)| {
+            FrameParams::SetupAck(pdu_ref, calling, called, pdu_length)
+        }),
+    ]
+}
+
+fn tsap() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..8)
+}
+
+fn parameter() -> impl Strategy<Value = Parameter> {
+    prop_oneof![
+        prop::sample::select(vec![
+            TpduSize::L8192,
+            TpduSize::L4096,
+            TpduSize::L2048,
+            TpduSize::L1024,
+            TpduSize::L512,
+            TpduSize::L256,
+            TpduSize::L128,
+        ])
+        .prop_map(Parameter::TpduSize),
+        tsap().prop_map(Parameter::SrcTsap),
+        tsap().prop_map(Parameter::DstTsap),
+        any::<u16>().prop_map(Parameter::Checksum),
+        any::<u8>().prop_map(Parameter::Version),
+        any::<u8>().prop_map(Parameter::AdditionalOptions),
+        prop::collection::vec(any::<u8>(), 0..4).prop_map(Parameter::AlternativeClasses),
+        (
+            any::<u8>().prop_filter("a known parameter", |code| {
+                !matches!(code, 0xc0..=0xc4 | 0xc6 | 0xc7)
+            }),
+            tsap()
+        )
+            .prop_map(|(code, data)| Parameter::Unknown { code, data }),
+    ]
+}
+
+fn connect_params() -> impl Strategy<Value = ConnectParams> {
+    (
+        any::<[u8; 2]>(),
+        any::<[u8; 2]>(),
+        0u8..16,
+        any::<bool>(),
+        any::<bool>(),
+        prop::collection::vec(parameter(), 0..4),
+    )
+}
+
+fn copt_params() -> impl Strategy<Value = CoptParams> {
+    prop_oneof![
+        (0u8..0x80, frame_params()).prop_map(|(number, frame)| CoptParams::DtData(number, frame)),
+        connect_params().prop_map(CoptParams::ConnectRequest),
+        connect_params().prop_map(CoptParams::ConnectConfirm),
+        (any::<[u8; 2]>(), any::<[u8; 2]>(), any::<u8>(), tsap()).prop_map(
+            |(destination_ref, source_ref, reason, info)| {
+                CoptParams::DisconnectRequest(destination_ref, source_ref, reason, info)
+            }
+        ),
+        (any::<[u8; 2]>(), any::<[u8; 2]>()).prop_map(|(destination_ref, source_ref)| {
+            CoptParams::DisconnectConfirm(destination_ref, source_ref)
+        }),
+        (any::<[u8; 2]>(), any::<u8>(), tsap()).prop_map(
+            |(destination_ref, cause, invalid_tpdu)| {
+                CoptParams::Error(destination_ref, cause, invalid_tpdu)
+            }
+        ),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn frame_round_trip(params in frame_params()) {
+        let mut dst = BytesMut::new();
+        S7CommEncoder.encode(params.build(), &mut dst).unwrap();
+        let frame = S7CommDecoder.decode(&mut dst).unwrap();
+        prop_assert_eq!(Some(params.build()), frame);
+        prop_assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn copt_frame_round_trip(params in copt_params()) {
+        let mut dst = BytesMut::new();
+        CoptEncoder(S7CommEncoder).encode(params.build(), &mut dst).unwrap();
+        let frame = CoptDecoder::new(S7CommDecoder).decode(&mut dst).unwrap();
+        prop_assert_eq!(Some(params.build()), frame);
+        prop_assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn tpkt_frame_round_trip(params in copt_params()) {
+        let mut dst = BytesMut::new();
+        TpktEncoder(CoptEncoder(S7CommEncoder))
+            .encode(TpktFrame::new(params.build()), &mut dst)
+            .unwrap();
+        let frame = TpktDecoder(CoptDecoder::new(S7CommDecoder))
+            .decode(&mut dst)
+            .unwrap();
+        prop_assert_eq!(Some(TpktFrame::new(params.build())), frame);
+        prop_assert!(dst.is_empty());
+    }
+}
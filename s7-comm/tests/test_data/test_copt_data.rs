@@ -1,5 +1,5 @@
 use super::test_s7_comm_data::init_job_setup_frame;
-use copt::{CoptFrame, Parameter, TpduSize};
+use copt::{CoptFrame, DisconnectReason, Parameter, RejectCause, TpduSize};
 use s7_comm::Frame;
 
 pub fn init_copt_dt_data_frame() -> CoptFrame<Frame> {
@@ -52,3 +52,58 @@ pub fn init_copt_connect_confirm_frame_bytes() -> &'static [u8] {
     ]
     .as_ref()
 }
+
+/// a connect confirm of a gateway, with the optional parameters and a vendor one
+pub fn init_copt_connect_confirm_with_options_frame() -> CoptFrame<Frame> {
+    CoptFrame::<Frame>::builder_of_connect()
+        .source_ref([0, 8])
+        .destination_ref([0, 1])
+        .class_and_others(0, false, false)
+        .push_parameter(Parameter::new_tpdu_size(TpduSize::L1024))
+        .push_parameter(Parameter::new_src_tsap([1u8, 0].to_vec()))
+        .push_parameter(Parameter::new_dst_tsap([2u8, 1].to_vec()))
+        .push_parameter(Parameter::Checksum(0x1234))
+        .push_parameter(Parameter::Version(1))
+        .push_parameter(Parameter::AdditionalOptions(1))
+        .push_parameter(Parameter::AlternativeClasses(vec![0x10]))
+        .push_parameter(Parameter::Unknown {
+            code: 0xe9,
+            data: vec![0xab, 0xcd],
+        })
+        .build_to_confirm()
+}
+
+pub fn init_copt_connect_confirm_with_options_frame_bytes() -> &'static [u8] {
+    [
+        0x22, 0xd0, 0x00, 0x01, 0x00, 0x08, 0x00, 0xc0, 0x01, 0x0a, 0xc1, 0x02, 0x01, 0x00, 0xc2,
+        0x02, 0x02, 0x01, 0xc3, 0x02, 0x12, 0x34, 0xc4, 0x01, 0x01, 0xc6, 0x01, 0x01, 0xc7, 0x01,
+        0x10, 0xe9, 0x02, 0xab, 0xcd,
+    ]
+    .as_ref()
+}
+
+/// a connect request refused by the plc, the tsap not reachable
+pub fn init_copt_disconnect_request_frame() -> CoptFrame<Frame> {
+    CoptFrame::disconnect_request([0, 1], [0, 0x0c], DisconnectReason::NotAttachedToTsap)
+}
+
+pub fn init_copt_disconnect_request_frame_bytes() -> &'static [u8] {
+    [0x06, 0x80, 0x00, 0x01, 0x00, 0x0c, 0x02].as_ref()
+}
+
+pub fn init_copt_disconnect_confirm_frame() -> CoptFrame<Frame> {
+    CoptFrame::disconnect_confirm([0, 0x0c], [0, 1])
+}
+
+pub fn init_copt_disconnect_confirm_frame_bytes() -> &'static [u8] {
+    [0x05, 0xc0, 0x00, 0x0c, 0x00, 0x01].as_ref()
+}
+
+/// a tpdu of type 0xe0 rejected
+pub fn init_copt_error_frame() -> CoptFrame<Frame> {
+    CoptFrame::error([0, 1], RejectCause::InvalidTpduType, vec![0xe0])
+}
+
+pub fn init_copt_error_frame_bytes() -> &'static [u8] {
+    [0x07, 0x70, 0x00, 0x01, 0x02, 0xc1, 0x01, 0xe0].as_ref()
+}
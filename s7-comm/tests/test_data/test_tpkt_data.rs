@@ -1,6 +1,6 @@
 use super::test_copt_data::init_copt_connect_request_frame;
 use copt::CoptFrame;
-use s7_comm::Frame;
+use s7_comm::{AckData, DataItemVal, Frame, HearderAckData, ReadVarAckData, ReturnCode};
 use tpkt::TpktFrame;
 
 pub fn init_tpkt_frame() -> TpktFrame<CoptFrame<Frame>> {
@@ -15,3 +15,57 @@ pub fn init_tpkt_frame_bytes() -> &'static [u8] {
     ]
     .as_ref()
 }
+
+/// a read var ack of 112 bytes, in dt data tpdus of 128 bytes
+pub fn init_segmented_tpkt_frame() -> TpktFrame<CoptFrame<Frame>> {
+    let data: Vec<u8> = (0..112).collect();
+    let ack_data = ReadVarAckData::default()
+        .add_response(DataItemVal::init_with_bytes(ReturnCode::Success, &data));
+    let frame = Frame::AckData {
+        header: HearderAckData::init(5, 2, ack_data.bytes_len_data(), 0, 0),
+        ack_data: AckData::ReadVar(ack_data),
+    };
+    TpktFrame::new(CoptFrame::builder_of_dt_data(frame).build(1, true))
+}
+
+pub fn init_segmented_tpkt_frame_bytes() -> &'static [u8] {
+    [
+        0x03, 0x00, 0x00, 0x84, 0x02, 0xf0, 0x00, 0x32, 0x03, 0x00, 0x00, 0x00, 0x05, 0x00, 0x02,
+        0x00, 0x74, 0x00, 0x00, 0x04, 0x01, 0xff, 0x04, 0x03, 0x80, 0x00, 0x01, 0x02, 0x03, 0x04,
+        0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+        0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22,
+        0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x31,
+        0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e, 0x3f, 0x40,
+        0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f,
+        0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x5b, 0x5c, 0x5d, 0x5e,
+        0x5f, 0x60, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x03, 0x00, 0x00,
+        0x0c, 0x02, 0xf0, 0x81, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f,
+    ]
+    .as_ref()
+}
+
+/// a read var ack of 38 bytes, in dt data tpdus of 10, 12 and 16 bytes
+pub fn init_uneven_segmented_tpkt_frame() -> TpktFrame<CoptFrame<Frame>> {
+    let data: Vec<u8> = (0..20).collect();
+    let ack_data = ReadVarAckData::default()
+        .add_response(DataItemVal::init_with_bytes(ReturnCode::Success, &data));
+    let frame = Frame::AckData {
+        header: HearderAckData::init(7, 2, ack_data.bytes_len_data(), 0, 0),
+        ack_data: AckData::ReadVar(ack_data),
+    };
+    TpktFrame::new(CoptFrame::builder_of_dt_data(frame).build(2, true))
+}
+
+/// assembled by hand from RFC 1006 and RFC 905, not by `SegmentEncoder`, which
+/// only splits at the tpdu size: each tpkt header, the dt data header with the
+/// tpdu number, the last one with the end of the data unit, then a part of the
+/// s7 pdu
+pub fn init_uneven_segmented_tpkt_frame_bytes() -> &'static [u8] {
+    [
+        0x03, 0x00, 0x00, 0x11, 0x02, 0xf0, 0x00, 0x32, 0x03, 0x00, 0x00, 0x00, 0x07, 0x00, 0x02,
+        0x00, 0x18, 0x03, 0x00, 0x00, 0x13, 0x02, 0xf0, 0x01, 0x00, 0x00, 0x04, 0x01, 0xff, 0x04,
+        0x00, 0xa0, 0x00, 0x01, 0x02, 0x03, 0x03, 0x00, 0x00, 0x17, 0x02, 0xf0, 0x82, 0x04, 0x05,
+        0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13,
+    ]
+    .as_ref()
+}
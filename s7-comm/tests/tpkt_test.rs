@@ -1,15 +1,19 @@
 mod test_data;
 
-use crate::test_data::test_tpkt_data::{init_tpkt_frame, init_tpkt_frame_bytes};
+use crate::test_data::test_tpkt_data::{
+    init_segmented_tpkt_frame, init_segmented_tpkt_frame_bytes, init_tpkt_frame,
+    init_tpkt_frame_bytes, init_uneven_segmented_tpkt_frame,
+    init_uneven_segmented_tpkt_frame_bytes,
+};
 use bytes::BytesMut;
-use copt::{CoptDecoder, CoptEncoder};
+use copt::{CoptDecoder, CoptEncoder, SegmentEncoder, TpduSize};
 use s7_comm::{S7CommDecoder, S7CommEncoder};
 use tokio_util::codec::{Decoder, Encoder};
 use tpkt::{TpktDecoder, TpktEncoder};
 
 #[test]
 fn test_decode() {
-    let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+    let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
     let mut src = BytesMut::new();
     src.extend_from_slice(init_tpkt_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -31,3 +35,80 @@ fn test_encode() {
     assert!(rs.is_ok());
     assert_eq!(init_tpkt_frame_bytes(), dst.as_ref())
 }
+
+#[test]
+fn test_decode_segmented() {
+    let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
+    let mut src = BytesMut::new();
+    src.extend_from_slice(init_segmented_tpkt_frame_bytes());
+    let frame = decoder.decode(&mut src).unwrap().unwrap();
+    assert_eq!(init_segmented_tpkt_frame(), frame);
+    assert!(src.is_empty());
+
+    // received in pieces, the first tpdu is kept until the last one
+    let mut src = BytesMut::new();
+    for (index, byte) in init_segmented_tpkt_frame_bytes().iter().enumerate() {
+        src.extend_from_slice(&[*byte]);
+        let rs = decoder.decode(&mut src).unwrap();
+        if index + 1 < init_segmented_tpkt_frame_bytes().len() {
+            assert!(rs.is_none());
+        } else {
+            assert_eq!(Some(init_segmented_tpkt_frame()), rs);
+        }
+    }
+}
+
+#[test]
+fn test_decode_uneven_segmented() {
+    let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
+    let mut src = BytesMut::new();
+    src.extend_from_slice(init_uneven_segmented_tpkt_frame_bytes());
+    let frame = decoder.decode(&mut src).unwrap().unwrap();
+    assert_eq!(init_uneven_segmented_tpkt_frame(), frame);
+    assert!(src.is_empty());
+}
+
+#[test]
+fn test_encode_segmented() {
+    let mut encoder = SegmentEncoder::new(S7CommEncoder, TpduSize::L128);
+    let mut dst = BytesMut::new();
+    encoder
+        .encode(init_segmented_tpkt_frame(), &mut dst)
+        .unwrap();
+    assert_eq!(init_segmented_tpkt_frame_bytes(), dst.as_ref());
+
+    // not split within the tpdu size
+    encoder.set_tpdu_size(TpduSize::L256);
+    let mut dst = BytesMut::new();
+    encoder
+        .encode(init_segmented_tpkt_frame(), &mut dst)
+        .unwrap();
+    let mut unsplit = BytesMut::new();
+    TpktEncoder(CoptEncoder(S7CommEncoder))
+        .encode(init_segmented_tpkt_frame(), &mut unsplit)
+        .unwrap();
+    assert_eq!(unsplit, dst);
+    assert_eq!(dst.len(), 4 + 3 + 130);
+}
+
+#[test]
+fn test_decode_malformed() {
+    let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
+
+    let mut src = BytesMut::from([0x04, 0x00, 0x00, 0x07, 0x02, 0xf0, 0x80].as_ref());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(tpkt::Error::InvalidVersion(4))
+    ));
+
+    // shorter than its header
+    let mut src = BytesMut::from([0x03, 0x00, 0x00, 0x02, 0x02, 0xf0, 0x80].as_ref());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(tpkt::Error::InvalidLength(2))
+    ));
+
+    // a payload shorter than the s7comm header
+    let mut src = BytesMut::from([0x03, 0x00, 0x00, 0x09, 0x02, 0xf0, 0x80, 0x32, 0x01].as_ref());
+    assert!(decoder.decode(&mut src).is_err());
+}
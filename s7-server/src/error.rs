@@ -0,0 +1,17 @@
+use std::io;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    IoErr(#[from] io::Error),
+
+    #[error(transparent)]
+    TpktErr(#[from] tpkt::Error),
+
+    #[error("Error: {0}")]
+    Err(String)
+}
+
+pub type Result<T> =
+    std::result::Result<T, Error>;
@@ -0,0 +1,8 @@
+mod error;
+mod memory;
+mod server;
+
+pub use error::*;
+pub use memory::*;
+pub use s7_comm;
+pub use server::*;
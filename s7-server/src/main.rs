@@ -0,0 +1,52 @@
+use std::env;
+
+use s7_server::{
+    Error, Memory, Result, S7Server,
+    ServerOptions
+};
+use tokio::net::TcpListener;
+
+const USAGE: &str = "usage: s7-server [address] \
+                     [db_number=bytes]...
+example: s7-server 0.0.0.0:102 1=1024 2=256";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut address = "0.0.0.0:102".to_string();
+    let mut memory = Memory::default();
+    for arg in env::args().skip(1) {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        match arg.split_once('=') {
+            Some((number, len)) => {
+                let (Ok(number), Ok(len)) =
+                    (number.parse(), len.parse())
+                else {
+                    return Err(Error::Err(
+                        format!(
+                            "invalid db `{}`\n{}",
+                            arg, USAGE
+                        )
+                    ));
+                };
+                memory =
+                    memory.with_db(number, len);
+            },
+            None => address = arg
+        }
+    }
+    let listener =
+        TcpListener::bind(&address).await?;
+    println!(
+        "listen on {}",
+        listener.local_addr()?
+    );
+    S7Server::new(
+        ServerOptions::default(),
+        memory
+    )
+    .serve(listener)
+    .await
+}
@@ -0,0 +1,506 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use s7_comm::{
+    AlarmMessage, AlarmType, Area, BlockCount,
+    BlockHeader, BlockInfo, BlockListEntry,
+    BlockType, DataItemVal, DataTransportSize,
+    DateAndTime, DbNumber, ItemRequest,
+    ReturnCode, TransportSize
+};
+
+/// the block types counted by the list blocks
+/// function
+const BLOCK_TYPES: [BlockType; 7] = [
+    BlockType::OB,
+    BlockType::FB,
+    BlockType::FC,
+    BlockType::DB,
+    BlockType::SDB,
+    BlockType::SFC,
+    BlockType::SFB
+];
+
+/// In-memory areas of the simulated plc.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Memory {
+    pub inputs:         Vec<u8>,
+    pub outputs:        Vec<u8>,
+    pub merkers:        Vec<u8>,
+    /// 2 bytes for each timer
+    pub timers:         Vec<u8>,
+    /// 2 bytes for each counter
+    pub counters:       Vec<u8>,
+    pub dbs:            BTreeMap<u16, Vec<u8>>,
+    /// records of the system status lists, by id
+    /// and index
+    pub szl: BTreeMap<(u16, u16), Vec<Vec<u8>>>,
+    /// the plc in run, changed by the plc
+    /// control jobs
+    pub running:        bool,
+    /// the clock, set by the clock functions but
+    /// not ticking
+    pub clock:          DateAndTime,
+    /// blocks of the load memory, by type and
+    /// number: the info and the uploaded body
+    pub blocks: BTreeMap<
+        (BlockType, u16),
+        (BlockInfo, Vec<u8>)
+    >,
+    /// blocks downloaded to the passive file
+    /// system, not yet inserted in `blocks`
+    pub passive_blocks:
+        BTreeMap<(BlockType, u16), Vec<u8>>,
+    /// the password protecting the plc control
+    /// and download jobs, none by default
+    pub password:       Option<String>,
+    /// the last message of each alarm, by event
+    /// id, set by [`S7Server::notify_alarm`]
+    /// and the acknowledgements
+    ///
+    /// [`S7Server::notify_alarm`]:
+    /// crate::S7Server::notify_alarm
+    pub alarms:
+        BTreeMap<u32, (AlarmType, AlarmMessage)>
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self {
+            inputs:         vec![0; 1024],
+            outputs:        vec![0; 1024],
+            merkers:        vec![0; 1024],
+            timers:         vec![0; 512],
+            counters:       vec![0; 512],
+            dbs:            BTreeMap::new(),
+            szl:            BTreeMap::new(),
+            running:        true,
+            clock:          DateAndTime::default(
+            ),
+            blocks:         BTreeMap::new(),
+            passive_blocks: BTreeMap::new(),
+            password:       None,
+            alarms:         BTreeMap::new()
+        }
+    }
+}
+
+/// Bytes of an area accessed by an item.
+enum Location {
+    Bit { byte: usize, bit: u8 },
+    Bytes(Range<usize>)
+}
+
+impl Memory {
+    /// add a db of `len` bytes, initialized to 0
+    pub fn with_db(
+        mut self,
+        number: u16,
+        len: usize
+    ) -> Self {
+        self.dbs.insert(number, vec![0; len]);
+        self
+    }
+
+    /// add the system status list `id` with
+    /// `index`, records of the same length
+    pub fn with_szl(
+        mut self,
+        id: u16,
+        index: u16,
+        records: Vec<Vec<u8>>
+    ) -> Self {
+        self.szl.insert((id, index), records);
+        self
+    }
+
+    /// protect the plc control and download jobs
+    /// by `password`
+    pub fn with_password(
+        mut self,
+        password: &str
+    ) -> Self {
+        self.password =
+            Some(password.to_string());
+        self
+    }
+
+    /// add a block, uploaded as `body`
+    pub fn with_block(
+        mut self,
+        info: BlockInfo,
+        body: Vec<u8>
+    ) -> Self {
+        self.blocks.insert(
+            (info.block_type, info.number),
+            (info, body)
+        );
+        self
+    }
+
+    /// move a downloaded block to `blocks`, its
+    /// info updated from the header of the block
+    pub fn insert_block(
+        &mut self,
+        block_type: BlockType,
+        number: u16
+    ) -> bool {
+        // a block with an invalid header stays
+        // in the passive file
+        let Some(Ok(header)) = self
+            .passive_blocks
+            .get(&(block_type, number))
+            .map(|body| {
+                BlockHeader::decode(body)
+            })
+        else {
+            return false;
+        };
+        let Some(body) = self
+            .passive_blocks
+            .remove(&(block_type, number))
+        else {
+            return false;
+        };
+        let mut info = self
+            .blocks
+            .remove(&(block_type, number))
+            .map(|x| x.0)
+            .unwrap_or_else(|| BlockInfo {
+                block_type,
+                number,
+                flags: 0,
+                language: 0,
+                sub_block_type: 0,
+                load_size: 0,
+                security: 0,
+                code_time: Default::default(),
+                interface_time: Default::default(
+                ),
+                sbb_len: 0,
+                add_len: 0,
+                local_data_len: 0,
+                mc7_size: 0,
+                author: String::new(),
+                family: String::new(),
+                name: String::new(),
+                version: 0,
+                checksum: 0
+            });
+        info.flags = header.flags;
+        info.language = header.language;
+        info.sub_block_type =
+            header.sub_block_type;
+        info.load_size = header.load_size;
+        info.security = header.security;
+        info.code_time = header.code_time;
+        info.interface_time =
+            header.interface_time;
+        info.sbb_len = header.sbb_len;
+        info.add_len = header.add_len;
+        info.local_data_len =
+            header.local_data_len;
+        info.mc7_size = header.mc7_size;
+        self.blocks.insert(
+            (block_type, number),
+            (info, body)
+        );
+        true
+    }
+
+    /// remove a block of both file systems
+    pub fn delete_block(
+        &mut self,
+        block_type: BlockType,
+        number: u16
+    ) -> bool {
+        let active = self
+            .blocks
+            .remove(&(block_type, number))
+            .is_some();
+        let passive = self
+            .passive_blocks
+            .remove(&(block_type, number))
+            .is_some();
+        active || passive
+    }
+
+    /// the count of the blocks of each type
+    pub fn count_blocks(
+        &self
+    ) -> Vec<BlockCount> {
+        BLOCK_TYPES
+            .iter()
+            .map(|&block_type| BlockCount {
+                block_type,
+                count: self
+                    .blocks
+                    .keys()
+                    .filter(|x| x.0 == block_type)
+                    .count()
+                    as u16
+            })
+            .collect()
+    }
+
+    /// the blocks of `block_type`
+    pub fn list_blocks(
+        &self,
+        block_type: BlockType
+    ) -> Vec<BlockListEntry> {
+        self.blocks
+            .values()
+            .filter(|x| {
+                x.0.block_type == block_type
+            })
+            .map(|(info, _)| BlockListEntry {
+                number:   info.number,
+                flags:    info.flags,
+                language: info.language
+            })
+            .collect()
+    }
+
+    /// the data answering a read szl request: id,
+    /// index, record length and record count,
+    /// followed by the records. The cpu state,
+    /// szl 0x0424, follows `running` unless it is
+    /// added.
+    pub fn read_szl(
+        &self,
+        id: u16,
+        index: u16
+    ) -> Option<Vec<u8>> {
+        let cpu_state;
+        let records =
+            match self.szl.get(&(id, index)) {
+                Some(records) => records,
+                None if id == 0x0424 => {
+                    let mut record = vec![0; 20];
+                    record[2] = 0xff;
+                    // run, or stop by the plc
+                    // control
+                    record[3] = if self.running {
+                        0x08
+                    } else {
+                        0x04
+                    };
+                    cpu_state = vec![record];
+                    &cpu_state
+                },
+                None => return None
+            };
+        let record_len = records
+            .first()
+            .map_or(0, |x| x.len());
+        let mut data = Vec::with_capacity(
+            8 + record_len * records.len()
+        );
+        data.extend_from_slice(&id.to_be_bytes());
+        data.extend_from_slice(
+            &index.to_be_bytes()
+        );
+        data.extend_from_slice(
+            &(record_len as u16).to_be_bytes()
+        );
+        data.extend_from_slice(
+            &(records.len() as u16).to_be_bytes()
+        );
+        for record in records {
+            data.extend_from_slice(record);
+        }
+        Some(data)
+    }
+
+    /// answer a ReadVar item
+    pub fn read(
+        &self,
+        item: &ItemRequest
+    ) -> DataItemVal {
+        let rs = self.area(item).and_then(|area| {
+            Ok(match locate(item, area.len())? {
+                Location::Bit { byte, bit } => {
+                    DataItemVal::init_with_bit(
+                        ReturnCode::Success,
+                        area[byte] >> bit & 1 > 0
+                    )
+                },
+                Location::Bytes(range) => {
+                    DataItemVal::init_with_bytes(
+                        ReturnCode::Success,
+                        &area[range]
+                    )
+                },
+            })
+        });
+        rs.unwrap_or_else(|code| {
+            DataItemVal::init_with_bytes(
+                code,
+                &[]
+            )
+        })
+    }
+
+    /// apply a WriteVar item
+    pub fn write(
+        &mut self,
+        item: &ItemRequest,
+        data: &DataItemVal
+    ) -> ReturnCode {
+        let area = match self.area_mut(item) {
+            Ok(area) => area,
+            Err(code) => return code
+        };
+        let location =
+            match locate(item, area.len()) {
+                Ok(location) => location,
+                Err(code) => return code
+            };
+        match location {
+            Location::Bit { byte, bit } => {
+                if data.transport_size_type
+                    != DataTransportSize::Bit
+                    || data.data.len() != 1
+                {
+                    return ReturnCode::SizeMismatch;
+                }
+                if data.data[0] > 0 {
+                    area[byte] |= 1 << bit;
+                } else {
+                    area[byte] &= !(1 << bit);
+                }
+            },
+            Location::Bytes(range) => {
+                if data.data.len() != range.len()
+                {
+                    return ReturnCode::SizeMismatch;
+                }
+                area[range]
+                    .copy_from_slice(&data.data);
+            }
+        }
+        ReturnCode::Success
+    }
+
+    fn area(
+        &self,
+        item: &ItemRequest
+    ) -> Result<&Vec<u8>, ReturnCode> {
+        match (item.area(), item.db_number()) {
+            (Area::ProcessInput, _) => {
+                Ok(&self.inputs)
+            },
+            (Area::ProcessOutput, _) => {
+                Ok(&self.outputs)
+            },
+            (Area::Merker, _) => {
+                Ok(&self.merkers)
+            },
+            (Area::Timer, _) => Ok(&self.timers),
+            (Area::Counter, _) => {
+                Ok(&self.counters)
+            },
+            (
+                Area::DataBlocks,
+                DbNumber::DbNumber(number)
+            ) => self
+                .dbs
+                .get(&number)
+                .ok_or(ReturnCode::Err),
+            _ => Err(ReturnCode::Err)
+        }
+    }
+
+    fn area_mut(
+        &mut self,
+        item: &ItemRequest
+    ) -> Result<&mut Vec<u8>, ReturnCode> {
+        match (item.area(), item.db_number()) {
+            (Area::ProcessInput, _) => {
+                Ok(&mut self.inputs)
+            },
+            (Area::ProcessOutput, _) => {
+                Ok(&mut self.outputs)
+            },
+            (Area::Merker, _) => {
+                Ok(&mut self.merkers)
+            },
+            (Area::Timer, _) => {
+                Ok(&mut self.timers)
+            },
+            (Area::Counter, _) => {
+                Ok(&mut self.counters)
+            },
+            (
+                Area::DataBlocks,
+                DbNumber::DbNumber(number)
+            ) => self
+                .dbs
+                .get_mut(&number)
+                .ok_or(ReturnCode::Err),
+            _ => Err(ReturnCode::Err)
+        }
+    }
+}
+
+/// Locate the bytes accessed by `item` in an
+/// area of `len` bytes.
+fn locate(
+    item: &ItemRequest,
+    len: usize
+) -> Result<Location, ReturnCode> {
+    let address = item.address();
+    let byte_addr = address.byte_addr() as usize;
+    let count = item.length() as usize;
+    let (start, unit) = match item.area() {
+        // timers and counters are addressed by
+        // number, 2 bytes each
+        Area::Timer | Area::Counter => {
+            let number = byte_addr << 3
+                | address.bit_addr() as usize;
+            (number * 2, 2)
+        },
+        _ => {
+            let unit = match item
+                .transport_size_type()
+            {
+                TransportSize::Bit => {
+                    if count != 1
+                        || byte_addr >= len
+                    {
+                        return Err(
+                            ReturnCode::InvalidAddress
+                        );
+                    }
+                    return Ok(Location::Bit {
+                        byte: byte_addr,
+                        bit:  address.bit_addr()
+                    });
+                },
+                TransportSize::NoBit => 1,
+                // char
+                TransportSize::NotSupport(
+                    0x03
+                ) => 1,
+                // word, int
+                TransportSize::NotSupport(
+                    0x04 | 0x05
+                ) => 2,
+                // dword, dint, real
+                TransportSize::NotSupport(
+                    0x06..=0x08
+                ) => 4,
+                _ => {
+                    return Err(
+                        ReturnCode::NotSupported
+                    );
+                }
+            };
+            (byte_addr, unit)
+        }
+    };
+    let end = start + count * unit;
+    if end > len {
+        return Err(ReturnCode::InvalidAddress);
+    }
+    Ok(Location::Bytes(start..end))
+}
@@ -0,0 +1,1519 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration
+};
+
+use crate::{Memory, error::*};
+use bytes::BytesMut;
+use copt::{
+    ConnectComm, CoptDecoder, CoptFrame,
+    Parameter, PduType, SegmentEncoder, TpduSize
+};
+use log::{debug, warn};
+use s7_comm::{
+    ALARM_ACK, ALARM_QUERY, ALREADY_RUN,
+    ALREADY_STOP, AckData, AlarmAck, AlarmKind,
+    AlarmMessage, AlarmNotification, AlarmType,
+    BLOCK_NOT_FOUND, BlockCount, BlockFile,
+    BlockListEntry, BlockType, CLEAR_PASSWORD,
+    CYCLIC_TRANSFER, CYCLIC_UNSUBSCRIBE,
+    CyclicData, CyclicRequest, DELETE_BLOCK,
+    DataItemVal, DataItemWriteResponse, DataUnit,
+    DateAndTime, Frame, FunctionGroup, GARB,
+    GET_BLOCK_INFO, Header, HearderAckData,
+    INSERT_BLOCK, ItemRequest, Job, LIST_BLOCKS,
+    LIST_BLOCKS_OF_TYPE, MESSAGE_SERVICE, MODU,
+    MessageService, Method, P_PROGRAM,
+    PiServiceJob, PlcControlAckData, PlcStopJob,
+    READ_CLOCK, ReadVarAckData,
+    RequestDownloadJob, ReturnCode,
+    S7CommDecoder, S7CommEncoder, SET_CLOCK,
+    SET_PASSWORD, SetupCommunication,
+    StartUploadAckData, StartUploadJob,
+    UploadAckData, UploadJob, UserDataItem,
+    UserDataParameter, WriteVarAckData,
+    decode_alarm_query, decode_password,
+    decode_unsubscribe
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{self, error::RecvError},
+    time::{Instant, sleep_until}
+};
+use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktDecoder, TpktFrame};
+
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// the largest pdu length accepted in setup
+    /// communication
+    pub pdu_len:   u16,
+    /// the largest jobs in parallel accepted in
+    /// setup communication
+    pub max_amq:   u16,
+    /// the largest tpdu size accepted in connect
+    /// request
+    pub tpdu_size: TpduSize
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            pdu_len:   480,
+            max_amq:   8,
+            tpdu_size: TpduSize::L1024
+        }
+    }
+}
+
+/// subfunction of the cpu functions reading a
+/// system status list
+const READ_SZL: u8 = 0x01;
+/// userdata error code of an unknown szl
+const SZL_NOT_AVAILABLE: u16 = 0xd401;
+/// userdata error code of an invalid value
+const INVALID_VALUE: u16 = 0xdc01;
+/// userdata error code of an unsupported
+/// function
+const FUNCTION_NOT_AVAILABLE: u16 = 0x8104;
+/// bytes of a userdata response besides the
+/// data: header, parameter and data head
+const USER_DATA_OVERHEAD: u16 = 26;
+/// error code of an upload job out of its
+/// session: coordination rules violated
+const UPLOAD_SEQUENCE_ERROR: u16 = 0xd240;
+/// bytes of an upload ack besides the part of
+/// the block: header, parameter and data head
+const UPLOAD_OVERHEAD: u16 = 18;
+/// error code of a block type not downloaded
+const INVALID_BLOCK_TYPE: u16 = 0xd203;
+/// error code of a job protected by the
+/// password, the session not logged in
+const NEED_PASSWORD: u16 = 0xd241;
+/// userdata error code of a wrong password
+const INVALID_PASSWORD: u16 = 0xd602;
+/// userdata error code of clearing the password
+/// of a session not logged in
+const NO_PASSWORD_TO_CLEAR: u16 = 0xd604;
+/// userdata error code of a password set on a
+/// plc without protection
+const NO_PASSWORD_TO_SET: u16 = 0xd605;
+/// bytes of a userdata push besides the data
+/// of its items: header, parameter, data head and
+/// count
+const PUSH_OVERHEAD: u16 = 28;
+/// alarm notifications buffered for a slow
+/// connection, the older ones dropped
+const ALARM_CAPACITY: usize = 64;
+
+/// bytes of a read var ack besides the data
+/// items: header and parameter
+const READ_VAR_OVERHEAD: u16 = 14;
+/// error code of a job whose data doesn't match
+/// its items, or whose ack exceeds the pdu:
+/// wrong frames
+const WRONG_FRAMES: u16 = 0x8500;
+/// error class and code of a refused plc
+/// control job: error on service processing
+const SERVICE_ERROR: (u8, u8) = (0x84, 0x04);
+
+/// bytes of the read var ack of `items`, with
+/// the fill bytes between them
+fn read_var_len(items: &[DataItemVal]) -> usize {
+    let fill = items
+        .last()
+        .map_or(0, |x| x.data.len() % 2);
+    items
+        .iter()
+        .map(|x| {
+            4 + x.data.len() + x.data.len() % 2
+        })
+        .sum::<usize>()
+        - fill
+        + READ_VAR_OVERHEAD as usize
+}
+
+/// State of a connection.
+struct Session {
+    /// the negotiated tpdu size
+    tpdu_size:       TpduSize,
+    /// the negotiated pdu length
+    pdu_len:         u16,
+    /// the fragments, not yet requested, of a
+    /// fragmented userdata response
+    fragments:       VecDeque<Vec<u8>>,
+    sequence_number: u8,
+    /// the id of the last upload session
+    upload_id:       u32,
+    /// the upload session in progress, and its
+    /// parts not yet uploaded
+    upload: Option<(u32, VecDeque<Vec<u8>>)>,
+    /// the download in progress
+    download:        Option<Download>,
+    /// the pdu reference of the last job sent
+    /// to the client
+    job_ref:         u16,
+    /// the password of the memory set by the
+    /// session
+    logged_in:       bool,
+    /// the cyclic reads registered by the client
+    cyclic:          Vec<CyclicJob>,
+    /// the alarms subscribed by the client
+    alarms:          Option<AlarmType>,
+    /// the client requested the disconnection
+    disconnected:    bool
+}
+
+/// A cyclic read, whose items are pushed to the
+/// client at its interval.
+struct CyclicJob {
+    /// the sequence number of its response and
+    /// pushes
+    id:       u8,
+    items:    Vec<ItemRequest>,
+    interval: Duration,
+    next:     Instant
+}
+
+/// An alarm notification, pushed to the
+/// connections subscribed to its type.
+#[derive(Debug, Clone)]
+struct AlarmPush {
+    alarm_type:   AlarmType,
+    kind:         AlarmKind,
+    notification: AlarmNotification
+}
+
+/// A download requested by the client, the
+/// block requested part by part.
+struct Download {
+    file: BlockFile,
+    data: Vec<u8>,
+    /// more parts follow
+    more: bool
+}
+
+/// A simulated plc, serving ReadVar, WriteVar,
+/// plc control, upload and download jobs, szl
+/// reads, clock, block, cyclic read and alarm
+/// functions against a shared [`Memory`].
+#[derive(Clone)]
+pub struct S7Server {
+    options: Arc<ServerOptions>,
+    memory:  Arc<Mutex<Memory>>,
+    alarms:  broadcast::Sender<AlarmPush>
+}
+
+impl S7Server {
+    pub fn new(
+        options: ServerOptions,
+        memory: Memory
+    ) -> Self {
+        Self {
+            options: Arc::new(options),
+            memory:  Arc::new(Mutex::new(memory)),
+            alarms:  broadcast::channel(
+                ALARM_CAPACITY
+            )
+            .0
+        }
+    }
+
+    /// the memory, shared by the connections
+    pub fn memory(
+        &self
+    ) -> MutexGuard<'_, Memory> {
+        self.memory.lock().unwrap_or_else(
+            PoisonError::into_inner
+        )
+    }
+
+    /// raise, clear or update the alarm `message`
+    /// of `alarm_type`, stored in the memory and
+    /// notified at its clock to the connections
+    /// subscribed to this type
+    pub fn notify_alarm(
+        &self,
+        alarm_type: AlarmType,
+        message: AlarmMessage
+    ) {
+        let timestamp = {
+            let mut memory = self.memory();
+            memory.alarms.insert(
+                message.event_id,
+                (alarm_type, message.clone())
+            );
+            memory.clock
+        };
+        let kind = match alarm_type {
+            AlarmType::Scan => AlarmKind::Scan,
+            AlarmType::Alarm8 => {
+                AlarmKind::Alarm8
+            },
+            _ => AlarmKind::AlarmS
+        };
+        // none subscribed without connection
+        let _ = self.alarms.send(AlarmPush {
+            alarm_type,
+            kind,
+            notification: AlarmNotification {
+                timestamp,
+                messages: vec![message]
+            }
+        });
+    }
+
+    /// accept connections of `listener`, each one
+    /// served by its own task
+    pub async fn serve(
+        &self,
+        listener: TcpListener
+    ) -> Result<()> {
+        loop {
+            let (stream, addr) =
+                listener.accept().await?;
+            debug!("accept {}", addr);
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) =
+                    server.handle(stream).await
+                {
+                    warn!(
+                        "connection {}: {}",
+                        addr, err
+                    );
+                }
+            });
+        }
+    }
+
+    /// serve a connection until it is closed or
+    /// disconnected by the peer
+    pub async fn handle(
+        &self,
+        mut stream: TcpStream
+    ) -> Result<()> {
+        let mut buf = BytesMut::new();
+        let mut decoder = TpktDecoder(
+            CoptDecoder::new(S7CommDecoder)
+        );
+        let mut encoder = SegmentEncoder::new(
+            S7CommEncoder,
+            self.options.tpdu_size
+        );
+        let mut session = Session {
+            tpdu_size:       self
+                .options
+                .tpdu_size,
+            pdu_len:         self.options.pdu_len,
+            fragments:       VecDeque::new(),
+            sequence_number: 0,
+            upload_id:       0,
+            upload:          None,
+            download:        None,
+            job_ref:         0,
+            logged_in:       false,
+            cyclic:          Vec::new(),
+            alarms:          None,
+            disconnected:    false
+        };
+        let mut alarms = self.alarms.subscribe();
+        loop {
+            let mut frames = Vec::new();
+            while let Some(frame) =
+                decoder.decode(&mut buf)?
+            {
+                frames.extend(self.respond(
+                    &mut session,
+                    frame.payload()
+                ));
+            }
+            let next_push = session
+                .cyclic
+                .iter()
+                .map(|x| x.next)
+                .min();
+            if frames.is_empty() {
+                tokio::select! {
+                    rs = stream.read_buf(&mut buf) => {
+                        if rs? == 0 {
+                            return Ok(());
+                        }
+                    },
+                    _ = sleep_until(next_push.unwrap_or_else(Instant::now)),
+                        if next_push.is_some() => {
+                        frames = self
+                            .push(&mut session)
+                            .into_iter()
+                            .map(|x| {
+                                CoptFrame::builder_of_dt_data(x)
+                                    .build(0, true)
+                            })
+                            .collect();
+                    },
+                    alarm = alarms.recv() => match alarm {
+                        Ok(alarm) => {
+                            frames = session
+                                .push_alarm(alarm)
+                                .into_iter()
+                                .map(|x| {
+                                    CoptFrame::builder_of_dt_data(x)
+                                        .build(0, true)
+                                })
+                                .collect();
+                        },
+                        Err(RecvError::Lagged(count)) => warn!(
+                            "{} alarm notifications dropped",
+                            count
+                        ),
+                        // the server holds the sender
+                        Err(RecvError::Closed) => {},
+                    },
+                }
+            }
+            encoder
+                .set_tpdu_size(session.tpdu_size);
+            for frame in frames {
+                let mut dst = BytesMut::new();
+                encoder.encode(
+                    TpktFrame::new(frame),
+                    &mut dst
+                )?;
+                stream.write_all(&dst).await?;
+            }
+            if session.disconnected {
+                return Ok(());
+            }
+        }
+    }
+
+    fn respond(
+        &self,
+        session: &mut Session,
+        frame: CoptFrame<Frame>
+    ) -> Vec<CoptFrame<Frame>> {
+        match frame.pdu_type {
+            PduType::ConnectRequest(comm) => {
+                vec![self.confirm(session, comm)]
+            },
+            PduType::DtData(data) => self
+                .dt_data(session, data.payload())
+                .into_iter()
+                .map(|x| {
+                    CoptFrame::builder_of_dt_data(
+                        x
+                    )
+                    .build(0, true)
+                })
+                .collect(),
+            PduType::DisconnectRequest(comm) => {
+                session.disconnected = true;
+                vec![CoptFrame::disconnect_confirm(
+                    comm.source_ref,
+                    comm.destination_ref
+                )]
+            },
+            PduType::Error(comm) => {
+                warn!(
+                    "tpdu rejected by the client: {}",
+                    comm.cause
+                );
+                Vec::new()
+            },
+            PduType::ConnectConfirm(_)
+            | PduType::DisconnectConfirm(_) => {
+                debug!("ignore {:?}", frame.pdu_type);
+                Vec::new()
+            }
+        }
+    }
+
+    /// answer a s7 frame, by a frame or, for an
+    /// accepted download, the ack data followed
+    /// by the request of the first part
+    fn dt_data(
+        &self,
+        session: &mut Session,
+        frame: Frame
+    ) -> Vec<Frame> {
+        match frame {
+            Frame::Job { header, job } => {
+                let request_download = matches!(
+                    job,
+                    Job::RequestDownload(_)
+                );
+                let mut frames =
+                    vec![self.job(
+                        session, header, job
+                    )];
+                if request_download {
+                    frames.extend(
+                        session.download_job()
+                    );
+                }
+                frames
+            },
+            Frame::UserData {
+                header,
+                parameter,
+                data
+            } => vec![self.user_data(
+                session, header, parameter, data
+            )],
+            Frame::AckData {
+                header,
+                ack_data
+            } => self
+                .download_ack(
+                    session, header, ack_data
+                )
+                .into_iter()
+                .collect()
+        }
+    }
+
+    /// confirm the connect request, with the
+    /// smaller tpdu size and the tsaps
+    fn confirm(
+        &self,
+        session: &mut Session,
+        request: ConnectComm
+    ) -> CoptFrame<Frame> {
+        let mut builder =
+            CoptFrame::builder_of_connect()
+                .source_ref([0, 1])
+                .destination_ref(
+                    request.source_ref
+                )
+                .class_and_others(
+                    0, false, false
+                );
+        let mut tpdu_size =
+            self.options.tpdu_size;
+        for parameter in request.parameters {
+            match parameter {
+                Parameter::TpduSize(size) => {
+                    if u8::from(size)
+                        < u8::from(tpdu_size)
+                    {
+                        tpdu_size = size;
+                    }
+                },
+                Parameter::SrcTsap(_)
+                | Parameter::DstTsap(_) => {
+                    builder = builder
+                        .push_parameter(parameter)
+                },
+                // the other parameters are not
+                // supported in class 0
+                parameter => {
+                    debug!("ignore {:?}", parameter)
+                },
+            }
+        }
+        session.tpdu_size = tpdu_size;
+        builder
+            .push_parameter(
+                Parameter::new_tpdu_size(
+                    tpdu_size
+                )
+            )
+            .build_to_confirm()
+    }
+
+    fn job(
+        &self,
+        session: &mut Session,
+        header: Header,
+        job: Job
+    ) -> Frame {
+        // the state of a refused plc control job
+        let mut refused = None;
+        // the error of a refused upload, download
+        // or protected job
+        let mut error = 0;
+        let locked = matches!(
+            job,
+            Job::PiService(_)
+                | Job::PlcStop(_)
+                | Job::RequestDownload(_)
+        ) && self.locked(session);
+        if locked {
+            error = NEED_PASSWORD;
+        }
+        let (ack_data, parameter_len, data_len) =
+            match job {
+                Job::SetupCommunication(
+                    setup
+                ) => {
+                    let setup =
+                        SetupCommunication::init(
+                            setup
+                                .max_amq_calling()
+                                .min(
+                                    self.options
+                                        .max_amq
+                                ),
+                            setup
+                                .max_amq_called()
+                                .min(
+                                    self.options
+                                        .max_amq
+                                ),
+                            setup
+                                .pdu_length()
+                                .min(
+                                    self.options
+                                        .pdu_len
+                                )
+                        );
+                    session.pdu_len =
+                        setup.pdu_length();
+                    (
+                        AckData::SetupCommunication(
+                            setup
+                        ),
+                        8,
+                        0
+                    )
+                },
+                Job::ReadVar(job) => {
+                    let memory = self.memory();
+                    let mut items: Vec<_> = job
+                        .parameters_item()
+                        .iter()
+                        .map(|x| memory.read(x))
+                        .collect();
+                    if read_var_len(&items)
+                        > session.pdu_len as usize
+                    {
+                        error = WRONG_FRAMES;
+                        items.clear();
+                    }
+                    let ack_data = items.into_iter().fold(
+                        ReadVarAckData::default(),
+                        ReadVarAckData::add_response
+                    );
+                    let data_len =
+                        ack_data.bytes_len_data();
+                    (
+                        AckData::ReadVar(
+                            ack_data
+                        ),
+                        2,
+                        data_len
+                    )
+                },
+                Job::WriteVar(job) => {
+                    let mut memory =
+                        self.memory();
+                    let items = job
+                        .items()
+                        .unwrap_or_else(|| {
+                            error = WRONG_FRAMES;
+                            Vec::new()
+                        });
+                    let data_len =
+                        items.len() as u16;
+                    let ack_data = items.iter().fold(
+                        WriteVarAckData::default(),
+                        |ack_data, (item, data)| {
+                            ack_data.add_response(
+                                DataItemWriteResponse::init(
+                                    memory.write(item, data)
+                                )
+                            )
+                        }
+                    );
+                    (
+                        AckData::WriteVar(
+                            ack_data
+                        ),
+                        2,
+                        data_len
+                    )
+                },
+                Job::PiService(job) => {
+                    if !locked {
+                        refused = self
+                            .pi_service(&job)
+                            .err();
+                    }
+                    let ack_data =
+                        PlcControlAckData::init(
+                            refused.flatten()
+                        );
+                    let parameter_len = ack_data
+                        .bytes_len_parameter();
+                    (
+                        AckData::PiService(
+                            ack_data
+                        ),
+                        parameter_len,
+                        0
+                    )
+                },
+                Job::PlcStop(job) => {
+                    if !locked {
+                        refused = self
+                            .plc_stop(&job)
+                            .err();
+                    }
+                    let ack_data =
+                        PlcControlAckData::init(
+                            refused.flatten()
+                        );
+                    let parameter_len = ack_data
+                        .bytes_len_parameter();
+                    (
+                        AckData::PlcStop(
+                            ack_data
+                        ),
+                        parameter_len,
+                        0
+                    )
+                },
+                Job::StartUpload(job) => {
+                    let ack_data = self
+                        .start_upload(session, &job)
+                        .unwrap_or_else(|x| {
+                            error = x;
+                            StartUploadAckData::init(
+                                0, 0
+                            )
+                        });
+                    let parameter_len = ack_data
+                        .bytes_len_parameter();
+                    (
+                        AckData::StartUpload(
+                            ack_data
+                        ),
+                        parameter_len,
+                        0
+                    )
+                },
+                Job::Upload(job) => {
+                    let ack_data = session
+                        .upload(&job)
+                        .unwrap_or_else(|x| {
+                            error = x;
+                            UploadAckData::init(
+                                false,
+                                &[]
+                            )
+                        });
+                    let parameter_len = ack_data
+                        .bytes_len_parameter();
+                    let data_len =
+                        ack_data.bytes_len_data();
+                    (
+                        AckData::Upload(ack_data),
+                        parameter_len,
+                        data_len
+                    )
+                },
+                Job::EndUpload(job) => {
+                    if let Err(x) =
+                        session.end_upload(&job)
+                    {
+                        error = x;
+                    }
+                    (AckData::EndUpload, 1, 0)
+                },
+                Job::RequestDownload(job) => {
+                    if !locked {
+                        if let Err(x) = session
+                            .request_download(
+                                &job
+                            )
+                        {
+                            error = x;
+                        }
+                    }
+                    (
+                        AckData::RequestDownload,
+                        1,
+                        0
+                    )
+                },
+                job @ (Job::DownloadBlock(_)
+                | Job::DownloadEnded(_)) => {
+                    debug!("ignore {:?}", job);
+                    error =
+                        FUNCTION_NOT_AVAILABLE;
+                    (AckData::DownloadEnded, 1, 0)
+                }
+            };
+        let (error_class, error_code) =
+            if refused.is_some() {
+                SERVICE_ERROR
+            } else {
+                ((error >> 8) as u8, error as u8)
+            };
+        Frame::AckData {
+            header: HearderAckData::init(
+                header.pdu_ref,
+                parameter_len,
+                data_len,
+                error_class,
+                error_code
+            ),
+            ack_data
+        }
+    }
+
+    /// the memory protected by a password not
+    /// set by the session
+    fn locked(&self, session: &Session) -> bool {
+        !session.logged_in
+            && self.memory().password.is_some()
+    }
+
+    /// set or clear the password of the session
+    fn security(
+        &self,
+        session: &mut Session,
+        subfunction: u8,
+        data: &[u8]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        match subfunction {
+            SET_PASSWORD => {
+                let memory = self.memory();
+                let password = memory
+                    .password
+                    .as_ref()
+                    .ok_or(NO_PASSWORD_TO_SET)?;
+                if decode_password(data)
+                    .ok()
+                    .as_ref()
+                    != Some(password)
+                {
+                    return Err(INVALID_PASSWORD);
+                }
+                session.logged_in = true;
+                Ok(Vec::new())
+            },
+            CLEAR_PASSWORD
+                if session.logged_in =>
+            {
+                session.logged_in = false;
+                Ok(Vec::new())
+            },
+            CLEAR_PASSWORD => {
+                Err(NO_PASSWORD_TO_CLEAR)
+            },
+            _ => Err(FUNCTION_NOT_AVAILABLE)
+        }
+    }
+
+    /// register a cyclic read, answered with its
+    /// first items. Its id is the sequence
+    /// number of the response.
+    fn cyclic_read(
+        &self,
+        session: &mut Session,
+        data: &[u8]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        let request = CyclicRequest::decode(data)
+            .map_err(|_| INVALID_VALUE)?;
+        let interval = request
+            .interval
+            .duration()
+            .filter(|x| !x.is_zero())
+            .ok_or(INVALID_VALUE)?;
+        if request.items.is_empty() {
+            return Err(INVALID_VALUE);
+        }
+        let items = self.read_cyclic(
+            session.pdu_len,
+            &request.items
+        )?;
+        session.sequence_number = session
+            .sequence_number
+            .wrapping_add(1);
+        session.cyclic.push(CyclicJob {
+            id: session.sequence_number,
+            items: request.items,
+            interval,
+            next: Instant::now() + interval
+        });
+        Ok(items)
+    }
+
+    /// the data of a push of `items`, if it fits
+    /// the pdu
+    fn read_cyclic(
+        &self,
+        pdu_len: u16,
+        items: &[ItemRequest]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        let memory = self.memory();
+        let data = CyclicData::init(
+            items
+                .iter()
+                .map(|x| memory.read(x))
+                .collect()
+        )
+        .encode();
+        if data.len() + PUSH_OVERHEAD as usize
+            > pdu_len as usize
+        {
+            return Err(INVALID_VALUE);
+        }
+        Ok(data)
+    }
+
+    /// the pushes of the cyclic reads due
+    fn push(
+        &self,
+        session: &mut Session
+    ) -> Vec<Frame> {
+        let now = Instant::now();
+        let mut frames = Vec::new();
+        for job in &mut session.cyclic {
+            if job.next > now {
+                continue;
+            }
+            job.next = now + job.interval;
+            let Ok(data) = self.read_cyclic(
+                session.pdu_len,
+                &job.items
+            ) else {
+                continue;
+            };
+            frames.push(
+                Frame::user_data(0)
+                    .push(
+                        FunctionGroup::CyclicData,
+                        CYCLIC_TRANSFER
+                    )
+                    .sequence_number(job.id)
+                    .data_unit(DataUnit {
+                        reference:  0,
+                        last:       true,
+                        error_code: 0
+                    })
+                    .data(UserDataItem::init(
+                        ReturnCode::Success,
+                        &data
+                    ))
+                    .build()
+            );
+        }
+        frames
+    }
+
+    /// subscribe or unsubscribe the alarms of a
+    /// type, answered with the subscription
+    fn message_service(
+        &self,
+        session: &mut Session,
+        data: &[u8]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        let service =
+            MessageService::decode(data)
+                .map_err(|_| INVALID_VALUE)?;
+        match service.alarms {
+            Some((
+                AlarmType::NotSupport(_),
+                _
+            )) => {
+                return Err(INVALID_VALUE);
+            },
+            Some((alarm_type, true)) => {
+                session.alarms = Some(alarm_type)
+            },
+            Some((_, false)) => {
+                session.alarms = None
+            },
+            None => {}
+        }
+        Ok(data.to_vec())
+    }
+
+    /// acknowledge the signals of alarms, each
+    /// acknowledgement notified to the
+    /// connections subscribed to its type
+    fn alarm_ack(
+        &self,
+        data: &[u8]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        let acks = AlarmAck::decode_list(data)
+            .map_err(|_| INVALID_VALUE)?;
+        let mut memory = self.memory();
+        let timestamp = memory.clock;
+        let mut return_codes = Vec::new();
+        for ack in acks {
+            let Some((alarm_type, message)) =
+                memory
+                    .alarms
+                    .get_mut(&ack.event_id)
+            else {
+                return_codes
+                    .push(ReturnCode::Err);
+                continue;
+            };
+            message.ack_state_going |=
+                ack.ack_state_going;
+            message.ack_state_coming |=
+                ack.ack_state_coming;
+            return_codes
+                .push(ReturnCode::Success);
+            let _ = self.alarms.send(AlarmPush {
+                alarm_type:   *alarm_type,
+                kind:
+                    AlarmKind::AckIndication,
+                notification: AlarmNotification {
+                    timestamp,
+                    messages: vec![
+                        message.clone(),
+                    ]
+                }
+            });
+        }
+        Ok(AlarmAck::encode_response(
+            &return_codes
+        ))
+    }
+
+    /// the raised alarms of a type
+    fn alarm_query(
+        &self,
+        session: &mut Session,
+        data: &[u8]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        let alarm_type = decode_alarm_query(data)
+            .map_err(|_| INVALID_VALUE)?;
+        let memory = self.memory();
+        let notification = AlarmNotification {
+            timestamp: memory.clock,
+            messages:  memory
+                .alarms
+                .values()
+                .filter(|(x, message)| {
+                    *x == alarm_type
+                        && message.state != 0
+                })
+                .map(|(_, message)| {
+                    message.clone()
+                })
+                .collect()
+        };
+        drop(memory);
+        notification
+            .encode()
+            .map(|x| session.fragment(x))
+            .map_err(|_| INVALID_VALUE)
+    }
+
+    /// start the plc, copy ram to rom or compress
+    /// the memory. A refused job returns its
+    /// state.
+    fn pi_service(
+        &self,
+        job: &PiServiceJob
+    ) -> std::result::Result<(), Option<u8>> {
+        let mut memory = self.memory();
+        match std::str::from_utf8(job.service())
+            .unwrap_or_default()
+        {
+            P_PROGRAM if memory.running => {
+                Err(Some(ALREADY_RUN))
+            },
+            P_PROGRAM => {
+                memory.running = true;
+                Ok(())
+            },
+            // copy ram to rom and compress are
+            // accepted in stop
+            MODU | GARB if !memory.running => {
+                Ok(())
+            },
+            DELETE_BLOCK => {
+                BlockFile::decode_pi_parameter(
+                    job.parameter()
+                )
+                .ok()
+                .filter(|x| {
+                    memory.delete_block(
+                        x.block_type,
+                        x.number
+                    )
+                })
+                .map(|_| ())
+                .ok_or(None)
+            },
+            INSERT_BLOCK => {
+                BlockFile::decode_pi_parameter(
+                    job.parameter()
+                )
+                .ok()
+                .filter(|x| {
+                    memory.insert_block(
+                        x.block_type,
+                        x.number
+                    )
+                })
+                .map(|_| ())
+                .ok_or(None)
+            },
+            _ => Err(None)
+        }
+    }
+
+    /// the ack data of a download block or
+    /// download ended job, answered by the
+    /// request of the next part or the end of
+    /// the download. The ended download is kept
+    /// in the passive file system.
+    fn download_ack(
+        &self,
+        session: &mut Session,
+        header: HearderAckData,
+        ack_data: AckData
+    ) -> Option<Frame> {
+        if header.error_class() != 0
+            || header.error_code() != 0
+        {
+            debug!(
+                "download aborted: {:?}",
+                header
+            );
+            session.download = None;
+            return None;
+        }
+        match ack_data {
+            AckData::DownloadBlock(data) => {
+                let download =
+                    session.download.as_mut()?;
+                download.more = data.more();
+                download.data.extend(data.data());
+                session.download_job()
+            },
+            AckData::DownloadEnded => {
+                let Download {
+                    file, data, ..
+                } = session.download.take()?;
+                self.memory()
+                    .passive_blocks
+                    .insert(
+                        (
+                            file.block_type,
+                            file.number
+                        ),
+                        data
+                    );
+                None
+            },
+            ack_data => {
+                debug!("ignore {:?}", ack_data);
+                None
+            }
+        }
+    }
+
+    fn plc_stop(
+        &self,
+        job: &PlcStopJob
+    ) -> std::result::Result<(), Option<u8>> {
+        let mut memory = self.memory();
+        match std::str::from_utf8(job.service())
+            .unwrap_or_default()
+        {
+            P_PROGRAM if memory.running => {
+                memory.running = false;
+                Ok(())
+            },
+            P_PROGRAM => Err(Some(ALREADY_STOP)),
+            _ => Err(None)
+        }
+    }
+
+    /// open an upload session of a block, split
+    /// into parts fitting the pdu
+    fn start_upload(
+        &self,
+        session: &mut Session,
+        job: &StartUploadJob
+    ) -> std::result::Result<
+        StartUploadAckData,
+        u16
+    > {
+        let BlockFile {
+            block_type, number, ..
+        } = job.file();
+        let memory = self.memory();
+        let (_, body) = memory
+            .blocks
+            .get(&(block_type, number))
+            .ok_or(BLOCK_NOT_FOUND)?;
+        let capacity = session
+            .pdu_len
+            .saturating_sub(UPLOAD_OVERHEAD)
+            .max(1)
+            as usize;
+        session.upload_id =
+            session.upload_id.wrapping_add(1);
+        session.upload = Some((
+            session.upload_id,
+            body.chunks(capacity)
+                .map(|x| x.to_vec())
+                .collect()
+        ));
+        Ok(StartUploadAckData::init(
+            session.upload_id,
+            body.len() as u32
+        ))
+    }
+
+    /// answer a userdata request, the data of a
+    /// response exceeding the pdu split into
+    /// fragments
+    fn user_data(
+        &self,
+        session: &mut Session,
+        header: Header,
+        parameter: UserDataParameter,
+        data: Option<UserDataItem>
+    ) -> Frame {
+        let data = data
+            .map(|x| x.data)
+            .unwrap_or_default();
+        let rs = match (
+            parameter.method,
+            parameter.function_group,
+            parameter.subfunction
+        ) {
+            // the request of the next fragment
+            (Method::Response, ..) => session
+                .fragments
+                .pop_front()
+                .ok_or(FUNCTION_NOT_AVAILABLE),
+            (
+                Method::Request,
+                FunctionGroup::Cpu,
+                READ_SZL
+            ) if data.len() >= 4 => self
+                .memory()
+                .read_szl(
+                    u16::from_be_bytes([
+                        data[0], data[1]
+                    ]),
+                    u16::from_be_bytes([
+                        data[2], data[3]
+                    ])
+                )
+                .map(|x| session.fragment(x))
+                .ok_or(SZL_NOT_AVAILABLE),
+            (
+                Method::Request,
+                FunctionGroup::Block,
+                LIST_BLOCKS
+            ) => Ok(session.fragment(
+                BlockCount::encode_list(
+                    &self.memory().count_blocks()
+                )
+            )),
+            (
+                Method::Request,
+                FunctionGroup::Block,
+                LIST_BLOCKS_OF_TYPE
+            ) if data.len() >= 2 => Ok(session
+                .fragment(
+                    BlockListEntry::encode_list(
+                        &self
+                            .memory()
+                            .list_blocks(
+                                BlockType::from(
+                                    data[1]
+                                )
+                            )
+                    )
+                )),
+            (
+                Method::Request,
+                FunctionGroup::Block,
+                GET_BLOCK_INFO
+            ) => BlockFile::decode_name(&data)
+                .ok()
+                .and_then(|x| {
+                    self.memory()
+                        .blocks
+                        .get(&(
+                            x.block_type,
+                            x.number
+                        ))
+                        .map(|(info, _)| {
+                            info.clone().encode()
+                        })
+                })
+                .ok_or(BLOCK_NOT_FOUND),
+            (
+                Method::Request,
+                FunctionGroup::Time,
+                READ_CLOCK
+            ) => self
+                .memory()
+                .clock
+                .encode_clock()
+                .map(|x| x.to_vec())
+                .map_err(|_| INVALID_VALUE),
+            (
+                Method::Request,
+                FunctionGroup::Time,
+                SET_CLOCK
+            ) => match DateAndTime::decode_clock(
+                &data
+            ) {
+                Ok(clock) => {
+                    self.memory().clock = clock;
+                    Ok(Vec::new())
+                },
+                Err(_) => Err(INVALID_VALUE)
+            },
+            (
+                Method::Request,
+                FunctionGroup::Security,
+                subfunction
+            ) => self.security(
+                session,
+                subfunction,
+                &data
+            ),
+            (
+                Method::Request,
+                FunctionGroup::CyclicData,
+                CYCLIC_TRANSFER
+            ) => self.cyclic_read(session, &data),
+            (
+                Method::Request,
+                FunctionGroup::CyclicData,
+                CYCLIC_UNSUBSCRIBE
+            ) => session.unsubscribe(&data),
+            (
+                Method::Request,
+                FunctionGroup::Cpu,
+                MESSAGE_SERVICE
+            ) => self
+                .message_service(session, &data),
+            (
+                Method::Request,
+                FunctionGroup::Cpu,
+                ALARM_ACK
+            ) => self.alarm_ack(&data),
+            (
+                Method::Request,
+                FunctionGroup::Cpu,
+                ALARM_QUERY
+            ) => self.alarm_query(session, &data),
+            _ => Err(FUNCTION_NOT_AVAILABLE)
+        };
+        let builder =
+            Frame::user_data(header.pdu_ref)
+                .response(
+                    parameter.function_group,
+                    parameter.subfunction
+                )
+                .sequence_number(
+                    session.sequence_number
+                );
+        match rs {
+            Ok(fragment)
+                if fragment.is_empty() =>
+            {
+                builder
+                    .data(UserDataItem::no_data())
+                    .build()
+            },
+            Ok(fragment) => builder
+                .data_unit(DataUnit {
+                    reference:  0,
+                    last:       session
+                        .fragments
+                        .is_empty(),
+                    error_code: 0
+                })
+                .data(UserDataItem::init(
+                    ReturnCode::Success,
+                    &fragment
+                ))
+                .build(),
+            Err(error_code) => builder
+                .data_unit(DataUnit {
+                    reference: 0,
+                    last: true,
+                    error_code
+                })
+                .data(UserDataItem::no_data())
+                .build()
+        }
+    }
+}
+
+impl Session {
+    /// the push of an alarm notification, if the
+    /// alarms of its type are subscribed and it
+    /// fits the pdu
+    fn push_alarm(
+        &self,
+        alarm: AlarmPush
+    ) -> Option<Frame> {
+        if self.alarms != Some(alarm.alarm_type) {
+            return None;
+        }
+        let data =
+            alarm.notification.encode().ok()?;
+        if data.len()
+            + USER_DATA_OVERHEAD as usize
+            > self.pdu_len as usize
+        {
+            warn!(
+                "alarm notification exceeds the \
+                 pdu: {}",
+                data.len()
+            );
+            return None;
+        }
+        Some(
+            Frame::user_data(0)
+                .push(
+                    FunctionGroup::Cpu,
+                    alarm.kind.into()
+                )
+                .data_unit(DataUnit {
+                    reference:  0,
+                    last:       true,
+                    error_code: 0
+                })
+                .data(UserDataItem::init(
+                    ReturnCode::Success,
+                    &data
+                ))
+                .build()
+        )
+    }
+
+    /// cancel a cyclic read
+    fn unsubscribe(
+        &mut self,
+        data: &[u8]
+    ) -> std::result::Result<Vec<u8>, u16> {
+        let id = decode_unsubscribe(data)
+            .map_err(|_| INVALID_VALUE)?;
+        let len = self.cyclic.len();
+        self.cyclic.retain(|x| x.id != id);
+        if self.cyclic.len() == len {
+            return Err(INVALID_VALUE);
+        }
+        Ok(Vec::new())
+    }
+
+    /// start a download of a block to the
+    /// passive file system
+    fn request_download(
+        &mut self,
+        job: &RequestDownloadJob
+    ) -> std::result::Result<(), u16> {
+        let file = job.file();
+        if matches!(
+            file.block_type,
+            BlockType::SFC
+                | BlockType::SFB
+                | BlockType::NotSupport(_)
+        ) {
+            return Err(INVALID_BLOCK_TYPE);
+        }
+        self.download = Some(Download {
+            file,
+            data: Vec::with_capacity(
+                job.load_len() as usize
+            ),
+            more: true
+        });
+        Ok(())
+    }
+
+    /// the job requesting the next part of the
+    /// download, or ending it
+    fn download_job(&mut self) -> Option<Frame> {
+        let Download { file, more, .. } =
+            self.download.as_ref()?;
+        self.job_ref =
+            self.job_ref.wrapping_add(1);
+        let builder =
+            Frame::job_download(self.job_ref);
+        Some(if *more {
+            builder
+                .download_block(0, *file)
+                .build()
+        } else {
+            builder
+                .download_ended(0, *file)
+                .build()
+        })
+    }
+
+    /// the next part of the block uploaded by
+    /// the session
+    fn upload(
+        &mut self,
+        job: &UploadJob
+    ) -> std::result::Result<UploadAckData, u16>
+    {
+        match &mut self.upload {
+            Some((id, parts))
+                if *id == job.session_id() =>
+            {
+                let part = parts
+                    .pop_front()
+                    .unwrap_or_default();
+                Ok(UploadAckData::init(
+                    !parts.is_empty(),
+                    &part
+                ))
+            },
+            _ => Err(UPLOAD_SEQUENCE_ERROR)
+        }
+    }
+
+    fn end_upload(
+        &mut self,
+        job: &UploadJob
+    ) -> std::result::Result<(), u16> {
+        match self.upload.take() {
+            Some((id, _))
+                if id == job.session_id() =>
+            {
+                Ok(())
+            },
+            _ => Err(UPLOAD_SEQUENCE_ERROR)
+        }
+    }
+
+    /// split `data` into fragments fitting the
+    /// pdu, returning the first one
+    fn fragment(
+        &mut self,
+        mut data: Vec<u8>
+    ) -> Vec<u8> {
+        let capacity = self
+            .pdu_len
+            .saturating_sub(USER_DATA_OVERHEAD)
+            .max(1)
+            as usize;
+        self.fragments.clear();
+        if data.len() > capacity {
+            self.sequence_number = self
+                .sequence_number
+                .wrapping_add(1);
+            let rest = data.split_off(capacity);
+            self.fragments.extend(
+                rest.chunks(capacity)
+                    .map(|x| x.to_vec())
+            );
+        }
+        data
+    }
+}
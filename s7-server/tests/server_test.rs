@@ -0,0 +1,879 @@
+use bytes::BytesMut;
+use copt::{CoptDecoder, CoptEncoder, CoptFrame, PduType, TpduSize};
+use s7_client::{
+    Area, BitAddr, Confirmation, ConnectMode, ConnectionState, ConnectionType, CpuMode,
+    DataSizeType, DataType, Error, ErrorCode, ItemError, Options, Poller, Polling, Quality,
+    S7Client, TagError, TagEvent, TagTable, Value,
+};
+use s7_comm::{
+    AlarmAck, AlarmKind, AlarmMessage, AlarmType, BlockHeader, BlockInfo, BlockTimestamp,
+    BlockType, DataItemVal, DateAndTime, Frame, ReturnCode, S7CommDecoder, S7CommEncoder,
+    mc7_code,
+};
+use s7_server::{Memory, S7Server, ServerOptions};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktDecoder, TpktEncoder, TpktFrame};
+
+async fn start(memory: Memory) -> (S7Server, S7Client) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = S7Server::new(ServerOptions::default(), memory);
+    let serving = server.clone();
+    tokio::spawn(async move { serving.serve(listener).await });
+    let options = Options::new(
+        addr.ip(),
+        addr.port(),
+        ConnectMode::init_rack_slot(ConnectionType::PG, 0, 1),
+    );
+    let client = S7Client::connect(options).await.unwrap();
+    (server, client)
+}
+
+fn db_bytes(db: u16, addr: u16, len: u16) -> Area {
+    Area::DataBausteine(db, DataSizeType::Byte { addr, len })
+}
+
+#[tokio::test]
+async fn negotiate() {
+    let (_, client) = start(Memory::default()).await;
+    let options = client.options();
+    assert_eq!(options.pdu_len(), 480);
+    assert_eq!(options.max_amq_calling, 8);
+    let cotp = options.cotp().unwrap();
+    assert_eq!(cotp.tpdu_size, TpduSize::L1024);
+    assert_eq!(cotp.local_ref, [0, 1]);
+    assert_eq!(cotp.remote_ref, [0, 1]);
+    assert_eq!(cotp.remote_tsap, options.conn_mode.remote_tsap().to_vec());
+    assert!(cotp.parameters.is_empty());
+}
+
+#[tokio::test]
+async fn read_write_db() {
+    let (server, client) = start(Memory::default().with_db(1, 1024)).await;
+    let data: Vec<u8> = (0..1000).map(|x| x as u8).collect();
+    let resp = client
+        .write_vec(&[(db_bytes(1, 10, 1000), data.as_slice())])
+        .await
+        .unwrap();
+    assert!(resp[0].return_code.is_ok());
+    assert_eq!(&server.memory().dbs[&1][10..1010], data.as_slice());
+
+    let item = client.read(&db_bytes(1, 10, 1000)).await.unwrap();
+    assert!(item.return_code.is_ok());
+    assert_eq!(item.data, data);
+
+    let area = Area::DataBausteine(
+        1,
+        DataSizeType::Bit {
+            addr: 1020,
+            bit_addr: BitAddr::Addr3,
+        },
+    );
+    client.write_value(&area, true).await.unwrap();
+    assert_eq!(server.memory().dbs[&1][1020], 0b1000);
+    assert!(client.read_value::<bool>(&area).await.unwrap());
+
+    client
+        .write_value(&db_bytes(1, 0, 4), 1.5f32)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.read_value::<f32>(&db_bytes(1, 0, 4)).await.unwrap(),
+        1.5
+    );
+}
+
+#[tokio::test]
+async fn segmented_tpdus() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = S7Server::new(ServerOptions::default(), Memory::default().with_db(1, 1024));
+    let serving = server.clone();
+    tokio::spawn(async move { serving.serve(listener).await });
+    let mut options = Options::new(
+        addr.ip(),
+        addr.port(),
+        ConnectMode::init_rack_slot(ConnectionType::PG, 0, 1),
+    );
+    options.tpdu_size = TpduSize::L256;
+    let client = S7Client::connect(options).await.unwrap();
+    assert_eq!(client.options().tpdu_size, TpduSize::L256);
+
+    // jobs and acks longer than a tpdu
+    let data: Vec<u8> = (0..400).map(|x| (x * 7) as u8).collect();
+    let resp = client
+        .write_vec(&[(db_bytes(1, 0, 400), data.as_slice())])
+        .await
+        .unwrap();
+    assert!(resp[0].return_code.is_ok());
+    assert_eq!(&server.memory().dbs[&1][..400], data.as_slice());
+    let item = client.read(&db_bytes(1, 0, 400)).await.unwrap();
+    assert_eq!(item.data, data);
+}
+
+async fn send_job(stream: &mut TcpStream, frame: Frame) {
+    let mut dst = BytesMut::new();
+    TpktEncoder(CoptEncoder(S7CommEncoder))
+        .encode(
+            TpktFrame::new(CoptFrame::builder_of_dt_data(frame).build(0, true)),
+            &mut dst,
+        )
+        .unwrap();
+    stream.write_all(&dst).await.unwrap();
+}
+
+async fn recv_frame(stream: &mut TcpStream) -> CoptFrame<Frame> {
+    let mut decoder = TpktDecoder(CoptDecoder::new(S7CommDecoder));
+    let mut src = BytesMut::new();
+    loop {
+        if let Some(frame) = decoder.decode(&mut src).unwrap() {
+            return frame.payload();
+        }
+        assert!(stream.read_buf(&mut src).await.unwrap() > 0);
+    }
+}
+
+async fn recv_ack(stream: &mut TcpStream) -> Frame {
+    let PduType::DtData(comm) = recv_frame(stream).await.pdu_type else {
+        panic!("should recv dt data");
+    };
+    comm.payload()
+}
+
+#[tokio::test]
+async fn oversized_read_ack() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = S7Server::new(ServerOptions::default(), Memory::default().with_db(1, 1024));
+    tokio::spawn(async move { server.serve(listener).await });
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    // connect request to rack 0 slot 1, tpdu size 1024
+    stream
+        .write_all(&[
+            0x03, 0x00, 0x00, 0x16, 0x11, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc0, 0x01, 0x0a,
+            0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x01, 0x01,
+        ])
+        .await
+        .unwrap();
+    assert!(matches!(
+        recv_frame(&mut stream).await.pdu_type,
+        PduType::ConnectConfirm(_)
+    ));
+    send_job(
+        &mut stream,
+        Frame::job_setup(0)
+            .max_amq_calling(1)
+            .max_amq_called(1)
+            .pdu_length(240)
+            .build(),
+    )
+    .await;
+    recv_ack(&mut stream).await;
+
+    // the ack of two items of 200 bytes exceeds the pdu
+    let read = |first, second| {
+        Frame::job_read_var(1)
+            .read_bytes(Some(1), s7_comm::Area::DataBlocks, 0, first)
+            .read_bytes(Some(1), s7_comm::Area::DataBlocks, 200, second)
+            .build()
+    };
+    send_job(&mut stream, read(200, 200)).await;
+    let Frame::AckData { header, .. } = recv_ack(&mut stream).await else {
+        panic!("should recv ack data");
+    };
+    assert_eq!((header.error_class(), header.error_code()), (0x85, 0x00));
+    // exactly the pdu
+    send_job(&mut stream, read(110, 108)).await;
+    let Frame::AckData { header, .. } = recv_ack(&mut stream).await else {
+        panic!("should recv ack data");
+    };
+    assert_eq!((header.error_class(), header.error_code()), (0, 0));
+}
+
+#[tokio::test]
+async fn disconnect() {
+    let (_, client) = start(Memory::default().with_db(1, 16)).await;
+    let other = client.clone();
+    client.disconnect().await.unwrap();
+    let mut state = other.subscribe_state();
+    state
+        .wait_for(|x| *x == ConnectionState::Closed)
+        .await
+        .unwrap();
+    assert!(matches!(
+        other.read(&db_bytes(1, 0, 1)).await,
+        Err(Error::ConnectionClosed(_))
+    ));
+    // already released
+    other.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn read_areas() {
+    let mut memory = Memory::default();
+    memory.inputs[2] = 0x12;
+    memory.outputs[3] = 0x34;
+    memory.merkers[4] = 0x56;
+    // timer 5 and counter 6
+    memory.timers[10..12].copy_from_slice(&[0x11, 0x22]);
+    memory.counters[12..14].copy_from_slice(&[0x33, 0x44]);
+    let (_, client) = start(memory).await;
+    let areas = [
+        "IB2".parse::<Area>().unwrap(),
+        "QB3".parse().unwrap(),
+        "MB4".parse().unwrap(),
+        "T5".parse().unwrap(),
+        "C6".parse().unwrap(),
+    ];
+    let items = client.read_vec(&areas).await.unwrap();
+    let data: Vec<Vec<u8>> = items.into_iter().map(|x| x.data.to_vec()).collect();
+    assert_eq!(
+        data,
+        vec![
+            vec![0x12],
+            vec![0x34],
+            vec![0x56],
+            vec![0x11, 0x22],
+            vec![0x33, 0x44],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn return_codes() {
+    let (_, client) = start(Memory::default().with_db(1, 16)).await;
+    let items = client
+        .read_vec(&[db_bytes(2, 0, 1), db_bytes(1, 15, 2), db_bytes(1, 15, 1)])
+        .await
+        .unwrap();
+    assert_eq!(items[0].return_code, ReturnCode::Err);
+    assert_eq!(items[1].return_code, ReturnCode::InvalidAddress);
+    assert_eq!(items[2].return_code, ReturnCode::Success);
+
+    let resp = client
+        .write_vec(&[(db_bytes(2, 0, 1), &[1]), (db_bytes(1, 16, 1), &[1])])
+        .await
+        .unwrap();
+    assert_eq!(resp[0].return_code, ReturnCode::Err);
+    assert_eq!(resp[1].return_code, ReturnCode::InvalidAddress);
+
+    let err = client
+        .read_value::<u8>(&db_bytes(2, 0, 1))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Item {
+            index: 0,
+            area: Area::DataBausteine(2, _),
+            return_code: ReturnCode::Err,
+        }
+    ));
+    // item not available
+    assert_eq!(err.code(), 0x00C00000);
+}
+
+#[tokio::test]
+async fn partial_results() {
+    let (_, client) = start(Memory::default().with_db(1, 16)).await;
+    let results = client
+        .write_values(&[
+            (db_bytes(1, 0, 0), DataType::Int, Value::Int(-2)),
+            (db_bytes(2, 0, 0), DataType::Int, Value::Int(1)),
+            (db_bytes(1, 2, 0), DataType::Int, Value::Real(1.0)),
+            (db_bytes(1, 14, 0), DataType::Real, Value::Real(1.0)),
+            (db_bytes(1, 4, 0), DataType::Real, Value::Real(1.5)),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(results[0], Ok(()));
+    assert_eq!(results[1], Err(ItemError::ReturnCode(ReturnCode::Err)));
+    assert!(matches!(results[2], Err(ItemError::InvalidValue(_))));
+    assert_eq!(
+        results[3],
+        Err(ItemError::ReturnCode(ReturnCode::InvalidAddress))
+    );
+    assert_eq!(results[4], Ok(()));
+
+    let results = client
+        .read_values(&[
+            (db_bytes(1, 0, 0), DataType::Int),
+            (db_bytes(1, 0, 0), DataType::Bool),
+            (db_bytes(2, 0, 0), DataType::Int),
+            (db_bytes(1, 4, 0), DataType::Real),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(results[0], Ok(Value::Int(-2)));
+    assert!(matches!(results[1], Err(ItemError::InvalidInput(_))));
+    assert_eq!(results[2], Err(ItemError::ReturnCode(ReturnCode::Err)));
+    assert_eq!(results[3], Ok(Value::Real(1.5)));
+
+    let results = client
+        .read_areas(&[db_bytes(1, 0, 2), db_bytes(1, 15, 2)])
+        .await
+        .unwrap();
+    assert_eq!(results[0], Ok(vec![0xff, 0xfe]));
+    assert_eq!(
+        results[1],
+        Err(ItemError::ReturnCode(ReturnCode::InvalidAddress))
+    );
+    let results = client
+        .write_areas(&[(db_bytes(1, 0, 2), &[0, 1]), (db_bytes(1, 2, 2), &[0])])
+        .await
+        .unwrap();
+    assert_eq!(results[0], Ok(()));
+    assert!(matches!(results[1], Err(ItemError::InvalidInput(_))));
+}
+
+#[tokio::test]
+async fn read_tags() {
+    let (_, client) = start(Memory::default().with_db(1, 16)).await;
+    client
+        .write_areas(&[(
+            db_bytes(1, 0, 8),
+            &[0x00, 0x64, 0x02, 0x00, 0x3f, 0xc0, 0x00, 0x00],
+        )])
+        .await
+        .unwrap();
+    let table = TagTable::from_toml(
+        r#"
+        [[tags]]
+        name = "Motor1.Speed"
+        address = "DB1.DBW0"
+        type = "Int"
+        scaling = { gain = 0.5, offset = 1.0 }
+
+        [[tags]]
+        name = "Motor1.Running"
+        address = "DB1.DBX2.1"
+        type = "Bool"
+
+        [[tags]]
+        name = "Motor1.Current"
+        address = "DB1.DBD4"
+        type = "Real"
+
+        [[tags]]
+        name = "Motor2.Speed"
+        address = "DB2.DBW0"
+        type = "Int"
+        "#,
+    )
+    .unwrap();
+    let values = client
+        .read_tags(
+            &table,
+            &[
+                "Motor1.Speed",
+                "Motor1.Running",
+                "Motor1.Current",
+                "Motor2.Speed",
+                "Pump",
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(values.len(), 5);
+    assert_eq!(values["Motor1.Speed"], Ok(Value::LReal(51.0)));
+    assert_eq!(values["Motor1.Running"], Ok(Value::Bool(true)));
+    assert_eq!(values["Motor1.Current"], Ok(Value::Real(1.5)));
+    assert_eq!(
+        values["Motor2.Speed"],
+        Err(ItemError::ReturnCode(ReturnCode::Err))
+    );
+    assert!(matches!(values["Pump"], Err(ItemError::InvalidInput(_))));
+}
+
+async fn next_event(polling: &mut Polling) -> TagEvent {
+    tokio::time::timeout(Duration::from_secs(2), polling.recv())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn poll_tags() {
+    let (_, client) = start(Memory::default().with_db(1, 16)).await;
+    let table = TagTable::from_json(
+        r#"[
+            {"name": "Speed", "address": "DB1.DBW0", "type": "Int", "deadband": 5.0},
+            {"name": "Running", "address": "DB1.DBX2.0", "type": "Bool"},
+            {"name": "Missing", "address": "DB2.DBW0", "type": "Int"}
+        ]"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        Poller::new(client.clone(), table.clone())
+            .scan(Duration::from_millis(10), &["Speed"])
+            .unwrap()
+            .scan(Duration::from_millis(50), &["Speed"]),
+        Err(TagError::DuplicateTag(_))
+    ));
+    assert!(matches!(
+        Poller::new(client.clone(), table.clone()).scan(Duration::from_millis(10), &["Pump"]),
+        Err(TagError::UnknownTag(_))
+    ));
+    assert!(matches!(
+        Poller::new(client.clone(), table.clone()).scan(Duration::ZERO, &["Speed"]),
+        Err(TagError::InvalidInput(_))
+    ));
+    let mut polling = Poller::new(client.clone(), table)
+        .scan(Duration::from_millis(10), &["Speed", "Running"])
+        .unwrap()
+        .scan(Duration::from_millis(50), &["Missing"])
+        .unwrap()
+        .start();
+    let mut first = Vec::new();
+    for _ in 0..3 {
+        let event = next_event(&mut polling).await;
+        first.push((event.name, event.value, event.quality));
+    }
+    first.sort_by(|x, y| x.0.cmp(&y.0));
+    assert_eq!(
+        first,
+        [
+            (
+                "Missing".to_string(),
+                None,
+                Quality::Bad(ItemError::ReturnCode(ReturnCode::Err))
+            ),
+            (
+                "Running".to_string(),
+                Some(Value::Bool(false)),
+                Quality::Good
+            ),
+            ("Speed".to_string(), Some(Value::Int(0)), Quality::Good),
+        ]
+    );
+
+    // the change of speed is within its deadband
+    client
+        .write_areas(&[(db_bytes(1, 0, 3), &[0x00, 0x03, 0x01])])
+        .await
+        .unwrap();
+    let event = next_event(&mut polling).await;
+    assert_eq!(event.name, "Running");
+    assert_eq!(event.value, Some(Value::Bool(true)));
+    client
+        .write_areas(&[(db_bytes(1, 0, 2), &[0x00, 0x0a])])
+        .await
+        .unwrap();
+    let event = next_event(&mut polling).await;
+    assert_eq!(event.name, "Speed");
+    assert_eq!(event.value, Some(Value::Int(10)));
+}
+
+#[tokio::test]
+async fn cyclic_read() {
+    let (_, client) = start(Memory::default().with_db(1, 16)).await;
+    let item = |addr, len| db_bytes(1, addr, len).into();
+    assert!(matches!(
+        client
+            .subscribe_cyclic(Vec::new(), Duration::from_millis(100))
+            .await,
+        Err(Error::InvalidInput(_))
+    ));
+    let mut subscription = client
+        .subscribe_cyclic(vec![item(0, 2), item(4, 1)], Duration::from_millis(100))
+        .await
+        .unwrap();
+    let first = subscription.recv().await.unwrap().unwrap();
+    assert_eq!(
+        first,
+        [
+            DataItemVal::init_with_bytes(ReturnCode::Success, &[0, 0]),
+            DataItemVal::init_with_bytes(ReturnCode::Success, &[0]),
+        ]
+    );
+    // jobs answered between the pushes
+    client
+        .write_areas(&[(db_bytes(1, 0, 2), &[0x12, 0x34])])
+        .await
+        .unwrap();
+    assert_eq!(
+        client.read_areas(&[db_bytes(1, 0, 2)]).await.unwrap()[0],
+        Ok(vec![0x12, 0x34])
+    );
+    // a push may have been read before the write
+    let pushed = async {
+        while let Some(push) = subscription.recv().await {
+            if push.unwrap()[0].data == [0x12, 0x34][..] {
+                return;
+            }
+        }
+        unreachable!()
+    };
+    tokio::time::timeout(Duration::from_secs(2), pushed)
+        .await
+        .unwrap();
+    subscription.unsubscribe().await.unwrap();
+}
+
+fn alarm(event_id: u32, state: u8) -> AlarmMessage {
+    AlarmMessage {
+        event_id,
+        state,
+        ack_state_going: 0x00,
+        ack_state_coming: 0x00,
+        values: vec![DataItemVal::init_with_bytes(
+            ReturnCode::Success,
+            &[0x00, 0x2a],
+        )],
+    }
+}
+
+#[tokio::test]
+async fn alarms() {
+    let (server, client) = start(Memory::default()).await;
+    server.notify_alarm(AlarmType::AlarmS, alarm(1, 0x01));
+    let mut subscription = client.subscribe_alarms(AlarmType::AlarmS).await.unwrap();
+    // not subscribed
+    server.notify_alarm(AlarmType::Alarm8, alarm(2, 0x01));
+    server.notify_alarm(AlarmType::AlarmS, alarm(3, 0x01));
+    let event = subscription.recv().await.unwrap().unwrap();
+    assert_eq!(event.kind, AlarmKind::AlarmS);
+    assert_eq!(event.timestamp, DateAndTime::default());
+    assert_eq!(event.messages, [alarm(3, 0x01)]);
+
+    let active = client.query_alarms(AlarmType::AlarmS).await.unwrap();
+    assert_eq!(active.messages, [alarm(1, 0x01), alarm(3, 0x01)]);
+
+    let ack = |event_id| AlarmAck {
+        event_id,
+        ack_state_going: 0x00,
+        ack_state_coming: 0x01,
+    };
+    let results = client.ack_alarms(&[ack(3), ack(9)]).await.unwrap();
+    assert_eq!(
+        results,
+        [Ok(()), Err(ItemError::ReturnCode(ReturnCode::Err))]
+    );
+    let event = subscription.recv().await.unwrap().unwrap();
+    assert_eq!(event.kind, AlarmKind::AckIndication);
+    assert_eq!(event.messages[0].ack_state_coming, 0x01);
+    assert!(matches!(
+        client.ack_alarms(&[]).await,
+        Err(Error::InvalidInput(_))
+    ));
+
+    // cleared, no more active
+    server.notify_alarm(AlarmType::AlarmS, alarm(3, 0x00));
+    assert_eq!(
+        subscription.recv().await.unwrap().unwrap().messages,
+        [alarm(3, 0x00)]
+    );
+    let active = client.query_alarms(AlarmType::AlarmS).await.unwrap();
+    assert_eq!(active.messages, [alarm(1, 0x01)]);
+    subscription.unsubscribe().await.unwrap();
+}
+
+#[tokio::test]
+async fn replaced_alarm_subscription() {
+    let (server, client) = start(Memory::default()).await;
+    let first = client.subscribe_alarms(AlarmType::AlarmS).await.unwrap();
+    let second = client.subscribe_alarms(AlarmType::AlarmS).await.unwrap();
+    let mut third = client.subscribe_alarms(AlarmType::AlarmS).await.unwrap();
+    // the replaced subscriptions no longer end the alarms
+    first.unsubscribe().await.unwrap();
+    drop(second);
+    client.query_alarms(AlarmType::AlarmS).await.unwrap();
+    server.notify_alarm(AlarmType::AlarmS, alarm(1, 0x01));
+    let event = tokio::time::timeout(Duration::from_secs(1), third.recv())
+        .await
+        .expect("the alarms should not be ended");
+    assert_eq!(event.unwrap().unwrap().messages, [alarm(1, 0x01)]);
+    third.unsubscribe().await.unwrap();
+}
+
+#[tokio::test]
+async fn read_szl() {
+    let mut module = b"\x00\x016ES7 315-2EH14-0AB0 ".to_vec();
+    module.extend_from_slice(&[0x00, 0xc0, 0x00, 0x04, 0x00, 0x01]);
+    // 100 entries of 20 bytes, fragmented in pdus of 480 bytes
+    let entries: Vec<Vec<u8>> = (0..100u8).map(|x| vec![x; 20]).collect();
+    let memory = Memory::default()
+        .with_szl(0x0011, 0, vec![module])
+        .with_szl(0x00a0, 0, entries.clone());
+    let (_, client) = start(memory).await;
+
+    let modules = client.read_module_identification().await.unwrap();
+    assert_eq!(modules[0].order_number, "6ES7 315-2EH14-0AB0");
+
+    let szl = client.read_szl(0x00a0, 0).await.unwrap();
+    assert_eq!(szl.record_len, 20);
+    assert_eq!(szl.records, entries);
+    let diagnostic = client.read_diagnostic_buffer().await.unwrap();
+    assert_eq!(diagnostic[99].event_id, 0x6363);
+    let events = client.diagnostic_buffer().await.unwrap();
+    assert_eq!(events[99].text(), "Communication event");
+    assert_eq!(events[99].timestamp, None);
+
+    let Err(Error::Protocol(code)) = client.read_szl(0x0132, 0).await else {
+        unreachable!()
+    };
+    assert_eq!(code, ErrorCode::new(0xd4, 0x01));
+    assert_eq!(code.text(), "information function unavailable");
+}
+
+#[tokio::test]
+async fn plc_control() {
+    let (server, client) = start(Memory::default()).await;
+    assert_eq!(client.cpu_status().await.unwrap(), CpuMode::Run);
+    assert!(matches!(
+        client.plc_hot_start().await,
+        Err(Error::AlreadyRun)
+    ));
+    assert!(matches!(
+        client.copy_ram_to_rom().await,
+        Err(Error::CannotCopyRamToRom)
+    ));
+
+    client.plc_stop().await.unwrap();
+    assert!(!server.memory().running);
+    assert_eq!(client.cpu_status().await.unwrap(), CpuMode::Stop);
+    assert!(matches!(client.plc_stop().await, Err(Error::AlreadyStop)));
+    client.copy_ram_to_rom().await.unwrap();
+    client.compress().await.unwrap();
+
+    client.plc_cold_start().await.unwrap();
+    assert_eq!(client.cpu_status().await.unwrap(), CpuMode::Run);
+}
+
+#[tokio::test]
+async fn plc_clock() {
+    let (server, client) = start(Memory::default()).await;
+    assert_eq!(client.get_plc_time().await.unwrap(), DateAndTime::default());
+    let time = DateAndTime::new(2024, 2, 29, 23, 59, 59, 999).unwrap();
+    client.set_plc_time(&time).await.unwrap();
+    assert_eq!(server.memory().clock, time);
+    assert_eq!(client.get_plc_time().await.unwrap(), time);
+
+    let invalid = DateAndTime { month: 13, ..time };
+    assert!(matches!(
+        client.set_plc_time(&invalid).await,
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+fn block_info(block_type: BlockType, number: u16, load_size: u32) -> BlockInfo {
+    BlockInfo {
+        block_type,
+        number,
+        flags: 0x01,
+        language: 0x01,
+        sub_block_type: 0x0a,
+        load_size,
+        security: 0,
+        code_time: BlockTimestamp::default(),
+        interface_time: BlockTimestamp::default(),
+        sbb_len: 0,
+        add_len: 0,
+        local_data_len: 0,
+        mc7_size: 900,
+        author: "ME".to_string(),
+        family: "TEST".to_string(),
+        name: format!("BLOCK{}", number),
+        version: 0x01,
+        checksum: 0x1234,
+    }
+}
+
+#[tokio::test]
+async fn blocks() {
+    // a header of 36 bytes, the mc7 code and a footer, uploaded in 3 parts
+    let mut body = vec![0; 36];
+    body[34..36].copy_from_slice(&900u16.to_be_bytes());
+    body.extend((0..900).map(|x| x as u8));
+    body.extend_from_slice(&[0; 64]);
+    let memory = Memory::default()
+        .with_block(block_info(BlockType::DB, 1, 1000), body.clone())
+        .with_block(block_info(BlockType::DB, 7, 100), vec![0; 100])
+        .with_block(block_info(BlockType::OB, 1, 100), vec![0; 100]);
+    let (_, client) = start(memory).await;
+
+    let counts = client.count_blocks().await.unwrap();
+    assert_eq!(counts.len(), 7);
+    assert!(
+        counts
+            .iter()
+            .any(|x| x.block_type == BlockType::DB && x.count == 2)
+    );
+    let dbs = client.list_blocks(BlockType::DB).await.unwrap();
+    assert_eq!(dbs.iter().map(|x| x.number).collect::<Vec<_>>(), [1, 7]);
+    assert!(client.list_blocks(BlockType::FB).await.unwrap().is_empty());
+    assert!(matches!(
+        client.list_blocks(BlockType::NotSupport(0x30)).await,
+        Err(Error::InvalidBlockType(_))
+    ));
+
+    let info = client.block_info(BlockType::DB, 1).await.unwrap();
+    assert_eq!(info, block_info(BlockType::DB, 1, 1000));
+    assert!(matches!(
+        client.block_info(BlockType::DB, 2).await,
+        Err(Error::InvalidBlockNumber(2))
+    ));
+
+    let block = client.upload_block(BlockType::DB, 1).await.unwrap();
+    assert_eq!(block, body);
+    assert_eq!(mc7_code(&block), Some(&body[36..936]));
+    assert!(matches!(
+        client.upload_block(BlockType::FC, 1).await,
+        Err(Error::InvalidBlockNumber(1))
+    ));
+}
+
+/// a block of `len` bytes, with its header
+fn block(block_type: BlockType, number: u16, len: usize) -> Vec<u8> {
+    let header = BlockHeader {
+        flags: 0x01,
+        language: 0x05,
+        sub_block_type: block_type.sub_block_type().unwrap(),
+        number,
+        load_size: len as u32,
+        security: 0,
+        code_time: BlockTimestamp::default(),
+        interface_time: BlockTimestamp::default(),
+        sbb_len: 0,
+        add_len: 0,
+        local_data_len: 0,
+        mc7_size: (len - 36) as u16,
+    };
+    let mut block = header.encode().to_vec();
+    block.extend((36..len).map(|x| x as u8));
+    block
+}
+
+#[tokio::test]
+async fn download_and_delete() {
+    let memory = Memory::default().with_block(
+        block_info(BlockType::DB, 1, 100),
+        block(BlockType::DB, 1, 100),
+    );
+    let (server, client) = start(memory).await;
+
+    let mut db1 = client.upload_block(BlockType::DB, 1).await.unwrap();
+    db1[40] = 0xff;
+    assert!(matches!(
+        client
+            .download_block(&db1, Confirmation::block(BlockType::DB, 2))
+            .await,
+        Err(Error::NotConfirmed(BlockType::DB, 1))
+    ));
+    assert_eq!(server.memory().blocks[&(BlockType::DB, 1)].1[40], 40);
+    client
+        .download_block(&db1, Confirmation::block(BlockType::DB, 1))
+        .await
+        .unwrap();
+    let (info, body) = server.memory().blocks[&(BlockType::DB, 1)].clone();
+    assert_eq!(body, db1);
+    assert_eq!(info.author, "ME");
+    assert_eq!(info.language, 0x05);
+
+    // a new block, downloaded in 3 parts
+    let fc2 = block(BlockType::FC, 2, 1000);
+    client
+        .download_block(&fc2, Confirmation::block(BlockType::FC, 2))
+        .await
+        .unwrap();
+    assert_eq!(client.upload_block(BlockType::FC, 2).await.unwrap(), fc2);
+    let info = client.block_info(BlockType::FC, 2).await.unwrap();
+    assert_eq!((info.load_size, info.mc7_size), (1000, 964));
+    assert!(matches!(
+        client
+            .download_block(&fc2[..999], Confirmation::block(BlockType::FC, 2))
+            .await,
+        Err(Error::InvalidBlockSize(999))
+    ));
+
+    assert!(matches!(
+        client
+            .delete_block(BlockType::FC, 2, Confirmation::block(BlockType::FC, 3))
+            .await,
+        Err(Error::NotConfirmed(BlockType::FC, 2))
+    ));
+    client
+        .delete_block(BlockType::FC, 2, Confirmation::block(BlockType::FC, 2))
+        .await
+        .unwrap();
+    assert!(client.list_blocks(BlockType::FC).await.unwrap().is_empty());
+    assert!(matches!(
+        client
+            .delete_block(BlockType::FC, 2, Confirmation::block(BlockType::FC, 2))
+            .await,
+        Err(Error::DeleteRefused)
+    ));
+    assert!(matches!(
+        client
+            .delete_block(BlockType::SFC, 1, Confirmation::block(BlockType::SFC, 1))
+            .await,
+        Err(Error::InvalidBlockType(BlockType::SFC))
+    ));
+}
+
+#[test]
+fn insert_invalid_block() {
+    let mut memory = Memory::default();
+    memory
+        .passive_blocks
+        .insert((BlockType::FC, 3), vec![0; 10]);
+    assert!(!memory.insert_block(BlockType::FC, 3));
+    assert!(memory.passive_blocks.contains_key(&(BlockType::FC, 3)));
+    assert!(!memory.blocks.contains_key(&(BlockType::FC, 3)));
+    memory
+        .passive_blocks
+        .insert((BlockType::FC, 3), block(BlockType::FC, 3, 100));
+    assert!(memory.insert_block(BlockType::FC, 3));
+    assert!(memory.passive_blocks.is_empty());
+    assert_eq!(memory.blocks[&(BlockType::FC, 3)].0.load_size, 100);
+}
+
+#[tokio::test]
+async fn session_password() {
+    let (server, client) = start(Memory::default().with_password("secret")).await;
+    assert!(matches!(client.plc_stop().await, Err(Error::NeedPassword)));
+    assert!(matches!(
+        client.set_session_password("wrong").await,
+        Err(Error::InvalidPassword)
+    ));
+    assert!(matches!(
+        client.set_session_password("too long!").await,
+        Err(Error::InvalidInput(_))
+    ));
+    client.set_session_password("secret").await.unwrap();
+    client.plc_stop().await.unwrap();
+    assert!(!server.memory().running);
+
+    client.clear_session_password().await.unwrap();
+    assert!(matches!(
+        client.plc_hot_start().await,
+        Err(Error::NeedPassword)
+    ));
+    assert!(matches!(
+        client.clear_session_password().await,
+        Err(Error::NoPasswordToSetOrClear)
+    ));
+
+    // logged in by the options, as after a reconnection
+    let mut options = client.options();
+    options.password = Some("secret".to_string());
+    let logged_in = S7Client::connect(options.clone()).await.unwrap();
+    logged_in.plc_hot_start().await.unwrap();
+    assert!(server.memory().running);
+    options.password = Some("wrong".to_string());
+    assert!(matches!(
+        S7Client::connect(options).await,
+        Err(Error::InvalidPassword)
+    ));
+
+    server.memory().password = None;
+    assert!(matches!(
+        client.set_session_password("secret").await,
+        Err(Error::NoPasswordToSetOrClear)
+    ));
+}
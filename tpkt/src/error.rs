@@ -8,6 +8,13 @@ pub enum Error {
 
     #[error("Error: {0}")]
     Error(String),
+
+    #[error("invalid tpkt version: {0}")]
+    InvalidVersion(u8),
+
+    /// the length field, which covers the header
+    #[error("invalid tpkt length: {0}")]
+    InvalidLength(usize),
 }
 
 pub trait ToTpktError {
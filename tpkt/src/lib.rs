@@ -7,6 +7,12 @@ pub use error::*;
 pub use packet::TpktFrame;
 use tokio_util::codec::{Decoder, Encoder};
 
+/// the version of rfc 1006
+const VERSION: u8 = 3;
+const HEADER_LEN: usize = 4;
+/// the header and a cotp header of 3 bytes at least
+const MIN_LEN: usize = 7;
+
 pub struct TpktEncoder<E>(pub E);
 pub struct TpktDecoder<D>(pub D);
 
@@ -19,10 +25,13 @@ where
     fn encode(&mut self, item: TpktFrame<F>, dst: &mut BytesMut) -> Result<(), Self::Error> {
         let mut payload = BytesMut::new();
         self.0.encode(item.payload, &mut payload)?;
-        let length = payload.len() as u16 + 4;
+        let length = payload.len() + HEADER_LEN;
+        if length > u16::MAX as usize {
+            return Err(Error::InvalidLength(length));
+        }
         dst.put_u8(item.version);
         dst.put_u8(0);
-        dst.put_u16(length);
+        dst.put_u16(length as u16);
         dst.extend_from_slice(payload.as_ref());
         Ok(())
     }
@@ -36,26 +45,37 @@ where
     type Error = Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if src.len() < 4 {
-            return Ok(None);
-        }
-        let (Some(index_0), Some(index_1)) = (src.get(2), src.get(3)) else {
-            unreachable!()
-        };
-        let length = u16::from_be_bytes([*index_0, *index_1]);
-        let lenght_usize = length as usize;
-        if src.len() < lenght_usize {
-            return Ok(None);
+        loop {
+            if src.len() < HEADER_LEN {
+                return Ok(None);
+            }
+            if src[0] != VERSION {
+                return Err(Error::InvalidVersion(src[0]));
+            }
+            let length = u16::from_be_bytes([src[2], src[3]]) as usize;
+            if length < MIN_LEN {
+                return Err(Error::InvalidLength(length));
+            }
+            if src.len() < length {
+                return Ok(None);
+            }
+            let mut framed_datas = src.split_to(length);
+            let version = framed_datas.get_u8();
+            let _reserved = framed_datas.get_u8();
+            let _ = framed_datas.get_u16();
+            // none for a payload kept by the inner decoder, as a segment of a
+            // larger one, which continues in the next packet
+            match self.0.decode(&mut framed_datas)? {
+                Some(payload) => return Ok(Some(TpktFrame { version, payload })),
+                None if framed_datas.is_empty() => {}
+                None => {
+                    return Err(Error::Error(format!(
+                        "incomplete payload of {} bytes",
+                        framed_datas.len()
+                    )))
+                }
+            }
         }
-        let mut framed_datas = src.split_to(lenght_usize);
-        let version = framed_datas.get_u8();
-        let _reserved = framed_datas.get_u8();
-        let _ = framed_datas.get_u16();
-        let Some(payload) = self.0.decode(&mut framed_datas)? else {
-            // maybe return none
-            return Err(Error::Error("payload decode fail!".to_string()));
-        };
-        Ok(Some(TpktFrame { version, payload }))
     }
 }
 